@@ -0,0 +1,479 @@
+use crate::{
+    compile_c::{compile_c, run_native, DEFAULT_CFLAGS},
+    interpreter::{Interpreter, RuntimeOptions},
+    interpreter_op::InterpreterOp,
+    transpile_asm::transpile_asm,
+    transpile_bf::transpile_bf,
+    transpile_c::transpile_c,
+    transpile_elf::transpile_elf,
+    transpile_llvm::transpile_llvm,
+    transpile_rust::transpile_rust,
+    transpile_wat::transpile_wat,
+};
+use anyhow::{anyhow, Result};
+
+/// Configuration shared by every backend, each one reads the parts that apply to it
+#[derive(Clone, Debug)]
+pub struct BackendConfig {
+    pub runtime: RuntimeOptions,
+    /// File written by backends that generate code instead of running it
+    pub output_filename: Option<String>,
+    /// Leave bounds checks out of generated Rust
+    pub unchecked: bool,
+    /// Flags passed to the system C compiler
+    pub cflags: String,
+    /// Arguments passed to natively compiled programs
+    pub program_args: Vec<String>,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            runtime: RuntimeOptions::default(),
+            output_filename: None,
+            unchecked: false,
+            cflags: DEFAULT_CFLAGS.to_string(),
+            program_args: Vec::new(),
+        }
+    }
+}
+
+impl BackendConfig {
+    fn output_filename(&self, backend: &str) -> Result<&str> {
+        self.output_filename.as_deref().ok_or_else(|| {
+            anyhow!(
+                "[error] The {} backend writes a file, an output filename is required",
+                backend
+            )
+        })
+    }
+}
+
+/// Consumer of an optimized op stream, either running the program or generating code for it
+pub trait Backend {
+    /// Name the backend is selected by
+    fn name(&self) -> &str;
+
+    /// One line summary shown when listing backends
+    fn description(&self) -> &str;
+
+    /// Whether `config.output_filename` is written instead of running the program
+    fn writes_output(&self) -> bool {
+        true
+    }
+
+    /// Whether tape sizes and origins other than the default are honoured
+    fn supports_custom_tape(&self) -> bool {
+        false
+    }
+
+    /// Whether out of bounds tape accesses are reported when `config.runtime.bounds_checks` is set
+    fn supports_bounds_checks(&self) -> bool {
+        false
+    }
+
+    /// Whether output is only flushed before input and on exit when `config.runtime.buffered_output` is set
+    fn supports_buffered_output(&self) -> bool {
+        false
+    }
+
+    /// Runs or emits the program, returning the exit code the process should finish with
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32>;
+}
+
+/// Backends that can be selected by name
+pub struct Backends {
+    backends: Vec<Box<dyn Backend>>,
+}
+
+impl Backends {
+    pub fn new() -> Self {
+        Self { backends: Vec::new() }
+    }
+
+    /// All backends that ship with this crate
+    pub fn builtin() -> Self {
+        let mut backends = Self::new();
+        backends.register(Box::new(InterpreterBackend));
+        backends.register(Box::new(JitBackend));
+        backends.register(Box::new(CBackend));
+        backends.register(Box::new(CCompileBackend));
+        backends.register(Box::new(CRunBackend));
+        backends.register(Box::new(RustBackend));
+        backends.register(Box::new(LlvmBackend));
+        backends.register(Box::new(WatBackend));
+        backends.register(Box::new(AsmBackend));
+        backends.register(Box::new(ElfBackend));
+        backends.register(Box::new(BfBackend));
+        backends
+    }
+
+    /// Adds a backend, replacing any existing one with the same name
+    pub fn register(&mut self, backend: Box<dyn Backend>) {
+        self.backends.retain(|existing| existing.name() != backend.name());
+        self.backends.push(backend);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Backend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.name() == name)
+            .map(|backend| backend.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Backend> {
+        self.backends.iter().map(|backend| backend.as_ref())
+    }
+
+    /// Looks up a backend and runs it after checking that it supports the configuration
+    pub fn run(&self, name: &str, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        self.check(name, config)?.run(ops, config)
+    }
+
+    /// Looks up a backend, failing if it doesn't exist or would ignore part of the configuration
+    pub fn check(&self, name: &str, config: &BackendConfig) -> Result<&dyn Backend> {
+        let Some(backend) = self.get(name) else {
+            let names: Vec<&str> = self.iter().map(|backend| backend.name()).collect();
+            return Err(anyhow!(
+                "[error] Unknown backend {}, available backends are {}",
+                name,
+                names.join(", ")
+            ));
+        };
+
+        if config.runtime.custom_tape() && !backend.supports_custom_tape() {
+            return Err(anyhow!(
+                "[error] The {} backend does not support a custom tape size or origin",
+                name
+            ));
+        }
+
+        if backend.writes_output() {
+            config.output_filename(name)?;
+        }
+
+        if config.runtime.bounds_checks && !backend.supports_bounds_checks() {
+            return Err(anyhow!("[error] The {} backend does not support bounds checks", name));
+        }
+
+        if config.runtime.buffered_output && !backend.supports_buffered_output() {
+            return Err(anyhow!("[error] The {} backend does not support buffered output", name));
+        }
+
+        Ok(backend)
+    }
+}
+
+impl Default for Backends {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+pub struct InterpreterBackend;
+
+impl Backend for InterpreterBackend {
+    fn name(&self) -> &str {
+        "interpreter"
+    }
+
+    fn description(&self) -> &str {
+        "Run the program with the interpreter"
+    }
+
+    fn writes_output(&self) -> bool {
+        false
+    }
+
+    fn supports_custom_tape(&self) -> bool {
+        true
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    fn supports_buffered_output(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        let interpreter = Interpreter::new(ops, config.runtime);
+
+        if config.runtime.bounds_checks {
+            unsafe { interpreter.interpret::<true>() }
+        } else {
+            unsafe { interpreter.interpret::<false>() }
+        }
+    }
+}
+
+pub struct JitBackend;
+
+impl Backend for JitBackend {
+    fn name(&self) -> &str {
+        "jit"
+    }
+
+    fn description(&self) -> &str {
+        "Compile to native x86-64 code and run it"
+    }
+
+    fn writes_output(&self) -> bool {
+        false
+    }
+
+    fn supports_custom_tape(&self) -> bool {
+        true
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        crate::jit::Jit::new(ops, config.runtime)?.run()
+    }
+
+    #[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+    fn run(&self, _ops: &[InterpreterOp], _config: &BackendConfig) -> Result<i32> {
+        Err(anyhow!("[error] The JIT is only supported on x86-64 Linux"))
+    }
+}
+
+pub struct CBackend;
+
+impl Backend for CBackend {
+    fn name(&self) -> &str {
+        "c"
+    }
+
+    fn description(&self) -> &str {
+        "Write the program as C source"
+    }
+
+    fn supports_custom_tape(&self) -> bool {
+        true
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    fn supports_buffered_output(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        transpile_c(ops.iter(), config.output_filename(self.name())?, config.runtime)?;
+        Ok(0)
+    }
+}
+
+pub struct CCompileBackend;
+
+impl Backend for CCompileBackend {
+    fn name(&self) -> &str {
+        "c-compile"
+    }
+
+    fn description(&self) -> &str {
+        "Build an executable from the C output with the system C compiler"
+    }
+
+    fn supports_custom_tape(&self) -> bool {
+        true
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    fn supports_buffered_output(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        compile_c(
+            ops,
+            config.output_filename(self.name())?,
+            config.runtime,
+            &config.cflags,
+        )?;
+        Ok(0)
+    }
+}
+
+pub struct CRunBackend;
+
+impl Backend for CRunBackend {
+    fn name(&self) -> &str {
+        "c-run"
+    }
+
+    fn description(&self) -> &str {
+        "Build the C output with the system C compiler and run it"
+    }
+
+    fn writes_output(&self) -> bool {
+        false
+    }
+
+    fn supports_custom_tape(&self) -> bool {
+        true
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    fn supports_buffered_output(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        run_native(ops, config.runtime, &config.cflags, &config.program_args)
+    }
+}
+
+pub struct RustBackend;
+
+impl Backend for RustBackend {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    fn description(&self) -> &str {
+        "Write the program as a standalone Rust source file"
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        if config.unchecked && config.runtime.bounds_checks {
+            return Err(anyhow!("[error] Unchecked Rust can't also have bounds checks"));
+        }
+
+        transpile_rust(
+            ops.iter(),
+            config.output_filename(self.name())?,
+            config.unchecked,
+            config.runtime.eof_behavior,
+        )?;
+        Ok(0)
+    }
+}
+
+pub struct LlvmBackend;
+
+impl Backend for LlvmBackend {
+    fn name(&self) -> &str {
+        "llvm"
+    }
+
+    fn description(&self) -> &str {
+        "Write the program as textual LLVM IR, without bounds checks, custom tapes or buffered output"
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        transpile_llvm(
+            ops.iter(),
+            config.output_filename(self.name())?,
+            config.runtime.eof_behavior,
+        )?;
+        Ok(0)
+    }
+}
+
+pub struct WatBackend;
+
+impl Backend for WatBackend {
+    fn name(&self) -> &str {
+        "wat"
+    }
+
+    fn description(&self) -> &str {
+        "Write the program as a WebAssembly text module"
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        transpile_wat(
+            ops.iter(),
+            config.output_filename(self.name())?,
+            config.runtime.eof_behavior,
+        )?;
+        Ok(0)
+    }
+}
+
+pub struct AsmBackend;
+
+impl Backend for AsmBackend {
+    fn name(&self) -> &str {
+        "asm"
+    }
+
+    fn description(&self) -> &str {
+        "Write the program as x86-64 GNU assembler source"
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        transpile_asm(
+            ops,
+            config.output_filename(self.name())?,
+            config.runtime.bounds_checks,
+            config.runtime.eof_behavior,
+        )?;
+        Ok(0)
+    }
+}
+
+pub struct ElfBackend;
+
+impl Backend for ElfBackend {
+    fn name(&self) -> &str {
+        "elf"
+    }
+
+    fn description(&self) -> &str {
+        "Write the program as a static x86-64 Linux executable"
+    }
+
+    fn supports_bounds_checks(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        transpile_elf(
+            ops,
+            config.output_filename(self.name())?,
+            config.runtime.bounds_checks,
+            config.runtime.eof_behavior,
+        )?;
+        Ok(0)
+    }
+}
+
+pub struct BfBackend;
+
+impl Backend for BfBackend {
+    fn name(&self) -> &str {
+        "bf"
+    }
+
+    fn description(&self) -> &str {
+        "Write the optimized program back out as plain Brainfuck"
+    }
+
+    fn run(&self, ops: &[InterpreterOp], config: &BackendConfig) -> Result<i32> {
+        transpile_bf(ops.iter(), config.output_filename(self.name())?)?;
+        Ok(0)
+    }
+}
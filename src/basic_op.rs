@@ -2,7 +2,7 @@ use std::fmt;
 
 use anyhow::{anyhow, Result};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum BasicOp {
     ChangeBy(u8),
     Shift(i64),
@@ -47,7 +47,7 @@ impl BasicOpAcc {
                 self.building = Some(Output(current + new));
             }
             (Some(_), _) => {
-                return std::mem::replace(&mut self.building, Some(op)).and_then(Self::filter);
+                return self.building.replace(op).and_then(Self::filter);
             }
         }
 
@@ -64,7 +64,7 @@ impl BasicOpAcc {
 
         let op = match byte {
             b'+' => BasicOp::ChangeBy(count as u8),
-            b'-' => BasicOp::ChangeBy(0 - count as u8),
+            b'-' => BasicOp::ChangeBy((count as u8).wrapping_neg()),
             b'[' => {
                 let op = BasicOp::LoopStart;
                 if count > 1 {
@@ -127,7 +127,7 @@ impl BasicOpAcc {
     }
 
     pub fn finalize(&mut self) -> Option<BasicOp> {
-        let built = std::mem::replace(&mut self.building, None).and_then(Self::filter);
+        let built = self.building.take().and_then(Self::filter);
 
         match built {
             Some(_) => built,
@@ -136,6 +136,12 @@ impl BasicOpAcc {
     }
 }
 
+impl Default for BasicOpAcc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl fmt::Display for BasicOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
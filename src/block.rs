@@ -0,0 +1,135 @@
+use crate::{compound_op::CompoundOp, interpreter_op::InterpreterOp};
+use anyhow::{anyhow, Result};
+
+/// Ops with the loops among them nested as trees, for analyses that are awkward on the flat
+/// [`InterpreterOp`]s where a loop is only the distance between its start and end
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Block {
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    Op(CompoundOp),
+    Loop(Loop),
+    /// Loop whose body always leaves the cell it tests zero, so it runs at most once
+    If(Loop),
+}
+
+/// Loop along with what's known about its body, which is worked out by [`Loop::new`] so it has to be rebuilt
+/// when the body changes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loop {
+    pub body: Block,
+    /// How far each run of the body moves the pointer, or `None` if that depends on the tape
+    pub shift: Option<i64>,
+    /// Whether each run of the body ends on the cell it started on, so every test is of the same cell
+    pub balanced: bool,
+    /// Whether the body reads input or writes output
+    pub io: bool,
+}
+
+impl Block {
+    /// Nests the loops of the flat ops
+    pub fn from_ops(ops: &[InterpreterOp]) -> Result<Self> {
+        let mut nodes = Vec::new();
+        let mut instr_i = 0;
+
+        while instr_i < ops.len() {
+            match &ops[instr_i] {
+                InterpreterOp::LoopStart(distance) | InterpreterOp::IfStart(distance) => {
+                    let end = instr_i + distance;
+                    let is_if = matches!(ops[instr_i], InterpreterOp::IfStart(_));
+                    let matched = match ops.get(end) {
+                        Some(InterpreterOp::LoopEnd(back)) => !is_if && back == distance,
+                        Some(InterpreterOp::IfEnd(back)) => is_if && back == distance,
+                        _ => false,
+                    };
+
+                    if !matched {
+                        return Err(anyhow!("Instruction '[' is missing match"));
+                    }
+
+                    let inner = Loop::new(Self::from_ops(&ops[instr_i + 1..end])?);
+                    nodes.push(if is_if { Node::If(inner) } else { Node::Loop(inner) });
+                    instr_i = end + 1;
+                }
+                InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => {
+                    return Err(anyhow!("Instruction ']' is missing match"))
+                }
+                InterpreterOp::CompoundOp(op) => {
+                    nodes.push(Node::Op(op.clone()));
+                    instr_i += 1;
+                }
+            }
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Flattens the loops back into the ops backends run
+    pub fn to_ops(&self) -> Vec<InterpreterOp> {
+        let mut ops = Vec::new();
+        self.flatten(&mut ops);
+        ops
+    }
+
+    fn flatten(&self, ops: &mut Vec<InterpreterOp>) {
+        for node in &self.nodes {
+            match node {
+                Node::Op(op) => ops.push(InterpreterOp::CompoundOp(op.clone())),
+                Node::Loop(inner) => {
+                    let start_index = ops.len();
+                    ops.push(InterpreterOp::LoopStart(0));
+                    inner.body.flatten(ops);
+
+                    let distance = ops.len() - start_index;
+                    ops.push(InterpreterOp::LoopEnd(distance));
+                    ops[start_index] = InterpreterOp::LoopStart(distance);
+                }
+                Node::If(inner) => {
+                    let start_index = ops.len();
+                    ops.push(InterpreterOp::IfStart(0));
+                    inner.body.flatten(ops);
+
+                    let distance = ops.len() - start_index;
+                    ops.push(InterpreterOp::IfEnd(distance));
+                    ops[start_index] = InterpreterOp::IfStart(distance);
+                }
+            }
+        }
+    }
+
+    /// How far the ops move the pointer, or `None` if that depends on the tape
+    ///
+    /// A loop that isn't balanced runs an unknown number of times, so it moves the pointer an unknown amount, as
+    /// does an if that isn't balanced.
+    pub fn shift(&self) -> Option<i64> {
+        self.nodes.iter().try_fold(0, |shift, node| match node {
+            Node::Op(op) => Some(shift + op.shift()?),
+            Node::Loop(inner) | Node::If(inner) if inner.balanced => Some(shift),
+            Node::Loop(_) | Node::If(_) => None,
+        })
+    }
+
+    /// Whether any of the ops, those in loops included, reads input or writes output
+    pub fn does_io(&self) -> bool {
+        self.nodes.iter().any(|node| match node {
+            Node::Op(op) => op.does_io(),
+            Node::Loop(inner) | Node::If(inner) => inner.io,
+        })
+    }
+}
+
+impl Loop {
+    pub fn new(body: Block) -> Self {
+        let shift = body.shift();
+
+        Self {
+            balanced: shift == Some(0),
+            io: body.does_io(),
+            shift,
+            body,
+        }
+    }
+}
@@ -0,0 +1,539 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::{AffineUpdate, CompoundOp},
+    interpreter::{EofBehavior, RuntimeOptions},
+    interpreter_op::InterpreterOp,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+};
+
+// A bundle is a copy of this executable followed by the payload, the payload length and the magic
+const MAGIC: &[u8; 8] = b"BFBUNDL1";
+const TRAILER_SIZE: u64 = 16;
+
+const FLAG_BOUNDS_CHECKS: u8 = 1 << 0;
+const FLAG_JIT: u8 = 1 << 1;
+const FLAG_BUFFERED_OUTPUT: u8 = 1 << 2;
+
+/// Already optimized program and the options it should run with
+pub struct Bundle {
+    pub ops: Vec<InterpreterOp>,
+    pub jit: bool,
+    pub options: RuntimeOptions,
+}
+
+impl Bundle {
+    /// Writes a copy of the running executable with this bundle attached
+    pub fn write(&self, output_filename: &str) -> Result<()> {
+        let mut executable = std::fs::read(std::env::current_exe()?)?;
+
+        // Bundling from a bundle replaces the attached program instead of stacking another one
+        if let Some(payload_len) = payload_len(&executable) {
+            let len = (executable.len() as u64).saturating_sub(payload_len + TRAILER_SIZE);
+            executable.truncate(len as usize);
+        }
+
+        let payload = self.serialize();
+        executable.extend_from_slice(&payload);
+        executable.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        executable.extend_from_slice(MAGIC);
+        std::fs::write(output_filename, executable)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(output_filename, std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the bundle attached to the running executable, if there is one
+    pub fn load() -> Result<Option<Self>> {
+        let mut file = File::open(std::env::current_exe()?)?;
+        let file_len = file.metadata()?.len();
+
+        if file_len < TRAILER_SIZE {
+            return Ok(None);
+        }
+
+        let mut trailer = [0u8; TRAILER_SIZE as usize];
+        file.seek(SeekFrom::End(-(TRAILER_SIZE as i64)))?;
+        file.read_exact(&mut trailer)?;
+
+        let Some(payload_len) = payload_len(&trailer) else {
+            return Ok(None);
+        };
+
+        if payload_len > file_len - TRAILER_SIZE {
+            return Err(anyhow!("[error] Bundle payload is larger than the executable"));
+        }
+
+        let mut payload = vec![0u8; payload_len as usize];
+        file.seek(SeekFrom::End(-((payload_len + TRAILER_SIZE) as i64)))?;
+        file.read_exact(&mut payload)?;
+
+        Self::deserialize(&payload).map(Some)
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::new());
+
+        let mut flags = 0;
+        if self.options.bounds_checks {
+            flags |= FLAG_BOUNDS_CHECKS;
+        }
+        if self.jit {
+            flags |= FLAG_JIT;
+        }
+        if self.options.buffered_output {
+            flags |= FLAG_BUFFERED_OUTPUT;
+        }
+
+        w.u8(flags);
+        w.u8(match self.options.eof_behavior {
+            EofBehavior::Zero => 0,
+            EofBehavior::Unchanged => 1,
+            EofBehavior::Max => 2,
+        });
+        w.u64(self.options.tape_size as u64);
+        w.u64(self.options.tape_origin as u64);
+        w.u64(self.ops.len() as u64);
+
+        for op in &self.ops {
+            w.op(op);
+        }
+
+        w.0
+    }
+
+    fn deserialize(payload: &[u8]) -> Result<Self> {
+        let mut r = Reader(payload);
+
+        let flags = r.u8()?;
+        let eof_behavior = match r.u8()? {
+            0 => EofBehavior::Zero,
+            1 => EofBehavior::Unchanged,
+            2 => EofBehavior::Max,
+            value => return Err(anyhow!("[error] Bundle has unknown EOF behavior {}", value)),
+        };
+        let tape_size = r.u64()? as usize;
+        let tape_origin = r.u64()? as usize;
+
+        let count = r.u64()?;
+        let mut ops = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            ops.push(r.op()?);
+        }
+
+        Ok(Self {
+            ops,
+            jit: flags & FLAG_JIT != 0,
+            options: RuntimeOptions {
+                bounds_checks: flags & FLAG_BOUNDS_CHECKS != 0,
+                eof_behavior,
+                tape_size,
+                tape_origin,
+                buffered_output: flags & FLAG_BUFFERED_OUTPUT != 0,
+            },
+        })
+    }
+}
+
+/// Length of the payload if `data` ends with a bundle trailer
+fn payload_len(data: &[u8]) -> Option<u64> {
+    let trailer = &data[data.len().checked_sub(TRAILER_SIZE as usize)?..];
+
+    if &trailer[8..] != MAGIC {
+        return None;
+    }
+
+    Some(u64::from_le_bytes(trailer[..8].try_into().unwrap()))
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn op(&mut self, op: &InterpreterOp) {
+        match op {
+            InterpreterOp::LoopStart(distance) => {
+                self.u8(0);
+                self.u64(*distance as u64);
+            }
+            InterpreterOp::LoopEnd(distance) => {
+                self.u8(1);
+                self.u64(*distance as u64);
+            }
+            InterpreterOp::IfStart(distance) => {
+                self.u8(50);
+                self.u64(*distance as u64);
+            }
+            InterpreterOp::IfEnd(distance) => {
+                self.u8(51);
+                self.u64(*distance as u64);
+            }
+            InterpreterOp::CompoundOp(op) => self.compound_op(op),
+        }
+    }
+
+    fn compound_op(&mut self, op: &CompoundOp) {
+        match op {
+            CompoundOp::BasicOp(BasicOp::ChangeBy(amount)) => {
+                self.u8(2);
+                self.u8(*amount);
+            }
+            CompoundOp::BasicOp(BasicOp::Shift(amount)) => {
+                self.u8(3);
+                self.i64(*amount);
+            }
+            CompoundOp::BasicOp(BasicOp::Input(count)) => {
+                self.u8(4);
+                self.u64(*count);
+            }
+            CompoundOp::BasicOp(BasicOp::Output(count)) => {
+                self.u8(5);
+                self.u64(*count);
+            }
+            CompoundOp::BasicOp(BasicOp::LoopStart) => self.u8(6),
+            CompoundOp::BasicOp(BasicOp::LoopEnd) => self.u8(7),
+            CompoundOp::Panic(value) => {
+                self.u8(8);
+                self.u8(*value);
+            }
+            CompoundOp::Zero => self.u8(9),
+            CompoundOp::ZeroAdvance(amount) => {
+                self.u8(10);
+                self.u64(*amount);
+            }
+            CompoundOp::ZeroRetreat(amount) => {
+                self.u8(11);
+                self.u64(*amount);
+            }
+            CompoundOp::Set(value) => {
+                self.u8(12);
+                self.u8(*value);
+            }
+            CompoundOp::MoveAdd(offset) => {
+                self.u8(13);
+                self.i64(*offset);
+            }
+            CompoundOp::MoveAdd2(offset1, offset2) => {
+                self.u8(14);
+                self.i64(*offset1);
+                self.i64(*offset2);
+            }
+            CompoundOp::MoveSet(offset) => {
+                self.u8(15);
+                self.i64(*offset);
+            }
+            CompoundOp::Dupe(offset) => {
+                self.u8(16);
+                self.i64(*offset);
+            }
+            CompoundOp::Equals => self.u8(17),
+            CompoundOp::NotEquals => self.u8(18),
+            CompoundOp::ShiftLeftLogical => self.u8(19),
+            CompoundOp::ShiftRightLogical => self.u8(20),
+            CompoundOp::LessThan => self.u8(21),
+            CompoundOp::GreaterThan => self.u8(22),
+            CompoundOp::LessThanEqual => self.u8(23),
+            CompoundOp::GreaterThanEqual => self.u8(24),
+            CompoundOp::BitAnd => self.u8(25),
+            CompoundOp::BitNeg => self.u8(26),
+            CompoundOp::WellBehavedDivMod(shift_amount) => {
+                self.u8(27);
+                self.i64(*shift_amount);
+            }
+            CompoundOp::PrintStatic(content) => {
+                self.u8(28);
+                self.u64(content.len() as u64);
+                self.0.extend_from_slice(content);
+            }
+            CompoundOp::MoveCellDynamicU8(offset) => {
+                self.u8(29);
+                self.u64(*offset);
+            }
+            CompoundOp::MoveCellDynamicU16(offset) => {
+                self.u8(30);
+                self.u64(*offset);
+            }
+            CompoundOp::MoveCellDynamicU32(offset) => {
+                self.u8(31);
+                self.u64(*offset);
+            }
+            CompoundOp::CopyCellDynamicU8(offset) => {
+                self.u8(32);
+                self.u64(*offset);
+            }
+            CompoundOp::CopyCellDynamicU32(offset) => {
+                self.u8(33);
+                self.u64(*offset);
+            }
+            CompoundOp::MoveCellsStaticReverse(offset, count) => {
+                self.u8(34);
+                self.i64(*offset);
+                self.u64(*count);
+            }
+            CompoundOp::AddU32 => self.u8(35),
+            CompoundOp::MulU32 => self.u8(36),
+            CompoundOp::SubU32 => self.u8(52),
+            CompoundOp::LessThanU32 => self.u8(53),
+            CompoundOp::EqualsU32 => self.u8(54),
+            CompoundOp::AndU32 => self.u8(55),
+            CompoundOp::OrU32 => self.u8(56),
+            CompoundOp::XorU32 => self.u8(57),
+            CompoundOp::ShiftLeftU32 => self.u8(58),
+            CompoundOp::ShiftRightU32 => self.u8(59),
+            CompoundOp::DivModU32 => self.u8(60),
+            CompoundOp::MulAddMany(targets) => {
+                self.u8(38);
+                self.u64(targets.len() as u64);
+
+                for (offset, factor) in targets {
+                    self.i64(*offset);
+                    self.u8(*factor);
+                }
+            }
+            CompoundOp::ChangeByAt(offset, amount) => {
+                self.u8(39);
+                self.i64(*offset);
+                self.u8(*amount);
+            }
+            CompoundOp::SetAt(offset, value) => {
+                self.u8(40);
+                self.i64(*offset);
+                self.u8(*value);
+            }
+            CompoundOp::InputAt(offset, count) => {
+                self.u8(41);
+                self.i64(*offset);
+                self.u64(*count);
+            }
+            CompoundOp::OutputAt(offset, count) => {
+                self.u8(42);
+                self.i64(*offset);
+                self.u64(*count);
+            }
+            CompoundOp::MoveAddAt(from, to) => {
+                self.u8(43);
+                self.i64(*from);
+                self.i64(*to);
+            }
+            CompoundOp::MoveAdd2At(from, to1, to2) => {
+                self.u8(44);
+                self.i64(*from);
+                self.i64(*to1);
+                self.i64(*to2);
+            }
+            CompoundOp::MoveSetAt(from, to) => {
+                self.u8(45);
+                self.i64(*from);
+                self.i64(*to);
+            }
+            CompoundOp::MulAddManyAt(from, targets) => {
+                self.u8(46);
+                self.i64(*from);
+                self.u64(targets.len() as u64);
+
+                for (offset, factor) in targets {
+                    self.i64(*offset);
+                    self.u8(*factor);
+                }
+            }
+            CompoundOp::ScanRight(stride) => {
+                self.u8(47);
+                self.u64(*stride);
+            }
+            CompoundOp::ScanLeft(stride) => {
+                self.u8(48);
+                self.u64(*stride);
+            }
+            CompoundOp::SetCells(offset, values) => {
+                self.u8(49);
+                self.i64(*offset);
+                self.u64(values.len() as u64);
+                self.0.extend_from_slice(values);
+            }
+            CompoundOp::Affine(updates) => {
+                self.u8(37);
+                self.u64(updates.len() as u64);
+
+                for update in updates {
+                    self.i64(update.offset);
+                    self.u8(update.constant);
+                    self.u64(update.terms.len() as u64);
+
+                    for (offset, coefficient) in &update.terms {
+                        self.i64(*offset);
+                        self.u8(*coefficient);
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.0.len() < count {
+            return Err(anyhow!("[error] Bundle is truncated"));
+        }
+
+        let (bytes, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn op(&mut self) -> Result<InterpreterOp> {
+        let op = match self.u8()? {
+            0 => InterpreterOp::LoopStart(self.u64()? as usize),
+            1 => InterpreterOp::LoopEnd(self.u64()? as usize),
+            2 => CompoundOp::BasicOp(BasicOp::ChangeBy(self.u8()?)).into(),
+            3 => CompoundOp::BasicOp(BasicOp::Shift(self.i64()?)).into(),
+            4 => CompoundOp::BasicOp(BasicOp::Input(self.u64()?)).into(),
+            5 => CompoundOp::BasicOp(BasicOp::Output(self.u64()?)).into(),
+            6 => CompoundOp::BasicOp(BasicOp::LoopStart).into(),
+            7 => CompoundOp::BasicOp(BasicOp::LoopEnd).into(),
+            8 => CompoundOp::Panic(self.u8()?).into(),
+            9 => CompoundOp::Zero.into(),
+            10 => CompoundOp::ZeroAdvance(self.u64()?).into(),
+            11 => CompoundOp::ZeroRetreat(self.u64()?).into(),
+            12 => CompoundOp::Set(self.u8()?).into(),
+            13 => CompoundOp::MoveAdd(self.i64()?).into(),
+            14 => CompoundOp::MoveAdd2(self.i64()?, self.i64()?).into(),
+            15 => CompoundOp::MoveSet(self.i64()?).into(),
+            16 => CompoundOp::Dupe(self.i64()?).into(),
+            17 => CompoundOp::Equals.into(),
+            18 => CompoundOp::NotEquals.into(),
+            19 => CompoundOp::ShiftLeftLogical.into(),
+            20 => CompoundOp::ShiftRightLogical.into(),
+            21 => CompoundOp::LessThan.into(),
+            22 => CompoundOp::GreaterThan.into(),
+            23 => CompoundOp::LessThanEqual.into(),
+            24 => CompoundOp::GreaterThanEqual.into(),
+            25 => CompoundOp::BitAnd.into(),
+            26 => CompoundOp::BitNeg.into(),
+            27 => CompoundOp::WellBehavedDivMod(self.i64()?).into(),
+            28 => {
+                let len = self.u64()? as usize;
+                CompoundOp::PrintStatic(self.bytes(len)?.to_vec()).into()
+            }
+            29 => CompoundOp::MoveCellDynamicU8(self.u64()?).into(),
+            30 => CompoundOp::MoveCellDynamicU16(self.u64()?).into(),
+            31 => CompoundOp::MoveCellDynamicU32(self.u64()?).into(),
+            32 => CompoundOp::CopyCellDynamicU8(self.u64()?).into(),
+            33 => CompoundOp::CopyCellDynamicU32(self.u64()?).into(),
+            34 => CompoundOp::MoveCellsStaticReverse(self.i64()?, self.u64()?).into(),
+            35 => CompoundOp::AddU32.into(),
+            36 => CompoundOp::MulU32.into(),
+            37 => {
+                let len = self.u64()?;
+                let mut updates = Vec::new();
+
+                for _ in 0..len {
+                    let offset = self.i64()?;
+                    let constant = self.u8()?;
+                    let term_count = self.u64()?;
+                    let mut terms = Vec::new();
+
+                    for _ in 0..term_count {
+                        terms.push((self.i64()?, self.u8()?));
+                    }
+
+                    updates.push(AffineUpdate {
+                        offset,
+                        constant,
+                        terms,
+                    });
+                }
+
+                CompoundOp::Affine(updates).into()
+            }
+            38 => {
+                let len = self.u64()?;
+                let mut targets = Vec::new();
+
+                for _ in 0..len {
+                    targets.push((self.i64()?, self.u8()?));
+                }
+
+                CompoundOp::MulAddMany(targets).into()
+            }
+            39 => CompoundOp::ChangeByAt(self.i64()?, self.u8()?).into(),
+            40 => CompoundOp::SetAt(self.i64()?, self.u8()?).into(),
+            41 => CompoundOp::InputAt(self.i64()?, self.u64()?).into(),
+            42 => CompoundOp::OutputAt(self.i64()?, self.u64()?).into(),
+            43 => CompoundOp::MoveAddAt(self.i64()?, self.i64()?).into(),
+            44 => CompoundOp::MoveAdd2At(self.i64()?, self.i64()?, self.i64()?).into(),
+            45 => CompoundOp::MoveSetAt(self.i64()?, self.i64()?).into(),
+            46 => {
+                let from = self.i64()?;
+                let len = self.u64()?;
+                let mut targets = Vec::new();
+
+                for _ in 0..len {
+                    targets.push((self.i64()?, self.u8()?));
+                }
+
+                CompoundOp::MulAddManyAt(from, targets).into()
+            }
+            47 => CompoundOp::ScanRight(self.u64()?).into(),
+            48 => CompoundOp::ScanLeft(self.u64()?).into(),
+            49 => {
+                let offset = self.i64()?;
+                let len = self.u64()? as usize;
+                CompoundOp::SetCells(offset, self.bytes(len)?.to_vec()).into()
+            }
+            50 => InterpreterOp::IfStart(self.u64()? as usize),
+            51 => InterpreterOp::IfEnd(self.u64()? as usize),
+            52 => CompoundOp::SubU32.into(),
+            53 => CompoundOp::LessThanU32.into(),
+            54 => CompoundOp::EqualsU32.into(),
+            55 => CompoundOp::AndU32.into(),
+            56 => CompoundOp::OrU32.into(),
+            57 => CompoundOp::XorU32.into(),
+            58 => CompoundOp::ShiftLeftU32.into(),
+            59 => CompoundOp::ShiftRightU32.into(),
+            60 => CompoundOp::DivModU32.into(),
+            tag => return Err(anyhow!("[error] Bundle contains unknown op {}", tag)),
+        };
+
+        Ok(op)
+    }
+}
+
+impl From<CompoundOp> for InterpreterOp {
+    fn from(op: CompoundOp) -> Self {
+        InterpreterOp::CompoundOp(op)
+    }
+}
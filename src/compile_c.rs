@@ -0,0 +1,84 @@
+use crate::{interpreter::RuntimeOptions, interpreter_op::InterpreterOp, transpile_c::write_c};
+use anyhow::{anyhow, Result};
+use std::{fs::File, io::BufWriter, path::Path, process::Command};
+
+pub const DEFAULT_CFLAGS: &str = "-O2";
+
+/// Transpiles to C and builds it into an executable with the system C compiler
+///
+/// The compiler is taken from `$CC`, falling back to `cc`. The source is written to a private temporary
+/// directory, which is only kept if compiling fails. Diagnostics that point into the generated source are
+/// annotated with the op that produced the line.
+pub fn compile_c(ops: &[InterpreterOp], output_filename: &str, options: RuntimeOptions, cflags: &str) -> Result<()> {
+    let dir = tempfile::Builder::new().prefix("bfi-").tempdir()?;
+    let source = dir.path().join("program.c");
+    let op_lines = write_c(ops.iter(), BufWriter::new(File::create(&source)?), options)?;
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let output = Command::new(&compiler)
+        .args(cflags.split_whitespace())
+        .arg("-o")
+        .arg(output_filename)
+        .arg(&source)
+        .output()
+        .map_err(|error| anyhow!("[error] Failed to run C compiler `{}`: {}", compiler, error))?;
+
+    let diagnostics = annotate_diagnostics(&String::from_utf8_lossy(&output.stderr), &source, ops, &op_lines);
+
+    if !output.status.success() {
+        let source = dir.keep().join("program.c");
+
+        return Err(anyhow!(
+            "[error] C compiler failed, generated source kept at {}\n{}",
+            source.display(),
+            diagnostics
+        ));
+    }
+
+    eprint!("{}", diagnostics);
+    Ok(())
+}
+
+/// Compiles to a temporary executable and runs it with the given arguments, inheriting stdin/stdout/stderr
+///
+/// The executable lives in a private temporary directory that's removed once the program exits. Returns the
+/// exit code of the program.
+pub fn run_native(ops: &[InterpreterOp], options: RuntimeOptions, cflags: &str, args: &[String]) -> Result<i32> {
+    let dir = tempfile::Builder::new().prefix("bfi-native-").tempdir()?;
+    let executable = dir.path().join("program");
+    let executable_name = executable.to_string_lossy().into_owned();
+
+    compile_c(ops, &executable_name, options, cflags)?;
+
+    let status = Command::new(&executable).args(args).status()?;
+
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Appends the op that generated the line to each diagnostic of the form `source:line:...`
+fn annotate_diagnostics(stderr: &str, source: &Path, ops: &[InterpreterOp], op_lines: &[usize]) -> String {
+    let prefix = format!("{}:", source.display());
+    let mut annotated = String::new();
+
+    for line in stderr.lines() {
+        annotated.push_str(line);
+
+        let line_number = line
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.split(':').next())
+            .and_then(|number| number.parse::<usize>().ok());
+
+        // Ops are emitted in order, so the generating op is the last one starting at or before the line
+        if let Some(line_number) = line_number {
+            let op_i = op_lines.partition_point(|&start| start <= line_number);
+
+            if op_i > 0 {
+                annotated.push_str(&format!(" [op {}: {:?}]", op_i - 1, ops[op_i - 1]));
+            }
+        }
+
+        annotated.push('\n');
+    }
+
+    annotated
+}
@@ -1,7 +1,14 @@
-use crate::basic_op::BasicOp;
+use crate::{
+    basic_op::BasicOp,
+    opt_stats::{OptStats, LINEAR_LOOPS},
+    passes::Passes,
+    patterns::{self, Pattern},
+    rules::{self, RULES},
+};
 use slice_deque::SliceDeque;
+use std::time::Instant;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CompoundOp {
     BasicOp(BasicOp),
     Panic(u8),
@@ -33,2800 +40,355 @@ pub enum CompoundOp {
     MoveCellsStaticReverse(i64, u64),
     AddU32,
     MulU32,
+    /// First number minus the second. This and the u32 ops after it read little-endian numbers from the four
+    /// cells before the pointer and the four before those, leave the result in the first number, zero the second
+    /// and move the pointer onto the last cell of the first number.
+    SubU32,
+    /// 1 if the first number is less than the second, else 0
+    LessThanU32,
+    /// 1 if the numbers are equal, else 0
+    EqualsU32,
+    /// Bitwise and of the numbers
+    AndU32,
+    /// Bitwise or of the numbers
+    OrU32,
+    /// Bitwise exclusive or of the numbers
+    XorU32,
+    /// First number shifted left by the second, 0 when shifting by 32 or more
+    ShiftLeftU32,
+    /// First number shifted right by the second, 0 when shifting by 32 or more
+    ShiftRightU32,
+    /// Leaves the quotient in the first number and the remainder in the second, both 0 when dividing by 0
+    DivModU32,
+    /// Adds the current cell, multiplied by each factor, to the cells at the offsets and zeroes it
+    MulAddMany(Vec<(i64, u8)>),
+    /// Moves the pointer right by the stride until it's on a zero cell
+    ScanRight(u64),
+    /// Moves the pointer left by the stride until it's on a zero cell
+    ScanLeft(u64),
+    /// Sets the cells from the offset onwards to the values, which are never empty, such as the tape left by
+    /// partial evaluation
+    SetCells(i64, Vec<u8>),
+    // Forms of the ops above that act on cells away from the pointer, produced by `sink_shifts`. Every
+    // offset is relative to the pointer, including those of the cells an op moves to.
+    ChangeByAt(i64, u8),
+    SetAt(i64, u8),
+    InputAt(i64, u64),
+    OutputAt(i64, u64),
+    /// Source offset and destination offset
+    MoveAddAt(i64, i64),
+    MoveAdd2At(i64, i64, i64),
+    MoveSetAt(i64, i64),
+    MulAddManyAt(i64, Vec<(i64, u8)>),
+    /// Cells set to an affine function of the cells around the pointer, all reading the values from before the op
+    Affine(Vec<AffineUpdate>),
 }
 
+impl CompoundOp {
+    /// How far the op moves the pointer, or `None` if that depends on the tape
+    pub fn shift(&self) -> Option<i64> {
+        let shift = match self {
+            CompoundOp::BasicOp(BasicOp::Shift(amount)) => *amount,
+            CompoundOp::ZeroAdvance(amount) => *amount as i64,
+            CompoundOp::ZeroRetreat(amount) => -(*amount as i64),
+            CompoundOp::Dupe(_) | CompoundOp::Equals | CompoundOp::NotEquals | CompoundOp::BitNeg => 1,
+            CompoundOp::BitAnd => 2,
+            CompoundOp::ShiftLeftLogical | CompoundOp::ShiftRightLogical => -1,
+            CompoundOp::WellBehavedDivMod(amount) => *amount,
+            CompoundOp::MoveCellDynamicU8(_) => -2,
+            CompoundOp::MoveCellDynamicU16(_) | CompoundOp::CopyCellDynamicU32(_) => -3,
+            CompoundOp::MoveCellDynamicU32(_)
+            | CompoundOp::AddU32
+            | CompoundOp::MulU32
+            | CompoundOp::SubU32
+            | CompoundOp::LessThanU32
+            | CompoundOp::EqualsU32
+            | CompoundOp::AndU32
+            | CompoundOp::OrU32
+            | CompoundOp::XorU32
+            | CompoundOp::ShiftLeftU32
+            | CompoundOp::ShiftRightU32
+            | CompoundOp::DivModU32 => -5,
+            CompoundOp::MoveCellsStaticReverse(_, count) => -(*count as i64),
+            CompoundOp::ScanRight(_) | CompoundOp::ScanLeft(_) => return None,
+            _ => 0,
+        };
+
+        Some(shift)
+    }
+
+    /// Whether the op reads input or writes output
+    pub fn does_io(&self) -> bool {
+        matches!(
+            self,
+            CompoundOp::BasicOp(BasicOp::Input(_) | BasicOp::Output(_))
+                | CompoundOp::PrintStatic(_)
+                | CompoundOp::InputAt(..)
+                | CompoundOp::OutputAt(..)
+        )
+    }
+}
+
+/// New value of one cell, `constant + sum(coefficient * cell)` wrapping around
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AffineUpdate {
+    /// Cell written, relative to the pointer
+    pub offset: i64,
+    pub constant: u8,
+    /// Cells read relative to the pointer, each with the amount it's multiplied by
+    pub terms: Vec<(i64, u8)>,
+}
+
+/// Recognises algorithms in the stream of basic ops using the [`rules`] and any user-defined patterns
 pub struct CompoundOpAcc {
     building: SliceDeque<CompoundOp>,
+    passes: Passes,
+    patterns: Vec<Pattern>,
+    linear_loops: bool,
+    whole_program: bool,
+    stats: Option<OptStats>,
 }
 
-use BasicOp::*;
-use CompoundOp::*;
-
-const WINDOW_SIZE: usize = 2048;
+pub(crate) const WINDOW_SIZE: usize = 2048;
 
 impl CompoundOpAcc {
     pub fn new() -> Self {
+        Self::with_patterns(Passes::default(), Vec::new())
+    }
+
+    /// Only applies the enabled rules, followed by the given patterns
+    pub fn with_patterns(passes: Passes, patterns: Vec<Pattern>) -> Self {
         Self {
             building: SliceDeque::with_capacity(WINDOW_SIZE + 1),
+            linear_loops: passes.is_enabled(LINEAR_LOOPS),
+            passes,
+            patterns,
+            whole_program: false,
+            stats: None,
         }
     }
 
-    pub fn feed(&mut self, basic_op: BasicOp) -> Option<CompoundOp> {
-        self.building.push_back(CompoundOp::BasicOp(basic_op));
-
-        match &self.building[..] {
-            // Panic loop pattern
-            [.., Set(value), BasicOp(LoopStart), BasicOp(LoopEnd)] if *value != 0 => {
-                let value = *value;
-                self.building.truncate_back(self.building.len() - 3);
-                self.building.push_back(Panic(value));
-            }
-            // Zero cell pattern
-            [.., BasicOp(LoopStart), BasicOp(ChangeBy(1 | u8::MAX)), BasicOp(LoopEnd)] => {
-                self.building.truncate_back(self.building.len() - 3);
-
-                if let Some(Zero) = self.building.back() {
-                    // Don't add as it's redundant
-                } else {
-                    self.building.push_back(Zero);
-                }
-            }
-            // Zero advance cell pattern
-            [.., Zero, BasicOp(Shift(1))] => {
-                self.building.truncate_back(self.building.len() - 2);
-
-                // Merge with existing if there is one
-                if let Some(ZeroAdvance(existing_amount)) = self.building.back() {
-                    *self.building.back_mut().unwrap() = ZeroAdvance(existing_amount + 1);
-                } else {
-                    self.building.push_back(ZeroAdvance(1));
-                }
-            }
-            // Zero retreat cell pattern
-            [.., Zero, BasicOp(Shift(-1))] => {
-                self.building.truncate_back(self.building.len() - 2);
-
-                // Merge with existing if there is one
-                if let Some(ZeroRetreat(existing_amount)) = self.building.back() {
-                    *self.building.back_mut().unwrap() = ZeroRetreat(existing_amount + 1);
-                } else {
-                    self.building.push_back(ZeroRetreat(1));
-                }
-            }
-            // Set cell pattern
-            [.., Zero, BasicOp(ChangeBy(value))] => {
-                let value = *value;
-                self.building.truncate(self.building.len() - 2);
-
-                // Merge with existing set instructions
-                while let Some(Set(_)) = self.building.back() {
-                    self.building.truncate(self.building.len() - 1);
-                }
-
-                self.building.push_back(Set(value));
-            }
-            // Equals algorithm
-            [
-                ..,
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopEnd),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(-1)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(1)),
-                Zero,
-                BasicOp(LoopEnd),
-            ] => {
-                self.building.truncate_back(self.building.len() - 14);
-                self.building.push_back(Equals);
-            }
-            // Not equals algorithm
-            [
-                ..,
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(-1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(1)),
-                Zero,
-                BasicOp(LoopEnd),
-            ] => {
-                self.building.truncate_back(self.building.len() - 13);
-                self.building.push_back(NotEquals);
-            }
-            // u8 shift left logical algorithm
-            [
-                ..,
-                ZeroRetreat(1),
-                BasicOp(LoopStart),
-                BasicOp(Shift(-1)),
-                MoveAdd(2),
-                BasicOp(Shift(2)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(-2)),
-                BasicOp(ChangeBy(2)),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-            ] => {
-                self.building.truncate_back(self.building.len() - 14);
-                self.building.push_back(ShiftLeftLogical);
-            }
-            // u8 shift right logical algorithm
-            [
-                ..,
-                ZeroAdvance(3),
-                Zero,
-                BasicOp(Shift(-4)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(2)),
-                BasicOp(Shift(-2)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(u8::MAX)),
-                MoveAdd2(2, 3),
-                BasicOp(Shift(3)),
-                MoveAdd(-3),
-                BasicOp(Shift(-1)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(-1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-1)),
-                BasicOp(ChangeBy(2)),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-4)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(3)),
-                MoveAdd(-3),
-                BasicOp(Shift(-1)),
-                ZeroRetreat(1),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-            ] => {
-                self.building.truncate_back(self.building.len() - 32);
-                self.building.push_back(ShiftRightLogical);
-            }
-            // Move add algorithm
-            [
-                ..,
-                BasicOp(LoopStart),
-                BasicOp(Shift(toward_amount)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(back_amount)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-            ]
-            | [
-                ..,
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(toward_amount)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(back_amount)),
-                BasicOp(LoopEnd),
-            ] if *toward_amount == -*back_amount => {
-                let offset = *toward_amount;
-                self.building.truncate_back(self.building.len() - 6);
-                self.building.push_back(MoveAdd(offset));
-
-                match &self.building[..] {
-                    // Move set algorithm
-                    [
-                        ..,
-                        BasicOp(Shift(toward_amount_plus_extra)),
-                        CompoundOp::Zero,
-                        BasicOp(Shift(back_amount)),
-                        CompoundOp::MoveAdd(move_add_toward_amount),
-                    ] if toward_amount_plus_extra.abs() >= back_amount.abs()
-                        && -*back_amount == *move_add_toward_amount =>
-                    {
-                        let extra = toward_amount_plus_extra + *back_amount;
-                        let offset = *move_add_toward_amount;
+    /// Keeps every op fed from now on instead of a window, rewriting the whole program again until nothing changes
+    /// once it's finalized
+    pub fn whole_program(&mut self) {
+        self.whole_program = true;
+    }
 
-                        self.building.truncate_back(self.building.len() - 4);
+    /// Starts counting the rewrites applied to the ops fed from now on
+    pub fn collect_stats(&mut self) {
+        self.stats = Some(OptStats::new(&self.patterns));
+    }
 
-                        if extra != 0 {
-                            self.building.push_back(BasicOp(Shift(extra)));
-                        }
+    pub fn stats(&self) -> Option<&OptStats> {
+        self.stats.as_ref()
+    }
 
-                        self.building.push_back(MoveSet(offset));
-                    }
+    pub fn feed(&mut self, basic_op: BasicOp) -> Option<CompoundOp> {
+        self.timed(|acc| acc.rewrite_fed(basic_op))
+    }
 
-                    // Dupe cell algorithm
-                    [
-                        ..,
-                        ZeroAdvance(advance_amount),
-                        Zero,
-                        BasicOp(Shift(toward_shift)),
-                        MoveAdd2(offset, offset_plus_1),
-                        BasicOp(Shift(back_shift)),
-                        MoveAdd(return_shift),
-                    ] if -*toward_shift == *offset_plus_1
-                        && *offset + 1 == *offset_plus_1
-                        && -*toward_shift == *back_shift
-                        && *toward_shift == *return_shift
-                        && *advance_amount > 0 =>
-                    {
-                        let offset = -*offset;
-                        let advance_amount = *advance_amount;
+    pub fn finalize(&mut self) -> Option<CompoundOp> {
+        self.timed(Self::rewrite_rest)
+    }
 
-                        self.building.truncate_back(self.building.len() - 6);
+    /// Runs `rewrite`, adding the time it took to the stats if they're being collected
+    fn timed(&mut self, rewrite: impl FnOnce(&mut Self) -> Option<CompoundOp>) -> Option<CompoundOp> {
+        let start = self.stats.is_some().then(Instant::now);
+        let op = rewrite(self);
 
-                        if advance_amount > 1 {
-                            self.building.push_back(ZeroAdvance(advance_amount - 1));
-                        }
+        if let (Some(stats), Some(start)) = (&mut self.stats, start) {
+            stats.rewrite_time += start.elapsed();
+        }
 
-                        self.building.push_back(Dupe(offset));
-                    }
+        op
+    }
 
-                    // Less than algorithm
-                    [
-                        ..,
-                        ZeroAdvance(zero_advance_amount),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd2(2, 3),
-                        BasicOp(Shift(2)),
-                        MoveAdd(-2),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        ZeroRetreat(1),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(3)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        Zero,
-                        BasicOp(Shift(2)),
-                        MoveAdd(-2),
-                    ] if *zero_advance_amount > 0 => {
-                        let zero_advance_amount = *zero_advance_amount;
+    fn rewrite_fed(&mut self, basic_op: BasicOp) -> Option<CompoundOp> {
+        self.building.push_back(CompoundOp::BasicOp(basic_op));
 
-                        self.building.truncate_back(self.building.len() - 26);
+        if let Some(stats) = &mut self.stats {
+            stats.push();
+        }
 
-                        if zero_advance_amount > 1 {
-                            self.building.push_back(ZeroAdvance(zero_advance_amount - 1));
-                        }
+        self.rewrite_back();
 
-                        self.building.push_back(LessThan);
-                    }
-                    // Greater than algorithm
-                    [
-                        ..,
-                        ZeroAdvance(zero_advance_amount),
-                        Zero,
-                        BasicOp(Shift(-3)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(2)),
-                        ZeroRetreat(1),
-                        MoveAdd2(1, 2),
-                        BasicOp(Shift(1)),
-                        MoveAdd(-1),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        ZeroRetreat(1),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(2)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-3)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(2)),
-                        MoveAdd(-2),
-                    ] if *zero_advance_amount > 0 => {
-                        let zero_advance_amount = *zero_advance_amount;
+        if !self.whole_program && self.building.len() > WINDOW_SIZE {
+            self.pop_front()
+        } else {
+            None
+        }
+    }
 
-                        self.building.truncate_back(self.building.len() - 23);
+    fn rewrite_rest(&mut self) -> Option<CompoundOp> {
+        if self.whole_program {
+            self.whole_program = false;
 
-                        if zero_advance_amount > 1 {
-                            self.building.push_back(ZeroAdvance(zero_advance_amount - 1));
-                        }
+            // Linear loops are only collapsed once the rules are done, as a rule can match more around them
+            while self.rewrite_again(false) || (self.linear_loops && self.rewrite_again(true)) {}
+        }
 
-                        self.building.push_back(GreaterThan);
-                    }
-                    // Less than or equal algorithm
-                    [
-                        ..,
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(Shift(-3)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(2)),
-                        ZeroRetreat(1),
-                        MoveAdd2(1, 2),
-                        BasicOp(Shift(1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        ZeroRetreat(1),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(2)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-3)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(2)),
-                        MoveAdd(-2),
-                    ] => {
-                        self.building.truncate_back(self.building.len() - 23);
-                        self.building.push_back(LessThanEqual);
-                    }
-                    // Greater than or equal algorithm
-                    [
-                        ..,
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd2(2, 3),
-                        BasicOp(Shift(2)),
-                        MoveAdd(-2),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        ZeroRetreat(1),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(3)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        Zero,
-                        BasicOp(Shift(2)),
-                        MoveAdd(-2),
-                    ] => {
-                        self.building.truncate_back(self.building.len() - 26);
-                        self.building.push_back(GreaterThanEqual);
-                    }
+        self.pop_front()
+    }
 
-                    // Bit and algorithm
-                    [
-                        ..,
-                        Zero,
-                        BasicOp(Shift(2)),
-                        ZeroRetreat(1),
-                        Set(248),
-                        BasicOp(LoopStart),
-                        BasicOp(ChangeBy(8)),
-                        BasicOp(Shift(-2)),
-                        ZeroRetreat(4),
-                        Set(2),
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(2)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        MoveAdd2(1, 3),
-                        BasicOp(Shift(1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(4)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(2)),
-                        BasicOp(Shift(5)),
-                        BasicOp(ChangeBy(254)),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-5)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(4)),
-                        MoveAdd(-4),
-                        BasicOp(Shift(-2)),
-                        Set(2),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        MoveAdd2(1, 3),
-                        BasicOp(Shift(1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(3)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(254)),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(2)),
-                        BasicOp(Shift(3)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-4)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(3)),
-                        MoveAdd(-3),
-                        BasicOp(Shift(2)),
-                        BasicOp(LoopStart),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(Shift(1)),
-                        MoveAdd(-2),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(1)),
-                        ZeroAdvance(1),
-                        MoveAdd2(-1, -2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(2)),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(2)),
-                        BasicOp(ChangeBy(u8::MAX)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(3)),
-                        BasicOp(ChangeBy(249)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(1)),
-                        MoveAdd(-9),
-                    ] => {
-                        self.building.truncate_back(self.building.len() - 96);
-                        self.building.push_back(BitAnd);
-                    }
-                    _ => {}
-                }
-            }
-            // Move add 2 algorithm
-            [
-                ..,
-                BasicOp(LoopStart),
-                BasicOp(Shift(toward_amount1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(toward_amount2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(back_amount)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-            ] if *toward_amount1 + *toward_amount2 == -*back_amount => {
-                let offset1 = *toward_amount1;
-                let offset2 = *toward_amount2;
-                self.building.truncate_back(self.building.len() - 8);
-                self.building.push_back(MoveAdd2(offset1, offset1 + offset2));
-            }
-            // Bit negate algorithm
-            [
-                ..,
-                MoveAdd(1),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(-1)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-            ] => {
-                self.building.truncate_back(self.building.len() - 9);
-                self.building.push_back(BitNeg);
-            }
-            // Divmod algorithm
-            [
-                ..,
-                ZeroAdvance(3),
-                Zero,
-                BasicOp(Shift(-5)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(-2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-5)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(3)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(neg_5_plus_extra)),
-            ] => {
-                let default_shift_amount = -2;
-                let shift_amount = neg_5_plus_extra + 5 + default_shift_amount;
-                self.building.truncate_back(self.building.len() - 36);
-                self.building.push_back(WellBehavedDivMod(shift_amount));
-            }
-            // Print Static pattern
-            [.., Set(initial_letter), BasicOp(Output(letter_count))] => {
-                let initial_letter = *initial_letter;
-                let letter_count = *letter_count as usize;
+    /// Ops still in the window, which rules and patterns can rewrite
+    pub(crate) fn window(&self) -> &[CompoundOp] {
+        &self.building
+    }
 
-                self.building.truncate_back(self.building.len() - 2);
+    /// Applies the rules and then the patterns to the end of the window
+    fn rewrite_back(&mut self) {
+        let mut rules = RULES;
+        let mut len = self.building.len();
 
-                // Merge with previous if exists
-                if let Some(PrintStatic(content)) = self.building.back_mut() {
-                    for _ in 0..letter_count {
-                        content.push(initial_letter);
-                    }
-                } else {
-                    self.building
-                        .push_back(PrintStatic(vec![initial_letter; letter_count as usize]));
-                }
+        while let Some(rule) = rules::apply(rules, &self.passes, &mut self.building) {
+            if let Some(stats) = &mut self.stats {
+                stats.rewrote_back(rule.name, len, rule.len, self.building.len());
             }
-            // Print Static pattern (continuation)
-            [
-                ..,
-                PrintStatic(string),
-                BasicOp(ChangeBy(letter_change_amount)),
-                BasicOp(Output(letter_count)),
-            ] => {
-                let new_letter = string.last().unwrap().wrapping_add(*letter_change_amount);
-                let letter_count = *letter_count;
 
-                self.building.truncate_back(self.building.len() - 2);
+            rules = rule.follow_ups;
+            len = self.building.len();
+        }
 
-                // Re pattern match with mut
-                if let PrintStatic(string) = self.building.back_mut().unwrap() {
-                    for _ in 0..letter_count {
-                        string.push(new_letter);
-                    }
-                } else {
-                    panic!();
-                }
+        if let Some(pattern) = patterns::apply(&self.patterns, &mut self.building) {
+            if let Some(stats) = &mut self.stats {
+                stats.rewrote_back(&pattern.name, len, pattern.matched.len(), self.building.len());
             }
+        }
+    }
 
-            // Move cell dynamic u8 algorithm
-            [
-                ..,
-                ZeroAdvance(2),
-                Zero,
-                BasicOp(Shift(-3)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-3)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(3)),
-                MoveAdd(-3),
-                BasicOp(Shift(-3)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(3)),
-                ZeroRetreat(1),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                MoveSet(neg_offset),
-                BasicOp(Shift(3)),
-                MoveAdd(-2),
-                BasicOp(Shift(-2)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(-2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                MoveAdd(-2),
-                BasicOp(Shift(neg_2_plus_extra)),
-            ] if *neg_2_plus_extra <= -2 && *neg_offset < 0 => {
-                // We ignore normal first shift right instruction,
-                // so offset will be 1 less than normal
-                let offset = -*neg_offset as u64 - 1;
-                let neg_2_plus_extra = *neg_2_plus_extra;
-
-                self.building.truncate(self.building.len() - 44);
-
-                self.building.push_back(MoveCellDynamicU8(offset));
+    /// Feeds every op through the rewrites again, collapsing linear loops as they end if asked to, returning
+    /// whether that changed the program
+    fn rewrite_again(&mut self, linear_loops: bool) -> bool {
+        let previous: Vec<CompoundOp> = self.building.to_vec();
+        let weights = self.stats.as_mut().map(OptStats::restart).unwrap_or_default();
+        self.building.clear();
 
-                if neg_2_plus_extra != -2 {
-                    self.building.push_back(BasicOp(Shift(neg_2_plus_extra - -2)));
-                }
-            }
+        for (i, op) in previous.iter().enumerate() {
+            self.building.push_back(op.clone());
 
-            // Copy cell dynamic u8 algorithm
-            [
-                ..,
-                Dupe(-1),
-                BasicOp(Shift(-2)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(2)),
-                ZeroRetreat(1),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(1)),
-                BasicOp(LoopEnd),
-                Zero,
-                BasicOp(Shift(2)),
-                Zero,
-                BasicOp(Shift(neg_2_plus_neg_offset)),
-                MoveAdd2(move_offset, pos_2_plus_pos_offset),
-                BasicOp(Shift(shift_pos_2_plus_pos_offset)),
-                MoveAdd(move_neg_2_plus_neg_offset),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopEnd),
-            ] if *neg_2_plus_neg_offset == *move_neg_2_plus_neg_offset
-                && *pos_2_plus_pos_offset == *shift_pos_2_plus_pos_offset
-                && -*neg_2_plus_neg_offset == *pos_2_plus_pos_offset
-                && *pos_2_plus_pos_offset - 2 == *move_offset =>
-            {
-                // We ignore normal first shift left instruction,
-                // so offset will be 1 less than normal
-                let offset = *move_offset as u64;
-                self.building.truncate_back(self.building.len() - 27);
-                self.building.push_back(CopyCellDynamicU8(offset));
+            if let Some(stats) = &mut self.stats {
+                stats.requeue(weights[i]);
             }
 
-            // Move cell dynamic u16 algorithm
-            [
-                ..,
-                ZeroAdvance(six_plus_extra),
-                Zero,
-                BasicOp(Shift(-8)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-6)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(2)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-6)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-2)),
-                MoveAdd(2),
-                BasicOp(Shift(8)),
-                MoveAdd(-7),
-                BasicOp(Shift(-1)),
-                MoveAdd(-7),
-                BasicOp(Shift(-3)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(254)),
-                ZeroAdvance(4),
-                Zero,
-                BasicOp(Shift(-256)),
-                MoveAdd(256),
-                BasicOp(Shift(-1)),
-                MoveAdd(256),
-                BasicOp(Shift(-1)),
-                MoveAdd(256),
-                BasicOp(Shift(-1)),
-                MoveAdd(256),
-                BasicOp(Shift(-1)),
-                MoveAdd(256),
-                BasicOp(Shift(258)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(4)),
-                ZeroRetreat(1),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                MoveSet(neg_offset_and_2),
-                BasicOp(Shift(3)),
-                MoveAdd(-3),
-                BasicOp(Shift(1)),
-                MoveAdd(-3),
-                BasicOp(Shift(-4)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(-2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                MoveAdd(-256),
-                BasicOp(Shift(-256)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(neg_three_plus_extra)),
-            ] if *six_plus_extra >= 6 => {
-                let advance_extra = *six_plus_extra - 6;
-                let extra_post_shift = neg_three_plus_extra + 3;
-                let offset = -*neg_offset_and_2 + 1;
+            self.rewrite_back();
 
-                self.building.truncate_back(self.building.len() - 85);
-
-                if advance_extra != 0 {
-                    self.building.push_back(ZeroAdvance(advance_extra));
-                }
-
-                self.building.push_back(MoveCellDynamicU16(offset as u64));
-
-                if extra_post_shift != 0 {
-                    self.building.push_back(BasicOp(Shift(extra_post_shift)));
-                }
+            if linear_loops {
+                self.collapse_linear_loop_back();
             }
+        }
 
-            // Move cell dynamic u32 algorithm
-            [
-                ..,
-                ZeroAdvance(amount_plus_11),
-                Zero,
-                BasicOp(Shift(-16)),
-                MoveSet(5),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(-5)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(5)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-13)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(5)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-13)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(5)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-13)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(Shift(5)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(4)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(-13)),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(13)),
-                MoveAdd(-13),
-                BasicOp(Shift(-1)),
-                MoveAdd(-13),
-                BasicOp(Shift(-1)),
-                MoveAdd(-13),
-                BasicOp(Shift(-1)),
-                MoveAdd(-13),
-                BasicOp(Shift(-5)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(16777212)),
-                ZeroAdvance(8),
-                Zero,
-                BasicOp(Shift(-16777224)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(16777202)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(65533)),
-                ZeroAdvance(8),
-                Zero,
-                BasicOp(Shift(-65544)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(65531)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(254)),
-                ZeroAdvance(8),
-                Zero,
-                BasicOp(Shift(-264)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(250)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(8)),
-                ZeroRetreat(1),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-4)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(2)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                MoveSet(negative_offset_plus_sizeof_index_which_is_4),
-                BasicOp(Shift(5)),
-                MoveAdd(-5),
-                BasicOp(Shift(1)),
-                MoveAdd(-5),
-                BasicOp(Shift(1)),
-                MoveAdd(-5),
-                BasicOp(Shift(1)),
-                MoveAdd(-5),
-                BasicOp(Shift(-8)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-1)),
-                ZeroAdvance(1),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(-4)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                MoveAdd(-256),
-                BasicOp(Shift(1)),
-                MoveAdd(-256),
-                BasicOp(Shift(1)),
-                MoveAdd(-256),
-                BasicOp(Shift(-258)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                MoveAdd(-65536),
-                BasicOp(Shift(1)),
-                MoveAdd(-65536),
-                BasicOp(Shift(-65537)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                MoveAdd(-16777216),
-                BasicOp(Shift(-16777216)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(extra_plus_negative_seven)),
-            ] if *amount_plus_11 >= 11 => {
-                let extra_post_shift = *extra_plus_negative_seven + 7;
-                let extra_pre_advance = *amount_plus_11 - 11;
-                let offset = -*negative_offset_plus_sizeof_index_which_is_4 + 1;
-
-                self.building.truncate_back(self.building.len() - 211);
-
-                if extra_pre_advance != 0 {
-                    self.building.push_back(ZeroAdvance(extra_pre_advance));
-                }
+        self.building[..] != previous[..]
+    }
 
-                self.building.push_back(MoveCellDynamicU32(offset as u64));
+    /// Collapses the linear loop ending the window, if it is one
+    fn collapse_linear_loop_back(&mut self) {
+        if self.building.last() != Some(&CompoundOp::BasicOp(BasicOp::LoopEnd)) {
+            return;
+        }
 
-                if extra_post_shift != 0 {
-                    self.building.push_back(BasicOp(Shift(extra_post_shift)));
-                }
+        let len = self.building.len();
+        let Some(start) = self
+            .building
+            .iter()
+            .rposition(|op| *op == CompoundOp::BasicOp(BasicOp::LoopStart))
+        else {
+            return;
+        };
+
+        if let Some((matched, op)) = linear_loop(&self.building[start..]).filter(|&(matched, _)| start + matched == len)
+        {
+            self.building.truncate_back(start);
+            self.building.push_back(op);
+
+            if let Some(stats) = &mut self.stats {
+                stats.rewrote_back(LINEAR_LOOPS, len, matched, self.building.len());
             }
+        }
+    }
 
-            // Copy cell dynamic u32 algorithm
-            [
-                ..,
-                ZeroAdvance(seven_plus_extra),
-                Zero,
-                BasicOp(Shift(-11)),
-                MoveAdd2(4, 8),
-                BasicOp(Shift(1)),
-                MoveAdd2(4, 8),
-                BasicOp(Shift(1)),
-                MoveAdd2(4, 8),
-                BasicOp(Shift(1)),
-                MoveAdd2(4, 8),
-                BasicOp(Shift(5)),
-                MoveAdd(-8),
-                BasicOp(Shift(1)),
-                MoveAdd(-8),
-                BasicOp(Shift(1)),
-                MoveAdd(-8),
-                BasicOp(Shift(1)),
-                MoveAdd(-8),
-                BasicOp(Shift(-8)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(16777213)),
-                ZeroAdvance(7),
-                Zero,
-                BasicOp(Shift(-16777223)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(1)),
-                MoveAdd(16777216),
-                BasicOp(Shift(16777212)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(65534)),
-                ZeroAdvance(7),
-                Zero,
-                BasicOp(Shift(-65543)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(1)),
-                MoveAdd(65536),
-                BasicOp(Shift(65531)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(255)),
-                ZeroAdvance(7),
-                Zero,
-                BasicOp(Shift(-263)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(1)),
-                MoveAdd(256),
-                BasicOp(Shift(250)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(-1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(8)),
-                ZeroRetreat(1),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(-1)),
-                MoveAdd(1),
-                BasicOp(Shift(1)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(neg_offset)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(pos_offset)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(1)),
-                BasicOp(ChangeBy(1)),
-                BasicOp(Shift(neg_offset_and_1)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(pos_offset_and_1)),
-                MoveAdd(neg_offset_and_1_second),
-                BasicOp(Shift(3)),
-                MoveAdd(-3),
-                BasicOp(Shift(1)),
-                MoveAdd(-3),
-                BasicOp(Shift(1)),
-                MoveAdd(-3),
-                BasicOp(Shift(1)),
-                MoveAdd(-3),
-                BasicOp(Shift(-6)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(1)),
-                MoveAdd(-1),
-                BasicOp(Shift(-4)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-2)),
-                MoveAdd(-256),
-                BasicOp(Shift(2)),
-                MoveAdd(-256),
-                BasicOp(Shift(1)),
-                MoveAdd(-256),
-                BasicOp(Shift(1)),
-                MoveAdd(-256),
-                BasicOp(Shift(-258)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-3)),
-                MoveAdd(-65536),
-                BasicOp(Shift(3)),
-                MoveAdd(-65536),
-                BasicOp(Shift(1)),
-                MoveAdd(-65536),
-                BasicOp(Shift(-65537)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(1)),
-                BasicOp(LoopStart),
-                BasicOp(ChangeBy(u8::MAX)),
-                BasicOp(Shift(-4)),
-                MoveAdd(-16777216),
-                BasicOp(Shift(4)),
-                MoveAdd(-16777216),
-                BasicOp(Shift(-16777216)),
-                BasicOp(LoopEnd),
-                BasicOp(Shift(negative_3_plus_extra)),
-            ] if *seven_plus_extra >= 7
-                && *pos_offset == -*neg_offset
-                && *neg_offset - 1 == *neg_offset_and_1
-                && *pos_offset + 1 == *pos_offset_and_1
-                && *neg_offset_and_1 == *neg_offset_and_1_second =>
-            {
-                let extra_advance = *seven_plus_extra - 7;
-                let extra_shift = *negative_3_plus_extra + 3;
-                let offset = *pos_offset as u64 + 4;
+    /// Takes the op leaving the window, collapsing a linear loop starting there that no rule recognised
+    fn pop_front(&mut self) -> Option<CompoundOp> {
+        if let Some((len, op)) = self.linear_loops.then(|| linear_loop(&self.building)).flatten() {
+            self.building.truncate_front(self.building.len() - len);
+            self.building.push_front(op);
 
-                self.building.truncate_back(self.building.len() - 180);
+            if let Some(stats) = &mut self.stats {
+                stats.rewrote_front(LINEAR_LOOPS, len);
+            }
+        }
 
-                if extra_advance != 0 {
-                    self.building.push_back(ZeroAdvance(extra_advance));
-                }
+        let op = self.building.pop_front();
 
-                self.building.push_back(CopyCellDynamicU32(offset));
+        if let (Some(stats), Some(_)) = (&mut self.stats, &op) {
+            stats.pop();
+        }
 
-                if extra_shift != 0 {
-                    self.building.push_back(BasicOp(Shift(extra_shift)));
-                }
-            }
+        op
+    }
+}
 
-            // MoveCellsStaticReverse algorithm
-            [
-                ..,
-                MoveSet(offset),
-                BasicOp(Shift(-1)),
-                MoveSet(offset_again),
-                BasicOp(Shift(negative_1_plus_extra)),
-            ] if *offset == *offset_again => {
-                let offset = *offset;
-                let extra_shift = *negative_1_plus_extra + 1;
+/// Loop at the start of `ops` that only changes and moves between cells, returning how many ops it
+/// spans and the op it's equivalent to
+///
+/// The loop must end on the cell it started on and change that cell by an odd amount, so it runs
+/// `cell * -inverse(step)` times modulo 256 and always finishes.
+fn linear_loop(ops: &[CompoundOp]) -> Option<(usize, CompoundOp)> {
+    if ops.first() != Some(&CompoundOp::BasicOp(BasicOp::LoopStart)) {
+        return None;
+    }
 
-                self.building.truncate_back(self.building.len() - 4);
-                self.building.push_back(MoveCellsStaticReverse(offset, 2));
+    let mut offset = 0;
+    let mut changes: Vec<(i64, u8)> = Vec::new();
 
-                if extra_shift != 0 {
-                    self.building.push_back(BasicOp(Shift(extra_shift)));
+    for (i, op) in ops.iter().enumerate().skip(1) {
+        match op {
+            CompoundOp::BasicOp(BasicOp::Shift(amount)) => offset += amount,
+            CompoundOp::BasicOp(BasicOp::ChangeBy(amount)) => {
+                match changes.iter_mut().find(|(existing, _)| *existing == offset) {
+                    Some((_, change)) => *change = change.wrapping_add(*amount),
+                    None => changes.push((offset, *amount)),
                 }
             }
-            [
-                ..,
-                MoveCellsStaticReverse(offset, count),
-                MoveSet(offset_again),
-                BasicOp(Shift(negative_1_plus_extra)),
-            ] if *offset == *offset_again => {
-                let offset = *offset;
-                let count = *count;
-                let extra_shift = *negative_1_plus_extra + 1;
+            CompoundOp::BasicOp(BasicOp::LoopEnd) if offset == 0 => {
+                let step = changes.iter().find(|(offset, _)| *offset == 0)?.1;
 
-                self.building.truncate_back(self.building.len() - 3);
-                self.building.push_back(MoveCellsStaticReverse(offset, count + 1));
-
-                if extra_shift != 0 {
-                    self.building.push_back(BasicOp(Shift(extra_shift)));
+                if step % 2 == 0 {
+                    return None;
                 }
 
-                match &self.building[..] {
-                    [
-                        ..,
-                        ZeroAdvance(three_plus_extra_advance),
-                        Zero,
-                        BasicOp(Shift(-4)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(12)),
-                        ZeroAdvance(8),
-                        Dupe(-16),
-                        Dupe(-16),
-                        Dupe(-14),
-                        Dupe(-14),
-                        ZeroAdvance(2),
-                        Dupe(-6),
-                        Dupe(-6),
-                        Dupe(-6),
-                        Dupe(-6),
-                        ZeroAdvance(1),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(6)),
-                        ZeroAdvance(4),
-                        Dupe(-8),
-                        Dupe(-7),
-                        ZeroAdvance(3),
-                        Dupe(-5),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-3),
-                        Dupe(-8),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-2),
-                        Dupe(-6),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-3),
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-7, 2),
-                        BasicOp(Shift(-5)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-1),
-                        Dupe(-6),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-2),
-                        Dupe(-2),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-8, 2),
-                        BasicOp(Shift(-6)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        ZeroAdvance(4),
-                        ZeroRetreat(1),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-8),
-                        Dupe(-8),
-                        Dupe(-14),
-                        Dupe(-14),
-                        ZeroRetreat(1),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(5)),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-5),
-                        Dupe(-4),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(1),
-                        Dupe(-5),
-                        Dupe(-4),
-                        BasicOp(Shift(-2)),
-                        Equals,
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-6),
-                        Dupe(-5),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-5),
-                        BasicOp(Shift(-5)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-7),
-                        Dupe(-7),
-                        Dupe(-11),
-                        Dupe(-11),
-                        ZeroRetreat(1),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(5)),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-5),
-                        Dupe(-4),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(1),
-                        Dupe(-5),
-                        Dupe(-4),
-                        BasicOp(Shift(-2)),
-                        Equals,
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-6),
-                        Dupe(-5),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-5),
-                        BasicOp(Shift(-5)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        Dupe(-6),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-14, 4),
-                        BasicOp(Shift(-10)),
-                        MoveCellsStaticReverse(-4, 3),
-                        MoveSet(-4),
-                        Dupe(-2),
-                        Dupe(-2),
-                        Dupe(-12),
-                        Dupe(-12),
-                        Dupe(-10),
-                        Dupe(-10),
-                        ZeroAdvance(1),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(6)),
-                        ZeroAdvance(4),
-                        Dupe(-8),
-                        Dupe(-7),
-                        ZeroAdvance(3),
-                        Dupe(-5),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-3),
-                        Dupe(-8),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-2),
-                        Dupe(-6),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-3),
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-7, 2),
-                        BasicOp(Shift(-5)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-1),
-                        Dupe(-6),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-2),
-                        Dupe(-2),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-8, 2),
-                        BasicOp(Shift(-5)),
-                        ZeroAdvance(1),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(6)),
-                        ZeroAdvance(4),
-                        Dupe(-8),
-                        Dupe(-7),
-                        ZeroAdvance(3),
-                        Dupe(-5),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-3),
-                        Dupe(-8),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-2),
-                        Dupe(-6),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-3),
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-7, 2),
-                        BasicOp(Shift(-5)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-1),
-                        Dupe(-6),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-2),
-                        Dupe(-2),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-8, 2),
-                        BasicOp(Shift(-6)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-16, 4),
-                        BasicOp(Shift(neg_twelve_plus_extra_shift)),
-                    ] if *three_plus_extra_advance >= 3 => {
-                        let extra_advance = *three_plus_extra_advance - 3;
-                        let extra_shift = *neg_twelve_plus_extra_shift + 12;
-
-                        self.building.truncate_back(self.building.len() - 441);
-
-                        if extra_advance != 0 {
-                            self.building.push_back(ZeroAdvance(extra_advance));
-                        }
-
-                        self.building.push_back(AddU32);
-
-                        if extra_shift != 0 {
-                            self.building.push_back(BasicOp(Shift(extra_shift)));
-                        }
-                    }
-                    [
-                        ..,
-                        ZeroAdvance(three_plus_extra_advance),
-                        Zero,
-                        BasicOp(Shift(-4)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(4),
-                        BasicOp(Shift(12)),
-                        ZeroAdvance(33),
-                        ZeroRetreat(1),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        Dupe(-38),
-                        Dupe(-2),
-                        ShiftRightLogical,
-                        Set(1),
-                        BasicOp(Shift(6)),
-                        BitAnd,
-                        BasicOp(Shift(-8)),
-                        ZeroRetreat(1),
-                        NotEquals,
-                        Set(32),
-                        BasicOp(Shift(1)),
-                        Set(24),
-                        BasicOp(Shift(1)),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        Set(1),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        MoveCellDynamicU8(32),
-                        Dupe(-1),
-                        Set(1),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        ZeroAdvance(1),
-                        ZeroRetreat(1),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        Dupe(-39),
-                        Dupe(-2),
-                        ShiftRightLogical,
-                        Set(1),
-                        BasicOp(Shift(6)),
-                        BitAnd,
-                        BasicOp(Shift(-8)),
-                        ZeroRetreat(1),
-                        NotEquals,
-                        Set(32),
-                        BasicOp(Shift(1)),
-                        Set(16),
-                        BasicOp(Shift(1)),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        Set(1),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        MoveCellDynamicU8(32),
-                        Dupe(-1),
-                        Set(1),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        ZeroAdvance(1),
-                        ZeroRetreat(1),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        Dupe(-40),
-                        Dupe(-2),
-                        ShiftRightLogical,
-                        Set(1),
-                        BasicOp(Shift(6)),
-                        BitAnd,
-                        BasicOp(Shift(-8)),
-                        ZeroRetreat(1),
-                        NotEquals,
-                        Set(32),
-                        BasicOp(Shift(1)),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        Set(1),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        MoveCellDynamicU8(32),
-                        Dupe(-1),
-                        Set(1),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        ZeroAdvance(1),
-                        ZeroRetreat(1),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        Dupe(-41),
-                        Dupe(-2),
-                        ShiftRightLogical,
-                        Set(1),
-                        BasicOp(Shift(6)),
-                        BitAnd,
-                        BasicOp(Shift(-8)),
-                        ZeroRetreat(1),
-                        NotEquals,
-                        Set(32),
-                        BasicOp(Shift(1)),
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        Set(1),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        MoveCellDynamicU8(32),
-                        Dupe(-1),
-                        Set(1),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        ZeroAdvance(7),
-                        Zero,
-                        MoveCellsStaticReverse(-4, 3),
-                        MoveSet(-4),
-                        ZeroAdvance(1),
-                        ZeroRetreat(1),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(32),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        Dupe(-1),
-                        CopyCellDynamicU8(37),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(4),
-                        Dupe(-9),
-                        Dupe(-9),
-                        Dupe(-9),
-                        Dupe(-9),
-                        ZeroAdvance(4),
-                        Dupe(-53),
-                        Dupe(-53),
-                        Dupe(-53),
-                        Dupe(-53),
-                        Set(31),
-                        BasicOp(Shift(1)),
-                        Dupe(-18),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ZeroAdvance(4),
-                        Dupe(-5),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-1)),
-                        Set(1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(1),
-                        Dupe(-10),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        Dupe(-8),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftRightLogical,
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        ZeroAdvance(1),
-                        Dupe(-10),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        Dupe(-9),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftRightLogical,
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        ZeroAdvance(1),
-                        Dupe(-10),
-                        Set(8),
-                        BasicOp(Shift(1)),
-                        Dupe(-10),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftRightLogical,
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-9),
-                        Dupe(-9),
-                        ShiftLeftLogical,
-                        Dupe(-2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-4),
-                        Dupe(-10),
-                        Dupe(-9),
-                        ShiftLeftLogical,
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-5),
-                        Dupe(-11),
-                        Dupe(-9),
-                        ShiftLeftLogical,
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-6),
-                        Dupe(-12),
-                        Dupe(-9),
-                        ShiftLeftLogical,
-                        BasicOp(Shift(-1)),
-                        MoveSet(-7),
-                        BasicOp(Shift(-2)),
-                        ZeroRetreat(1),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        Dupe(-6),
-                        Set(16),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-1)),
-                        Set(1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(1),
-                        Dupe(-11),
-                        Set(16),
-                        BasicOp(Shift(1)),
-                        Dupe(-9),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftRightLogical,
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        ZeroAdvance(1),
-                        Dupe(-11),
-                        Set(16),
-                        BasicOp(Shift(1)),
-                        Dupe(-10),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftRightLogical,
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-10),
-                        Dupe(-9),
-                        Set(8),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftLeftLogical,
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-4),
-                        Dupe(-11),
-                        Dupe(-9),
-                        Set(8),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftLeftLogical,
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-5),
-                        Dupe(-12),
-                        Dupe(-9),
-                        Set(8),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftLeftLogical,
-                        BasicOp(Shift(-1)),
-                        MoveSet(-6),
-                        Zero,
-                        MoveSet(-7),
-                        BasicOp(Shift(-1)),
-                        ZeroRetreat(1),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        Dupe(-7),
-                        Set(24),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-1)),
-                        Set(1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(1),
-                        Dupe(-12),
-                        Set(24),
-                        BasicOp(Shift(1)),
-                        Dupe(-10),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftRightLogical,
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        Dupe(-11),
-                        Dupe(-9),
-                        Set(16),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftLeftLogical,
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-4),
-                        Dupe(-12),
-                        Dupe(-9),
-                        Set(16),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftLeftLogical,
-                        BasicOp(Shift(-1)),
-                        MoveSet(-5),
-                        Zero,
-                        MoveSet(-6),
-                        Zero,
-                        MoveSet(-7),
-                        ZeroRetreat(1),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        Dupe(-8),
-                        Set(32),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-1)),
-                        Set(1),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-12),
-                        Dupe(-9),
-                        Set(24),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(LoopEnd),
-                        ShiftLeftLogical,
-                        BasicOp(Shift(-1)),
-                        MoveSet(-4),
-                        Zero,
-                        MoveSet(-5),
-                        Zero,
-                        MoveSet(-6),
-                        Zero,
-                        MoveSet(-7),
-                        BasicOp(Shift(1)),
-                        ZeroRetreat(1),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(3),
-                        Zero,
-                        MoveCellsStaticReverse(-8, 3),
-                        MoveSet(-8),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-13, 4),
-                        BasicOp(Shift(-8)),
-                        ZeroAdvance(8),
-                        Dupe(-16),
-                        Dupe(-16),
-                        Dupe(-14),
-                        Dupe(-14),
-                        ZeroAdvance(2),
-                        Dupe(-6),
-                        Dupe(-6),
-                        Dupe(-6),
-                        Dupe(-6),
-                        ZeroAdvance(1),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(6)),
-                        ZeroAdvance(4),
-                        Dupe(-8),
-                        Dupe(-7),
-                        ZeroAdvance(3),
-                        Dupe(-5),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-3),
-                        Dupe(-8),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-2),
-                        Dupe(-6),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-3),
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-7, 2),
-                        BasicOp(Shift(-5)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-1),
-                        Dupe(-6),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-2),
-                        Dupe(-2),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-8, 2),
-                        BasicOp(Shift(-6)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        ZeroAdvance(4),
-                        ZeroRetreat(1),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-8),
-                        Dupe(-8),
-                        Dupe(-14),
-                        Dupe(-14),
-                        ZeroRetreat(1),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(5)),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-5),
-                        Dupe(-4),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(1),
-                        Dupe(-5),
-                        Dupe(-4),
-                        BasicOp(Shift(-2)),
-                        Equals,
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-6),
-                        Dupe(-5),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-5),
-                        BasicOp(Shift(-5)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-7),
-                        Dupe(-7),
-                        Dupe(-11),
-                        Dupe(-11),
-                        ZeroRetreat(1),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(1),
-                        BasicOp(Shift(5)),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-5),
-                        Dupe(-4),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        ZeroAdvance(1),
-                        Dupe(-5),
-                        Dupe(-4),
-                        BasicOp(Shift(-2)),
-                        Equals,
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-6),
-                        Dupe(-5),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-5),
-                        BasicOp(Shift(-5)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        Dupe(-6),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-14, 4),
-                        BasicOp(Shift(-10)),
-                        MoveCellsStaticReverse(-4, 3),
-                        MoveSet(-4),
-                        Dupe(-2),
-                        Dupe(-2),
-                        Dupe(-12),
-                        Dupe(-12),
-                        Dupe(-10),
-                        Dupe(-10),
-                        ZeroAdvance(1),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(6)),
-                        ZeroAdvance(4),
-                        Dupe(-8),
-                        Dupe(-7),
-                        ZeroAdvance(3),
-                        Dupe(-5),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-3),
-                        Dupe(-8),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-2),
-                        Dupe(-6),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-3),
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-7, 2),
-                        BasicOp(Shift(-5)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-1),
-                        Dupe(-6),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-2),
-                        Dupe(-2),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-8, 2),
-                        BasicOp(Shift(-5)),
-                        ZeroAdvance(1),
-                        Zero,
-                        BasicOp(Shift(-2)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(2),
-                        BasicOp(Shift(6)),
-                        ZeroAdvance(4),
-                        Dupe(-8),
-                        Dupe(-7),
-                        ZeroAdvance(3),
-                        Dupe(-5),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-3),
-                        ZeroAdvance(1),
-                        Set(1),
-                        BasicOp(Shift(1)),
-                        Dupe(-3),
-                        Dupe(-8),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-2)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        BasicOp(ChangeBy(255)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        BasicOp(LoopStart),
-                        Dupe(-2),
-                        Dupe(-6),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopStart),
-                        BasicOp(Shift(-1)),
-                        BasicOp(ChangeBy(1)),
-                        BasicOp(Shift(1)),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-3),
-                        Dupe(-3),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-7, 2),
-                        BasicOp(Shift(-5)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-1),
-                        Dupe(-6),
-                        Dupe(-5),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-1)),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-2),
-                        Dupe(-2),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-8, 2),
-                        BasicOp(Shift(-6)),
-                        MoveSet(-2),
-                        BasicOp(Shift(-1)),
-                        MoveSet(-2),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-16, 4),
-                        BasicOp(Shift(-12)),
-                        MoveCellsStaticReverse(-5, 3),
-                        MoveSet(-5),
-                        Zero,
-                        BasicOp(LoopEnd),
-                        Dupe(-1),
-                        Set(1),
-                        MoveAdd(-1),
-                        BasicOp(Shift(-2)),
-                        ZeroAdvance(1),
-                        MoveAdd(-1),
-                        Dupe(-1),
-                        Set(32),
-                        BasicOp(Shift(1)),
-                        LessThan,
-                        BasicOp(Shift(-2)),
-                        BasicOp(LoopEnd),
-                        BasicOp(Shift(-1)),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        Dupe(-4),
-                        BasicOp(Shift(-1)),
-                        MoveCellsStaticReverse(-48, 4),
-                        BasicOp(Shift(neg_44_plus_extra_shift)),
-                    ] if *three_plus_extra_advance >= 3 => {
-                        let extra_advance = *three_plus_extra_advance - 3;
-                        let extra_shift = *neg_44_plus_extra_shift + 44;
+                let iterations = inverse(step).wrapping_neg();
+                let mut targets: Vec<(i64, u8)> = changes
+                    .into_iter()
+                    .filter(|&(offset, change)| offset != 0 && change != 0)
+                    .map(|(offset, change)| (offset, change.wrapping_mul(iterations)))
+                    .collect();
+                targets.sort_unstable();
 
-                        self.building.truncate_back(self.building.len() - 1009);
-
-                        if extra_advance != 0 {
-                            self.building.push_back(ZeroAdvance(extra_advance));
-                        }
-
-                        self.building.push_back(MulU32);
+                let op = if targets.is_empty() {
+                    CompoundOp::Zero
+                } else {
+                    CompoundOp::MulAddMany(targets)
+                };
 
-                        if extra_shift != 0 {
-                            self.building.push_back(BasicOp(Shift(extra_shift)));
-                        }
-                    }
-                    _ => (),
-                }
+                return Some((i + 1, op));
             }
-            _ => (),
-        }
-
-        if self.building.len() > WINDOW_SIZE {
-            self.building.pop_front()
-        } else {
-            None
+            _ => return None,
         }
     }
 
-    pub fn finalize(&mut self) -> Option<CompoundOp> {
-        self.building.pop_front()
+    None
+}
+
+/// Multiplicative inverse of an odd number modulo 256
+fn inverse(value: u8) -> u8 {
+    // Every iteration of Newton's method doubles the number of correct low bits, starting from 3
+    (0..3).fold(value, |inverse, _| {
+        inverse.wrapping_mul(2u8.wrapping_sub(value.wrapping_mul(inverse)))
+    })
+}
+
+impl Default for CompoundOpAcc {
+    fn default() -> Self {
+        Self::new()
     }
 }
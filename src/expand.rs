@@ -0,0 +1,350 @@
+use crate::{basic_op::BasicOp, compound_op::CompoundOp, rules};
+
+/// Values of the variables in a rule's patterns
+pub type Bindings = [(&'static str, i64)];
+
+/// Ops recognised as `op`, built from the patterns of the rule that produces it, or `None` if no rule does
+///
+/// The variables of the patterns are bound so the rule leaves nothing else behind, such as extra shifts. Ops
+/// that stand for runs, such as `ZeroAdvance` and `PrintStatic`, are expanded into the run of ops they're
+/// merged from.
+pub fn expand(op: &CompoundOp) -> Option<Vec<CompoundOp>> {
+    let (name, bindings) = match op {
+        CompoundOp::ZeroAdvance(amount) if *amount > 1 => {
+            return Some(vec![CompoundOp::ZeroAdvance(1); *amount as usize]);
+        }
+        CompoundOp::ZeroRetreat(amount) if *amount > 1 => {
+            return Some(vec![CompoundOp::ZeroRetreat(1); *amount as usize]);
+        }
+        CompoundOp::PrintStatic(content) if content.iter().any(|&letter| letter != content[0]) => {
+            return Some(print_static(content));
+        }
+        CompoundOp::Panic(value) => ("panic_loop", vec![("value", *value as i64)]),
+        CompoundOp::Zero => ("zero", vec![]),
+        CompoundOp::ZeroAdvance(_) => ("zero_advance", vec![]),
+        CompoundOp::ZeroRetreat(_) => ("zero_retreat", vec![]),
+        CompoundOp::Set(value) => ("set", vec![("value", *value as i64)]),
+        CompoundOp::PrintStatic(content) => (
+            "print_static",
+            vec![
+                ("initial_letter", *content.first()? as i64),
+                ("letter_count", content.len() as i64),
+            ],
+        ),
+        CompoundOp::ScanRight(stride) => ("scan_right", vec![("stride", *stride as i64)]),
+        CompoundOp::ScanLeft(stride) => ("scan_left", vec![("stride", -(*stride as i64))]),
+        CompoundOp::MoveAdd(offset) => ("move_add", vec![("toward_amount", *offset), ("back_amount", -offset)]),
+        CompoundOp::MoveAdd2(offset1, offset2) => (
+            "move_add_2",
+            vec![
+                ("toward_amount1", *offset1),
+                ("toward_amount2", offset2 - offset1),
+                ("back_amount", -offset2),
+            ],
+        ),
+        CompoundOp::MoveSet(offset) => (
+            "move_set",
+            vec![
+                ("toward_amount_plus_extra", *offset),
+                ("back_amount", -offset),
+                ("move_add_toward_amount", *offset),
+            ],
+        ),
+        CompoundOp::Dupe(offset) => (
+            "dupe",
+            vec![
+                ("advance_amount", 1),
+                ("toward_shift", offset - 1),
+                ("offset", -offset),
+                ("offset_plus_1", 1 - offset),
+                ("back_shift", 1 - offset),
+                ("return_shift", offset - 1),
+            ],
+        ),
+        CompoundOp::MoveCellsStaticReverse(offset, 2) => (
+            "move_cells_static_reverse",
+            vec![
+                ("offset", *offset),
+                ("offset_again", *offset),
+                ("negative_1_plus_extra", -1),
+            ],
+        ),
+        CompoundOp::MoveCellsStaticReverse(offset, count) if *count > 2 => (
+            "move_cells_static_reverse_extend",
+            vec![
+                ("offset", *offset),
+                ("count", *count as i64 - 1),
+                ("offset_again", *offset),
+                ("negative_1_plus_extra", -1),
+            ],
+        ),
+        CompoundOp::Equals => ("equals", vec![]),
+        CompoundOp::NotEquals => ("not_equals", vec![]),
+        CompoundOp::ShiftLeftLogical => ("shift_left_logical", vec![]),
+        CompoundOp::ShiftRightLogical => ("shift_right_logical", vec![]),
+        CompoundOp::LessThan => ("less_than", vec![("zero_advance_amount", 1)]),
+        CompoundOp::GreaterThan => ("greater_than", vec![("zero_advance_amount", 1)]),
+        CompoundOp::LessThanEqual => ("less_than_equal", vec![]),
+        CompoundOp::GreaterThanEqual => ("greater_than_equal", vec![]),
+        CompoundOp::BitAnd => ("bit_and", vec![]),
+        CompoundOp::BitNeg => ("bit_neg", vec![]),
+        CompoundOp::WellBehavedDivMod(shift_amount) => ("div_mod", vec![("neg_5_plus_extra", shift_amount - 3)]),
+        CompoundOp::MoveCellDynamicU8(offset) => (
+            "move_cell_dynamic_u8",
+            vec![("neg_offset", -(*offset as i64) - 1), ("neg_2_plus_extra", -2)],
+        ),
+        CompoundOp::MoveCellDynamicU16(offset) => (
+            "move_cell_dynamic_u16",
+            vec![
+                ("six_plus_extra", 6),
+                ("neg_offset_and_2", 1 - *offset as i64),
+                ("neg_three_plus_extra", -3),
+            ],
+        ),
+        CompoundOp::MoveCellDynamicU32(offset) => (
+            "move_cell_dynamic_u32",
+            vec![
+                ("amount_plus_11", 11),
+                ("negative_offset_plus_sizeof_index_which_is_4", 1 - *offset as i64),
+                ("extra_plus_negative_seven", -7),
+            ],
+        ),
+        CompoundOp::CopyCellDynamicU8(offset) => {
+            let offset = *offset as i64;
+
+            (
+                "copy_cell_dynamic_u8",
+                vec![
+                    ("neg_2_plus_neg_offset", -offset - 2),
+                    ("move_offset", offset),
+                    ("pos_2_plus_pos_offset", offset + 2),
+                    ("shift_pos_2_plus_pos_offset", offset + 2),
+                    ("move_neg_2_plus_neg_offset", -offset - 2),
+                ],
+            )
+        }
+        CompoundOp::CopyCellDynamicU32(offset) => {
+            let pos_offset = *offset as i64 - 4;
+
+            (
+                "copy_cell_dynamic_u32",
+                vec![
+                    ("seven_plus_extra", 7),
+                    ("neg_offset", -pos_offset),
+                    ("pos_offset", pos_offset),
+                    ("neg_offset_and_1", -pos_offset - 1),
+                    ("pos_offset_and_1", pos_offset + 1),
+                    ("neg_offset_and_1_second", -pos_offset - 1),
+                    ("negative_3_plus_extra", -3),
+                ],
+            )
+        }
+        CompoundOp::AddU32 => (
+            "add_u32",
+            vec![("three_plus_extra_advance", 3), ("neg_twelve_plus_extra_shift", -12)],
+        ),
+        CompoundOp::MulU32 => (
+            "mul_u32",
+            vec![("three_plus_extra_advance", 3), ("neg_44_plus_extra_shift", -44)],
+        ),
+        CompoundOp::SubU32 => (
+            "sub_u32",
+            vec![("six_plus_extra_shift", 6), ("neg_five_plus_extra_shift", -5)],
+        ),
+        CompoundOp::LessThanU32 => (
+            "less_than_u32",
+            vec![("six_plus_extra_shift", 6), ("neg_five_plus_extra_shift", -5)],
+        ),
+        CompoundOp::EqualsU32 => (
+            "equals_u32",
+            vec![("neg_four_plus_extra_shift", -4), ("neg_five_plus_extra_shift", -5)],
+        ),
+        CompoundOp::AndU32 => (
+            "and_u32",
+            vec![("ten_plus_extra_shift", 10), ("neg_fifteen_plus_extra_shift", -15)],
+        ),
+        CompoundOp::OrU32 => (
+            "or_u32",
+            vec![("ten_plus_extra_shift", 10), ("neg_fifteen_plus_extra_shift", -15)],
+        ),
+        CompoundOp::XorU32 => (
+            "xor_u32",
+            vec![("ten_plus_extra_shift", 10), ("neg_fifteen_plus_extra_shift", -15)],
+        ),
+        CompoundOp::ShiftLeftU32 => (
+            "shift_left_u32",
+            vec![("neg_three_plus_extra_shift", -3), ("neg_one_plus_extra_shift", -1)],
+        ),
+        CompoundOp::ShiftRightU32 => (
+            "shift_right_u32",
+            vec![("neg_three_plus_extra_shift", -3), ("neg_one_plus_extra_shift", -1)],
+        ),
+        CompoundOp::DivModU32 => (
+            "div_mod_u32",
+            vec![
+                ("twenty_three_plus_extra_shift", 23),
+                ("neg_eight_plus_extra_shift", -8),
+            ],
+        ),
+        _ => return None,
+    };
+
+    Some(instantiate(rules::find(name)?.patterns, &bindings))
+}
+
+/// Prints the first run of letters and changes the cell to each following run
+fn print_static(content: &[u8]) -> Vec<CompoundOp> {
+    let mut ops = Vec::new();
+    let mut previous = None;
+
+    for run in content.chunk_by(|a, b| a == b) {
+        ops.push(match previous {
+            None => CompoundOp::Set(run[0]),
+            Some(letter) => CompoundOp::BasicOp(BasicOp::ChangeBy(run[0].wrapping_sub(letter))),
+        });
+        ops.push(CompoundOp::BasicOp(BasicOp::Output(run.len() as u64)));
+        previous = Some(run[0]);
+    }
+
+    ops
+}
+
+/// Ops matching the patterns, with their variables bound to the values given and the first of any alternatives
+/// taken
+///
+/// Panics on patterns it can't parse, which the tests check every rule's patterns aren't.
+pub fn instantiate(patterns: &[&str], bindings: &Bindings) -> Vec<CompoundOp> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let source: String = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+            let mut rest = source.as_str();
+            let node = parse(&mut rest, bindings);
+            assert!(rest.is_empty(), "couldn't parse pattern {}", pattern);
+            compound_op(&node)
+        })
+        .collect()
+}
+
+enum Node {
+    Op(String, Vec<Node>),
+    Value(i64),
+}
+
+/// Parses the first alternative of a pattern, consuming it from `rest`
+fn parse(rest: &mut &str, bindings: &Bindings) -> Node {
+    let node = parse_alternative(rest, bindings);
+
+    while let Some(after) = rest.strip_prefix('|') {
+        *rest = after;
+        parse_alternative(rest, bindings);
+    }
+
+    node
+}
+
+fn parse_alternative(rest: &mut &str, bindings: &Bindings) -> Node {
+    let len = rest
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_' || *c == ':'))
+        .map_or(rest.len(), |(i, _)| i);
+    let word = &rest[..len];
+    *rest = &rest[len..];
+
+    if let Ok(value) = word.parse() {
+        return Node::Value(value);
+    }
+
+    if word == "u8::MAX" {
+        return Node::Value(u8::MAX as i64);
+    }
+
+    if word.starts_with(char::is_lowercase) {
+        let (_, value) = bindings
+            .iter()
+            .find(|(name, _)| *name == word)
+            .unwrap_or_else(|| panic!("no binding for {}", word));
+        return Node::Value(*value);
+    }
+
+    let mut args = Vec::new();
+
+    if let Some(after) = rest.strip_prefix('(') {
+        *rest = after;
+
+        loop {
+            args.push(parse(rest, bindings));
+
+            let (separator, after) = rest.split_at(1);
+            *rest = after;
+
+            if separator == ")" {
+                break;
+            }
+        }
+    }
+
+    Node::Op(word.trim_start_matches("CompoundOp::").to_string(), args)
+}
+
+fn compound_op(node: &Node) -> CompoundOp {
+    let Node::Op(name, args) = node else {
+        panic!("expected an op");
+    };
+    let value = |i: usize| match args[i] {
+        Node::Value(value) => value,
+        Node::Op(..) => panic!("expected a value"),
+    };
+
+    match name.as_str() {
+        "BasicOp" => CompoundOp::BasicOp(basic_op(&args[0])),
+        "Zero" => CompoundOp::Zero,
+        "ZeroAdvance" => CompoundOp::ZeroAdvance(value(0) as u64),
+        "ZeroRetreat" => CompoundOp::ZeroRetreat(value(0) as u64),
+        "Set" => CompoundOp::Set(value(0) as u8),
+        "MoveAdd" => CompoundOp::MoveAdd(value(0)),
+        "MoveAdd2" => CompoundOp::MoveAdd2(value(0), value(1)),
+        "MoveSet" => CompoundOp::MoveSet(value(0)),
+        "Dupe" => CompoundOp::Dupe(value(0)),
+        "Equals" => CompoundOp::Equals,
+        "NotEquals" => CompoundOp::NotEquals,
+        "ShiftLeftLogical" => CompoundOp::ShiftLeftLogical,
+        "ShiftRightLogical" => CompoundOp::ShiftRightLogical,
+        "LessThan" => CompoundOp::LessThan,
+        "GreaterThan" => CompoundOp::GreaterThan,
+        "LessThanEqual" => CompoundOp::LessThanEqual,
+        "GreaterThanEqual" => CompoundOp::GreaterThanEqual,
+        "BitAnd" => CompoundOp::BitAnd,
+        "BitNeg" => CompoundOp::BitNeg,
+        "PrintStatic" => CompoundOp::PrintStatic(vec![value(0) as u8]),
+        "MoveCellDynamicU8" => CompoundOp::MoveCellDynamicU8(value(0) as u64),
+        "CopyCellDynamicU8" => CompoundOp::CopyCellDynamicU8(value(0) as u64),
+        "MoveCellsStaticReverse" => CompoundOp::MoveCellsStaticReverse(value(0), value(1) as u64),
+        "ScanRight" => CompoundOp::ScanRight(value(0) as u64),
+        "ScanLeft" => CompoundOp::ScanLeft(value(0) as u64),
+        "SubU32" => CompoundOp::SubU32,
+        "LessThanU32" => CompoundOp::LessThanU32,
+        name => panic!("unknown op {}", name),
+    }
+}
+
+fn basic_op(node: &Node) -> BasicOp {
+    let Node::Op(name, args) = node else {
+        panic!("expected a basic op");
+    };
+    let value = || match args[0] {
+        Node::Value(value) => value,
+        Node::Op(..) => panic!("expected a value"),
+    };
+
+    match name.as_str() {
+        "LoopStart" => BasicOp::LoopStart,
+        "LoopEnd" => BasicOp::LoopEnd,
+        "Shift" => BasicOp::Shift(value()),
+        "ChangeBy" => BasicOp::ChangeBy(value() as u8),
+        "Output" => BasicOp::Output(value() as u64),
+        "Input" => BasicOp::Input(value() as u64),
+        name => panic!("unknown basic op {}", name),
+    }
+}
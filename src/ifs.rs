@@ -0,0 +1,101 @@
+use crate::{
+    basic_op::BasicOp,
+    block::{Block, Loop, Node},
+    compound_op::CompoundOp,
+    interpreter_op::InterpreterOp,
+    partial_eval::reach,
+};
+use anyhow::Result;
+
+/// Turns the loops whose body always leaves the cell they test zero into ifs, which only test the cell on the
+/// way in since the loop could never run twice
+///
+/// Only balanced bodies are looked at, following the pointer through them to the last op that writes the
+/// tested cell. The cell counts as left zero if that op zeroes it, or if it's a loop on that cell, as loops
+/// only finish on a zero cell.
+pub fn ifs(ops: &[InterpreterOp]) -> Result<Vec<InterpreterOp>> {
+    Ok(convert(Block::from_ops(ops)?).to_ops())
+}
+
+fn convert(block: Block) -> Block {
+    let nodes = block.nodes.into_iter().map(|node| match node {
+        Node::Op(op) => Node::Op(op),
+        Node::Loop(inner) => {
+            let inner = Loop::new(convert(inner.body));
+
+            if clears(&inner) {
+                Node::If(inner)
+            } else {
+                Node::Loop(inner)
+            }
+        }
+        Node::If(inner) => Node::If(Loop::new(convert(inner.body))),
+    });
+
+    Block { nodes: nodes.collect() }
+}
+
+/// Whether every run of the body ends on the cell the loop tests, having left it zero
+fn clears(inner: &Loop) -> bool {
+    if !inner.balanced {
+        return false;
+    }
+
+    // Where the pointer is relative to the tested cell
+    let mut offset = 0;
+    let mut zero = false;
+
+    for node in &inner.body.nodes {
+        match node {
+            Node::Op(op) => {
+                if zeroes(op, -offset) {
+                    zero = true;
+                } else if writes(op, -offset) {
+                    zero = false;
+                }
+
+                match op.shift() {
+                    Some(shift) => offset += shift,
+                    None => return false,
+                }
+            }
+            // The body being balanced, so are the loops in it
+            Node::Loop(_) | Node::If(_) => zero = offset == 0,
+        }
+    }
+
+    zero
+}
+
+/// Whether the op leaves the cell at `at` from the pointer zero
+fn zeroes(op: &CompoundOp, at: i64) -> bool {
+    match op {
+        CompoundOp::Zero
+        | CompoundOp::Set(0)
+        | CompoundOp::MoveAdd(_)
+        | CompoundOp::MoveAdd2(..)
+        | CompoundOp::MoveSet(_)
+        | CompoundOp::MulAddMany(_) => at == 0,
+        CompoundOp::SetAt(offset, 0) => at == *offset,
+        CompoundOp::MoveAddAt(from, to) | CompoundOp::MoveSetAt(from, to) => at == *from && at != *to,
+        CompoundOp::MoveAdd2At(from, to1, to2) => at == *from && at != *to1 && at != *to2,
+        CompoundOp::MulAddManyAt(from, targets) => at == *from && targets.iter().all(|&(to, _)| to != at),
+        CompoundOp::ZeroAdvance(amount) => (0..*amount as i64).contains(&at),
+        CompoundOp::ZeroRetreat(amount) => (0..*amount as i64).contains(&-at),
+        CompoundOp::SetCells(offset, values) => usize::try_from(at - offset)
+            .ok()
+            .and_then(|i| values.get(i))
+            .is_some_and(|&value| value == 0),
+        _ => false,
+    }
+}
+
+/// Whether the op might change the cell at `at` from the pointer
+fn writes(op: &CompoundOp, at: i64) -> bool {
+    match op {
+        CompoundOp::BasicOp(BasicOp::Shift(_) | BasicOp::Output(_))
+        | CompoundOp::OutputAt(..)
+        | CompoundOp::PrintStatic(_) => false,
+        _ => reach(op).is_none_or(|(lowest, highest)| (lowest..=highest).contains(&at)),
+    }
+}
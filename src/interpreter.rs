@@ -1,30 +1,150 @@
 use std::io::Read;
 
-use crate::{basic_op::BasicOp, compound_op::CompoundOp, interpreter_op::InterpreterOp};
-use std::io::Write;
+use crate::{basic_op::BasicOp, compound_op::CompoundOp, interpreter_op::InterpreterOp, partial_eval};
+use anyhow::{anyhow, Result};
+use std::io::{BufWriter, Write};
 
 pub const CELL_COUNT: usize = 25_000_000;
 
+/// What an input instruction stores into the current cell once stdin is exhausted
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EofBehavior {
+    Zero,
+    Unchanged,
+    Max,
+}
+
+impl EofBehavior {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "zero" => Some(Self::Zero),
+            "unchanged" => Some(Self::Unchanged),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, current: u8) -> u8 {
+        match self {
+            Self::Zero => 0,
+            Self::Unchanged => current,
+            Self::Max => u8::MAX,
+        }
+    }
+}
+
+/// Runtime configuration of the tape and I/O, honoured by the interpreter and the backends that support it
+#[derive(Copy, Clone, Debug)]
+pub struct RuntimeOptions {
+    pub bounds_checks: bool,
+    pub eof_behavior: EofBehavior,
+    pub tape_size: usize,
+    pub tape_origin: usize,
+    /// Only flush output when reading input or exiting instead of after every write
+    pub buffered_output: bool,
+}
+
+impl Default for RuntimeOptions {
+    fn default() -> Self {
+        Self {
+            bounds_checks: false,
+            eof_behavior: EofBehavior::Zero,
+            tape_size: CELL_COUNT,
+            tape_origin: 0,
+            buffered_output: false,
+        }
+    }
+}
+
+impl RuntimeOptions {
+    /// Whether the tape differs from the fixed one every backend supports
+    pub fn custom_tape(&self) -> bool {
+        self.tape_size != CELL_COUNT || self.tape_origin != 0
+    }
+}
+
 pub struct Interpreter<'ops> {
     ops: &'ops [InterpreterOp],
+    options: RuntimeOptions,
 }
 
 impl<'ops> Interpreter<'ops> {
-    pub fn new(ops: &'ops [InterpreterOp]) -> Self {
-        Self { ops }
+    pub fn new(ops: &'ops [InterpreterOp], options: RuntimeOptions) -> Self {
+        Self { ops, options }
     }
 
+    /// Runs the program on a fresh tape, returning the exit code the process should finish with
+    ///
+    /// That's the panic code when the program entered a panic loop, after reporting it, and 0 otherwise.
     #[profiling::function]
-    pub unsafe fn interpret<const BOUNDS_CHECKS: bool>(&self) {
-        let mut cells = vec![0u8; CELL_COUNT];
+    pub unsafe fn interpret<const BOUNDS_CHECKS: bool>(&self) -> Result<i32> {
+        let mut cells = vec![0u8; self.options.tape_size];
+        let mut output = BufWriter::new(std::io::stdout().lock());
+
+        let (instr_i, _) =
+            self.execute::<BOUNDS_CHECKS, false>(&mut cells, self.options.tape_origin, &mut output, usize::MAX)?;
+
+        match self.ops.get(instr_i) {
+            Some(InterpreterOp::CompoundOp(CompoundOp::Panic(value))) => Ok(*value as i32),
+            _ => Ok(0),
+        }
+    }
+
+    /// Runs the ops against the given tape starting at `cell_i`, returning the cell the program stopped on
+    ///
+    /// With bounds checks, leaving the tape is reported as an error instead of running on.
+    ///
+    /// # Safety
+    ///
+    /// Without bounds checks, the program must stay within the tape.
+    pub unsafe fn run<const BOUNDS_CHECKS: bool>(
+        &self,
+        cells: &mut [u8],
+        cell_i: usize,
+        output: &mut impl Write,
+    ) -> Result<usize> {
+        Ok(self
+            .execute::<BOUNDS_CHECKS, false>(cells, cell_i, output, usize::MAX)?
+            .1)
+    }
+
+    /// Runs the ops from the start until the next op can't be evaluated ahead of time or after `max_steps` ops,
+    /// returning the index of the op it stopped at and the cell it stopped on
+    pub fn run_prefix(
+        &self,
+        cells: &mut [u8],
+        cell_i: usize,
+        output: &mut Vec<u8>,
+        max_steps: usize,
+    ) -> Result<(usize, usize)> {
+        // Every op is checked to stay on the tape before it's run
+        unsafe { self.execute::<true, true>(cells, cell_i, output, max_steps) }
+    }
+
+    #[allow(clippy::identity_op)]
+    unsafe fn execute<const BOUNDS_CHECKS: bool, const PREFIX: bool>(
+        &self,
+        cells: &mut [u8],
+        mut cell_i: usize,
+        output: &mut impl Write,
+        max_steps: usize,
+    ) -> Result<(usize, usize)> {
         let mut instr_i: usize = 0;
-        let mut cell_i: usize = 0;
+        let mut steps = 0;
 
         while instr_i < self.ops.len() {
+            if PREFIX {
+                if steps == max_steps || !partial_eval::can_evaluate(&self.ops[instr_i], cells, cell_i) {
+                    break;
+                }
+
+                steps += 1;
+            }
+
             match &self.ops[instr_i] {
                 InterpreterOp::LoopStart(distance) => {
                     profiling::scope!("LoopStart");
-                    if *get::<BOUNDS_CHECKS>(&cells, cell_i) == 0 {
+                    if *get::<BOUNDS_CHECKS>(cells, cell_i)? == 0 {
                         instr_i += distance;
                     } else {
                         instr_i += 1;
@@ -32,16 +152,27 @@ impl<'ops> Interpreter<'ops> {
                 }
                 InterpreterOp::LoopEnd(distance) => {
                     profiling::scope!("LoopEnd");
-                    if *get::<BOUNDS_CHECKS>(&cells, cell_i) != 0 {
+                    if *get::<BOUNDS_CHECKS>(cells, cell_i)? != 0 {
                         instr_i -= distance;
                     } else {
                         instr_i += 1;
                     }
                 }
+                InterpreterOp::IfStart(distance) => {
+                    profiling::scope!("IfStart");
+                    if *get::<BOUNDS_CHECKS>(cells, cell_i)? == 0 {
+                        instr_i += distance + 1;
+                    } else {
+                        instr_i += 1;
+                    }
+                }
+                InterpreterOp::IfEnd(_) => {
+                    instr_i += 1;
+                }
                 InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::ChangeBy(amount))) => {
                     profiling::scope!("ChangeBy");
-                    let new_value = (*get::<BOUNDS_CHECKS>(&cells, cell_i)).wrapping_add(*amount);
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = new_value;
+                    let new_value = (*get::<BOUNDS_CHECKS>(cells, cell_i)?).wrapping_add(*amount);
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? = new_value;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Shift(amount))) => {
@@ -52,50 +183,37 @@ impl<'ops> Interpreter<'ops> {
                 InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Input(count))) => {
                     profiling::scope!("Input");
                     for _ in 0..*count {
-                        *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = Self::input();
+                        let cell = get_mut::<BOUNDS_CHECKS>(cells, cell_i)?;
+                        *cell = self.input(output, *cell);
                     }
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Output(count))) => {
                     profiling::scope!("Output");
                     for _ in 0..*count {
-                        let cell_value = *get::<BOUNDS_CHECKS>(&cells, cell_i);
-                        Self::output(&[cell_value]);
+                        let cell_value = *get::<BOUNDS_CHECKS>(cells, cell_i)?;
+                        self.output(output, &[cell_value]);
                     }
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::LoopStart | BasicOp::LoopEnd)) => {
                     eprintln!("[error] Cannot execute unprocessed loop instruction");
-                    return;
+                    return Ok((instr_i, cell_i));
                 }
                 InterpreterOp::CompoundOp(CompoundOp::Panic(value)) => {
-                    eprintln!(
-                        "[PANIC] Program entered panic loop with error code {}, instr_i = {}, cell_i = {}",
-                        value, instr_i, cell_i
-                    );
-                    eprintln!("Memory before panic:");
-                    let forward_range = 20;
-                    let back_range = 20;
-                    let start = if cell_i >= back_range { cell_i - back_range } else { 0 };
-                    let end = if cell_i + forward_range < CELL_COUNT {
-                        cell_i + forward_range
-                    } else {
-                        CELL_COUNT
-                    };
-                    for i in start..end {
-                        eprintln!("cell {} is {}", i, cells[i]);
-                    }
-                    return;
+                    _ = output.flush();
+                    report_panic(cells, *value, instr_i, cell_i);
+                    return Ok((instr_i, cell_i));
                 }
                 InterpreterOp::CompoundOp(CompoundOp::Zero) => {
                     profiling::scope!("Zero");
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? = 0;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::ZeroAdvance(amount)) => {
                     profiling::scope!("ZeroAdvance");
                     for _ in 0..*amount {
-                        *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = 0;
+                        *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? = 0;
                         cell_i += 1;
                     }
                     instr_i += 1;
@@ -103,23 +221,23 @@ impl<'ops> Interpreter<'ops> {
                 InterpreterOp::CompoundOp(CompoundOp::ZeroRetreat(amount)) => {
                     profiling::scope!("ZeroRetreat");
                     for _ in 0..*amount {
-                        *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = 0;
+                        *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? = 0;
                         cell_i -= 1;
                     }
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::Set(value)) => {
                     profiling::scope!("Set");
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = *value;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? = *value;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::Dupe(offset)) => {
                     profiling::scope!("Dupe");
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) =
-                        *get::<BOUNDS_CHECKS>(&cells, (cell_i as i64 + *offset) as usize);
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? =
+                        *get::<BOUNDS_CHECKS>(cells, (cell_i as i64 + *offset) as usize)?;
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 1)? = 0;
 
                     cell_i += 1;
                     instr_i += 1;
@@ -132,29 +250,32 @@ impl<'ops> Interpreter<'ops> {
 
                     profiling::scope!("BitAnd");
 
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i - 7);
-                    let b = *get::<BOUNDS_CHECKS>(&cells, cell_i - 6);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i - 7)?;
+                    let b = *get::<BOUNDS_CHECKS>(cells, cell_i - 6)?;
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 7) = a & b;
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 6) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 7)? = a & b;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 6)? = 0;
                     cell_i += 2;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::WellBehavedDivMod(shift_amount)) => {
                     profiling::scope!("WellBehavedDivMod");
-                    let n = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let d = *get::<BOUNDS_CHECKS>(&cells, cell_i - 1);
+                    let n = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let d = *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?;
 
-                    let (n_div_d, n_mod_d) = if d == 0 { (0, 0) } else { (n / d, n % d) };
+                    let (n_div_d, n_mod_d) = match n.checked_div(d) {
+                        Some(n_div_d) => (n_div_d, n % d),
+                        None => (0, 0),
+                    };
 
                     // Optionally check boundries (lower already checked)
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 3) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 3)? = 0;
 
-                    *get_mut::<false>(&mut cells, cell_i - 2) = 0;
-                    *get_mut::<false>(&mut cells, cell_i - 1) = d.wrapping_sub(n_mod_d);
-                    *get_mut::<false>(&mut cells, cell_i + 0) = n_mod_d;
-                    *get_mut::<false>(&mut cells, cell_i + 1) = n_div_d;
-                    *get_mut::<false>(&mut cells, cell_i + 2) = 0;
+                    *get_mut::<false>(cells, cell_i - 2)? = 0;
+                    *get_mut::<false>(cells, cell_i - 1)? = d.wrapping_sub(n_mod_d);
+                    *get_mut::<false>(cells, cell_i + 0)? = n_mod_d;
+                    *get_mut::<false>(cells, cell_i + 1)? = n_div_d;
+                    *get_mut::<false>(cells, cell_i + 2)? = 0;
 
                     cell_i = (cell_i as i64 + shift_amount) as usize;
                     instr_i += 1;
@@ -162,10 +283,10 @@ impl<'ops> Interpreter<'ops> {
                 InterpreterOp::CompoundOp(CompoundOp::BitNeg) => {
                     profiling::scope!("BitNeg");
 
-                    let value = *get::<BOUNDS_CHECKS>(&cells, cell_i);
+                    let value = *get::<BOUNDS_CHECKS>(cells, cell_i)?;
 
-                    *get_mut::<false>(&mut cells, cell_i) = !value;
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<false>(cells, cell_i)? = !value;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 1)? = 0;
 
                     cell_i += 1;
                     instr_i += 1;
@@ -173,11 +294,11 @@ impl<'ops> Interpreter<'ops> {
                 InterpreterOp::CompoundOp(CompoundOp::Equals) => {
                     profiling::scope!("Equals");
 
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i);
-                    let b = *get::<BOUNDS_CHECKS>(&cells, cell_i + 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i)?;
+                    let b = *get::<BOUNDS_CHECKS>(cells, cell_i + 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i) = (a == b) as u8;
-                    *get_mut::<false>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<false>(cells, cell_i)? = (a == b) as u8;
+                    *get_mut::<false>(cells, cell_i + 1)? = 0;
 
                     cell_i += 1;
                     instr_i += 1;
@@ -185,11 +306,11 @@ impl<'ops> Interpreter<'ops> {
                 InterpreterOp::CompoundOp(CompoundOp::NotEquals) => {
                     profiling::scope!("NotEquals");
 
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i);
-                    let b = *get::<BOUNDS_CHECKS>(&cells, cell_i + 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i)?;
+                    let b = *get::<BOUNDS_CHECKS>(cells, cell_i + 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i) = (a != b) as u8;
-                    *get_mut::<false>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<false>(cells, cell_i)? = (a != b) as u8;
+                    *get_mut::<false>(cells, cell_i + 1)? = 0;
 
                     cell_i += 1;
                     instr_i += 1;
@@ -197,12 +318,12 @@ impl<'ops> Interpreter<'ops> {
                 InterpreterOp::CompoundOp(CompoundOp::ShiftLeftLogical) => {
                     profiling::scope!("ShiftLeftLogical");
 
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let b = *get::<BOUNDS_CHECKS>(&cells, cell_i - 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let b = *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i - 2) = if b >= 8 { 0 } else { a << b };
-                    *get_mut::<false>(&mut cells, cell_i - 1) = 0;
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = 0;
+                    *get_mut::<false>(cells, cell_i - 2)? = if b >= 8 { 0 } else { a << b };
+                    *get_mut::<false>(cells, cell_i - 1)? = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? = 0;
 
                     cell_i -= 1;
                     instr_i += 1;
@@ -211,17 +332,17 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("ShiftRightLogical");
 
                     // Optionally check upper bound
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 3) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 3)? = 0;
 
                     // Optionally check lower bound
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let b = *get::<false>(&cells, cell_i - 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let b = *get::<false>(cells, cell_i - 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i - 2) = if b >= 8 { 0 } else { a >> b };
-                    *get_mut::<false>(&mut cells, cell_i - 1) = 0;
-                    *get_mut::<false>(&mut cells, cell_i) = 0;
-                    *get_mut::<false>(&mut cells, cell_i + 1) = 0;
-                    *get_mut::<false>(&mut cells, cell_i + 2) = 0;
+                    *get_mut::<false>(cells, cell_i - 2)? = if b >= 8 { 0 } else { a >> b };
+                    *get_mut::<false>(cells, cell_i - 1)? = 0;
+                    *get_mut::<false>(cells, cell_i)? = 0;
+                    *get_mut::<false>(cells, cell_i + 1)? = 0;
+                    *get_mut::<false>(cells, cell_i + 2)? = 0;
 
                     cell_i -= 1;
                     instr_i += 1;
@@ -230,15 +351,15 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("LessThan");
 
                     // Optionally check upper bound
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 1)? = 0;
 
                     // Optionally check lower bound
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let b = *get::<false>(&cells, cell_i - 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let b = *get::<false>(cells, cell_i - 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i - 2) = (a < b) as u8;
-                    *get_mut::<false>(&mut cells, cell_i - 1) = 0;
-                    *get_mut::<false>(&mut cells, cell_i + 0) = 0;
+                    *get_mut::<false>(cells, cell_i - 2)? = (a < b) as u8;
+                    *get_mut::<false>(cells, cell_i - 1)? = 0;
+                    *get_mut::<false>(cells, cell_i + 0)? = 0;
 
                     instr_i += 1;
                 }
@@ -246,15 +367,15 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("GreaterThan");
 
                     // Optionally check upper bound
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 1)? = 0;
 
                     // Optionally check lower bound
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let b = *get::<false>(&cells, cell_i - 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let b = *get::<false>(cells, cell_i - 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i - 2) = (a > b) as u8;
-                    *get_mut::<false>(&mut cells, cell_i - 1) = 0;
-                    *get_mut::<false>(&mut cells, cell_i + 0) = 0;
+                    *get_mut::<false>(cells, cell_i - 2)? = (a > b) as u8;
+                    *get_mut::<false>(cells, cell_i - 1)? = 0;
+                    *get_mut::<false>(cells, cell_i + 0)? = 0;
 
                     instr_i += 1;
                 }
@@ -262,15 +383,15 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("LessThanEqual");
 
                     // Check upper bound
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 1)? = 0;
 
                     // Check lower bound
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let b = *get::<false>(&cells, cell_i - 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let b = *get::<false>(cells, cell_i - 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i - 2) = (a <= b) as u8;
-                    *get_mut::<false>(&mut cells, cell_i - 1) = 0;
-                    *get_mut::<false>(&mut cells, cell_i + 0) = 0;
+                    *get_mut::<false>(cells, cell_i - 2)? = (a <= b) as u8;
+                    *get_mut::<false>(cells, cell_i - 1)? = 0;
+                    *get_mut::<false>(cells, cell_i + 0)? = 0;
 
                     instr_i += 1;
                 }
@@ -278,62 +399,62 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("GreaterThanEqual");
 
                     // Check upper bound
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i + 1) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i + 1)? = 0;
 
                     // Check lower bound
-                    let a = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let b = *get::<false>(&cells, cell_i - 1);
+                    let a = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let b = *get::<false>(cells, cell_i - 1)?;
 
-                    *get_mut::<false>(&mut cells, cell_i - 2) = (a >= b) as u8;
-                    *get_mut::<false>(&mut cells, cell_i - 1) = 0;
-                    *get_mut::<false>(&mut cells, cell_i + 0) = 0;
+                    *get_mut::<false>(cells, cell_i - 2)? = (a >= b) as u8;
+                    *get_mut::<false>(cells, cell_i - 1)? = 0;
+                    *get_mut::<false>(cells, cell_i + 0)? = 0;
 
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::MoveAdd(offset)) => {
                     profiling::scope!("MoveAdd");
-                    let current_value = *get::<BOUNDS_CHECKS>(&cells, cell_i);
-                    let destination = get_mut::<BOUNDS_CHECKS>(&mut cells, (cell_i as i64 + offset) as usize);
+                    let current_value = *get::<BOUNDS_CHECKS>(cells, cell_i)?;
+                    let destination = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)?;
                     *destination = (*destination).wrapping_add(current_value);
-                    *get_mut::<false>(&mut cells, cell_i) = 0;
+                    *get_mut::<false>(cells, cell_i)? = 0;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::MoveSet(offset)) => {
                     profiling::scope!("MoveSet");
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, (cell_i as i64 + offset) as usize) =
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i);
-                    *get_mut::<false>(&mut cells, cell_i) = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)? =
+                        *get::<BOUNDS_CHECKS>(cells, cell_i)?;
+                    *get_mut::<false>(cells, cell_i)? = 0;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::MoveAdd2(offset1, offset2)) => {
                     profiling::scope!("MoveAdd2");
-                    let current_value = *get::<BOUNDS_CHECKS>(&cells, cell_i);
+                    let current_value = *get::<BOUNDS_CHECKS>(cells, cell_i)?;
 
-                    let destination1 = get_mut::<BOUNDS_CHECKS>(&mut cells, (cell_i as i64 + offset1) as usize);
+                    let destination1 = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset1) as usize)?;
                     *destination1 = (*destination1).wrapping_add(current_value);
 
-                    let destination2 = get_mut::<BOUNDS_CHECKS>(&mut cells, (cell_i as i64 + offset2) as usize);
+                    let destination2 = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset2) as usize)?;
                     *destination2 = (*destination2).wrapping_add(current_value);
 
-                    *get_mut::<false>(&mut cells, cell_i) = 0;
+                    *get_mut::<false>(cells, cell_i)? = 0;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::PrintStatic(content)) => {
                     profiling::scope!("PrintStatic");
-                    Self::output(&content);
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i) = *content.last().unwrap();
+                    self.output(output, content);
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i)? = *content.last().unwrap();
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU8(offset)) => {
                     // Warning: Unsound
 
                     profiling::scope!("MoveCellDynamicU8");
-                    let value = *get::<BOUNDS_CHECKS>(&cells, cell_i - 2);
-                    let index = *get::<BOUNDS_CHECKS>(&cells, cell_i - 1);
+                    let value = *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?;
+                    let index = *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?;
                     let offset = *offset as usize;
                     let final_index = cell_i - 3 - offset + index as usize;
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, final_index) = value;
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 2) = index;
+                    *get_mut::<BOUNDS_CHECKS>(cells, final_index)? = value;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 2)? = index;
                     cell_i -= 2;
                     instr_i += 1;
                 }
@@ -343,17 +464,17 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("MoveCellDynamicU16");
 
                     let bytes = [
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 2),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 1),
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?,
                     ];
 
-                    let value = *get::<BOUNDS_CHECKS>(&cells, cell_i - 3);
+                    let value = *get::<BOUNDS_CHECKS>(cells, cell_i - 3)?;
                     let index = u16::from_le_bytes(bytes);
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - (*offset) as usize + index as usize) = value;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - (*offset) as usize + index as usize)? = value;
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 3) = bytes[0];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 2) = bytes[1];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 3)? = bytes[0];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 2)? = bytes[1];
                     cell_i -= 3;
                     instr_i += 1;
                 }
@@ -362,9 +483,9 @@ impl<'ops> Interpreter<'ops> {
 
                     profiling::scope!("CopyCellDynamicU8");
                     let offset = *offset as usize;
-                    let index = *get::<BOUNDS_CHECKS>(&cells, cell_i - 1) as usize;
+                    let index = *get::<BOUNDS_CHECKS>(cells, cell_i - 1)? as usize;
                     let final_index = cell_i - 1 - offset + index;
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 1) = *get::<BOUNDS_CHECKS>(&cells, final_index);
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 1)? = *get::<BOUNDS_CHECKS>(cells, final_index)?;
                     instr_i += 1;
                 }
                 InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU32(offset)) => {
@@ -373,21 +494,21 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("MoveCellDynamicU32");
 
                     let bytes = [
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 4),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 3),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 2),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 1),
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 4)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 3)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?,
                     ];
 
-                    let value = *get::<BOUNDS_CHECKS>(&cells, cell_i - 5);
+                    let value = *get::<BOUNDS_CHECKS>(cells, cell_i - 5)?;
                     let index = u32::from_le_bytes(bytes);
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - (*offset) as usize + index as usize) = value;
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - (*offset) as usize + index as usize)? = value;
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 5) = bytes[0];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 4) = bytes[1];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 3) = bytes[2];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 2) = bytes[3];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 5)? = bytes[0];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 4)? = bytes[1];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 3)? = bytes[2];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 2)? = bytes[3];
                     cell_i -= 5;
                     instr_i += 1;
                 }
@@ -397,16 +518,16 @@ impl<'ops> Interpreter<'ops> {
                     profiling::scope!("CopyCellDynamicU32");
 
                     let bytes = [
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 4),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 3),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 2),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 1),
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 4)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 3)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?,
                     ];
 
                     let index = u32::from_le_bytes(bytes);
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 4) =
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - *offset as usize + index as usize);
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 4)? =
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - *offset as usize + index as usize)?;
 
                     cell_i -= 3;
                     instr_i += 1;
@@ -417,6 +538,14 @@ impl<'ops> Interpreter<'ops> {
                     let end_dest = (cell_i as i64 + *offset + 1) as usize;
                     let start_dest = (end_dest as i64 - *count as i64) as usize;
 
+                    // Checking the first and last cell of both ranges covers them
+                    if BOUNDS_CHECKS {
+                        get::<true>(cells, start_src)?;
+                        get::<true>(cells, end_src - 1)?;
+                        get::<true>(cells, start_dest)?;
+                        get::<true>(cells, end_dest - 1)?;
+                    }
+
                     cells.copy_within(start_src..end_src, start_dest);
                     cells[start_src..end_src].fill(0);
                     cell_i -= *count as usize;
@@ -426,17 +555,17 @@ impl<'ops> Interpreter<'ops> {
                     // Warning: Unsound
 
                     let bytes1 = [
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 8),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 7),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 6),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 5),
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 8)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 7)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 6)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 5)?,
                     ];
 
                     let bytes2 = [
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 4),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 3),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 2),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 1),
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 4)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 3)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?,
                     ];
 
                     let a = u32::from_le_bytes(bytes1);
@@ -444,10 +573,10 @@ impl<'ops> Interpreter<'ops> {
 
                     let result = a.wrapping_add(b).to_le_bytes();
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 8) = result[0];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 7) = result[1];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 6) = result[2];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 5) = result[3];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 8)? = result[0];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 7)? = result[1];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 6)? = result[2];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 5)? = result[3];
 
                     cell_i -= 5;
                     instr_i += 1;
@@ -456,17 +585,17 @@ impl<'ops> Interpreter<'ops> {
                     // Warning: Unsound
 
                     let bytes1 = [
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 8),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 7),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 6),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 5),
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 8)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 7)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 6)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 5)?,
                     ];
 
                     let bytes2 = [
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 4),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 3),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 2),
-                        *get::<BOUNDS_CHECKS>(&cells, cell_i - 1),
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 4)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 3)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 2)?,
+                        *get::<BOUNDS_CHECKS>(cells, cell_i - 1)?,
                     ];
 
                     let a = u32::from_le_bytes(bytes1);
@@ -474,45 +603,363 @@ impl<'ops> Interpreter<'ops> {
 
                     let result = a.wrapping_mul(b).to_le_bytes();
 
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 8) = result[0];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 7) = result[1];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 6) = result[2];
-                    *get_mut::<BOUNDS_CHECKS>(&mut cells, cell_i - 5) = result[3];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 8)? = result[0];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 7)? = result[1];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 6)? = result[2];
+                    *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 5)? = result[3];
+
+                    cell_i -= 5;
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(
+                    op @ (CompoundOp::SubU32
+                    | CompoundOp::LessThanU32
+                    | CompoundOp::EqualsU32
+                    | CompoundOp::AndU32
+                    | CompoundOp::OrU32
+                    | CompoundOp::XorU32
+                    | CompoundOp::ShiftLeftU32
+                    | CompoundOp::ShiftRightU32
+                    | CompoundOp::DivModU32),
+                ) => {
+                    let mut bytes = [0; 8];
+
+                    for (i, byte) in bytes.iter_mut().enumerate() {
+                        *byte = *get::<BOUNDS_CHECKS>(cells, cell_i - 8 + i)?;
+                    }
+
+                    let a = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    let b = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+
+                    let (result, rest) = match op {
+                        CompoundOp::SubU32 => (a.wrapping_sub(b), 0),
+                        CompoundOp::LessThanU32 => ((a < b) as u32, 0),
+                        CompoundOp::EqualsU32 => ((a == b) as u32, 0),
+                        CompoundOp::AndU32 => (a & b, 0),
+                        CompoundOp::OrU32 => (a | b, 0),
+                        CompoundOp::XorU32 => (a ^ b, 0),
+                        CompoundOp::ShiftLeftU32 => (a.checked_shl(b).unwrap_or(0), 0),
+                        CompoundOp::ShiftRightU32 => (a.checked_shr(b).unwrap_or(0), 0),
+                        _ => (a.checked_div(b).unwrap_or(0), a.checked_rem(b).unwrap_or(0)),
+                    };
+
+                    bytes[..4].copy_from_slice(&result.to_le_bytes());
+                    bytes[4..].copy_from_slice(&rest.to_le_bytes());
+
+                    for (i, byte) in bytes.into_iter().enumerate() {
+                        *get_mut::<BOUNDS_CHECKS>(cells, cell_i - 8 + i)? = byte;
+                    }
 
                     cell_i -= 5;
                     instr_i += 1;
                 }
+                InterpreterOp::CompoundOp(CompoundOp::MulAddMany(targets)) => {
+                    profiling::scope!("MulAddMany");
+                    let current_value = *get::<BOUNDS_CHECKS>(cells, cell_i)?;
+
+                    for (offset, factor) in targets {
+                        let destination = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)?;
+                        *destination = (*destination).wrapping_add(current_value.wrapping_mul(*factor));
+                    }
+
+                    *get_mut::<false>(cells, cell_i)? = 0;
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::ScanRight(stride)) => {
+                    profiling::scope!("ScanRight");
+                    cell_i = scan_right(cells, cell_i, *stride as usize);
+
+                    if BOUNDS_CHECKS {
+                        get::<true>(cells, cell_i)?;
+                    }
+
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::ScanLeft(stride)) => {
+                    profiling::scope!("ScanLeft");
+                    cell_i = scan_left(cells, cell_i, *stride as usize);
+
+                    if BOUNDS_CHECKS {
+                        get::<true>(cells, cell_i)?;
+                    }
+
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::SetCells(offset, values)) => {
+                    profiling::scope!("SetCells");
+                    let start = (cell_i as i64 + offset) as usize;
+
+                    // Checking the first and last cell covers the whole range
+                    get_mut::<BOUNDS_CHECKS>(cells, start + values.len() - 1)?;
+                    std::ptr::copy_nonoverlapping(
+                        values.as_ptr(),
+                        get_mut::<BOUNDS_CHECKS>(cells, start)?,
+                        values.len(),
+                    );
+
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::ChangeByAt(offset, amount)) => {
+                    profiling::scope!("ChangeByAt");
+                    let cell = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)?;
+                    *cell = (*cell).wrapping_add(*amount);
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::SetAt(offset, value)) => {
+                    profiling::scope!("SetAt");
+                    *get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)? = *value;
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::InputAt(offset, count)) => {
+                    profiling::scope!("InputAt");
+                    for _ in 0..*count {
+                        let cell = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)?;
+                        *cell = self.input(output, *cell);
+                    }
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::OutputAt(offset, count)) => {
+                    profiling::scope!("OutputAt");
+                    for _ in 0..*count {
+                        let cell_value = *get::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)?;
+                        self.output(output, &[cell_value]);
+                    }
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::MoveAddAt(from, to)) => {
+                    profiling::scope!("MoveAddAt");
+                    let source = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + from) as usize)?;
+                    let current_value = *source;
+                    *source = 0;
+                    let destination = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + to) as usize)?;
+                    *destination = (*destination).wrapping_add(current_value);
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::MoveAdd2At(from, to1, to2)) => {
+                    profiling::scope!("MoveAdd2At");
+                    let source = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + from) as usize)?;
+                    let current_value = *source;
+                    *source = 0;
+
+                    let destination1 = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + to1) as usize)?;
+                    *destination1 = (*destination1).wrapping_add(current_value);
+
+                    let destination2 = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + to2) as usize)?;
+                    *destination2 = (*destination2).wrapping_add(current_value);
+
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::MoveSetAt(from, to)) => {
+                    profiling::scope!("MoveSetAt");
+                    let source = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + from) as usize)?;
+                    let current_value = *source;
+                    *source = 0;
+                    *get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + to) as usize)? = current_value;
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::MulAddManyAt(from, targets)) => {
+                    profiling::scope!("MulAddManyAt");
+                    let source = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + from) as usize)?;
+                    let current_value = *source;
+                    *source = 0;
+
+                    for (offset, factor) in targets {
+                        let destination = get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)?;
+                        *destination = (*destination).wrapping_add(current_value.wrapping_mul(*factor));
+                    }
+
+                    instr_i += 1;
+                }
+                InterpreterOp::CompoundOp(CompoundOp::Affine(updates)) => {
+                    profiling::scope!("Affine");
+
+                    let values = updates
+                        .iter()
+                        .map(|update| {
+                            update
+                                .terms
+                                .iter()
+                                .try_fold(update.constant, |value, (offset, coefficient)| {
+                                    let cell = *get::<BOUNDS_CHECKS>(cells, (cell_i as i64 + offset) as usize)?;
+                                    Ok(value.wrapping_add(cell.wrapping_mul(*coefficient)))
+                                })
+                        })
+                        .collect::<Result<Vec<u8>>>()?;
+
+                    for (update, value) in updates.iter().zip(values) {
+                        *get_mut::<BOUNDS_CHECKS>(cells, (cell_i as i64 + update.offset) as usize)? = value;
+                    }
+
+                    instr_i += 1;
+                }
             }
         }
+
+        Ok((instr_i, cell_i))
     }
 
-    fn input() -> u8 {
-        std::io::stdin()
-            .bytes()
-            .next()
-            .and_then(|result| Some(result.unwrap_or_default()))
-            .unwrap_or_default()
+    fn input(&self, output: &mut impl Write, current: u8) -> u8 {
+        let mut byte = [0u8];
+
+        // Make sure any prompt is visible before blocking on input
+        _ = output.flush();
+
+        match std::io::stdin().read(&mut byte) {
+            Ok(1) => byte[0],
+            _ => self.options.eof_behavior.apply(current),
+        }
     }
 
-    fn output(slice: &[u8]) {
-        let mut stdout = std::io::stdout();
-        _ = stdout.write(slice);
-        _ = stdout.flush();
+    fn output(&self, output: &mut impl Write, slice: &[u8]) {
+        _ = output.write_all(slice);
+
+        if !self.options.buffered_output {
+            _ = output.flush();
+        }
+    }
+}
+
+pub fn report_panic(cells: &[u8], value: u8, instr_i: usize, cell_i: usize) {
+    eprintln!(
+        "[PANIC] Program entered panic loop with error code {}, instr_i = {}, cell_i = {}",
+        value, instr_i, cell_i
+    );
+    eprintln!("Memory before panic:");
+    let forward_range = 20;
+    let back_range = 20;
+    let start = cell_i.saturating_sub(back_range);
+    let end = (cell_i + forward_range).min(cells.len());
+    for (i, cell) in cells.iter().enumerate().take(end).skip(start) {
+        eprintln!("cell {} is {}", i, cell);
     }
 }
 
-unsafe fn get<const BOUNDS_CHECKS: bool>(memory: &[u8], index: usize) -> *const u8 {
+unsafe fn get<const BOUNDS_CHECKS: bool>(memory: &[u8], index: usize) -> Result<*const u8> {
     if BOUNDS_CHECKS {
-        &memory[index]
+        memory
+            .get(index)
+            .map(|cell| cell as *const u8)
+            .ok_or_else(out_of_bounds)
     } else {
-        memory.get_unchecked(index)
+        Ok(memory.get_unchecked(index))
     }
 }
 
-unsafe fn get_mut<const BOUNDS_CHECKS: bool>(memory: &mut [u8], index: usize) -> *mut u8 {
+unsafe fn get_mut<const BOUNDS_CHECKS: bool>(memory: &mut [u8], index: usize) -> Result<*mut u8> {
     if BOUNDS_CHECKS {
-        &mut memory[index]
+        memory
+            .get_mut(index)
+            .map(|cell| cell as *mut u8)
+            .ok_or_else(out_of_bounds)
     } else {
-        memory.get_unchecked_mut(index)
+        Ok(memory.get_unchecked_mut(index))
     }
 }
+
+fn out_of_bounds() -> anyhow::Error {
+    anyhow!("[error] Out of bounds memory access")
+}
+
+/// Index of the first zero cell at or to the right of `from` visiting every `stride`th cell, past the end of the
+/// tape if there's none
+pub(crate) fn scan_right(cells: &[u8], from: usize, stride: usize) -> usize {
+    if from >= cells.len() {
+        return from;
+    }
+
+    if stride == 1 {
+        let rest = &cells[from..];
+        let found = unsafe { libc::memchr(rest.as_ptr().cast(), 0, rest.len()) };
+
+        return if found.is_null() {
+            cells.len()
+        } else {
+            found as usize - cells.as_ptr() as usize
+        };
+    }
+
+    let visited = stride_mask(stride);
+    let mut next = from;
+
+    while next + 64 <= cells.len() {
+        let zeros = zero_mask(cells[next..next + 64].try_into().unwrap()) & visited;
+
+        if zeros != 0 {
+            return next + zeros.trailing_zeros() as usize;
+        }
+
+        next += stride * 64usize.div_ceil(stride);
+    }
+
+    while next < cells.len() && cells[next] != 0 {
+        next += stride;
+    }
+
+    next
+}
+
+/// Index of the first zero cell at or to the left of `from` visiting every `stride`th cell, past the start of the
+/// tape (wrapped around) if there's none
+pub(crate) fn scan_left(cells: &[u8], from: usize, stride: usize) -> usize {
+    if from >= cells.len() {
+        return from;
+    }
+
+    if stride == 1 {
+        let found = unsafe { libc::memrchr(cells.as_ptr().cast(), 0, from + 1) };
+
+        return if found.is_null() {
+            usize::MAX
+        } else {
+            found as usize - cells.as_ptr() as usize
+        };
+    }
+
+    let visited = stride_mask(stride);
+    let mut next = from;
+
+    // Blocks end at the next cell visited, so reversing their bits puts that cell at bit 0
+    while next >= 63 {
+        let zeros = zero_mask(cells[next - 63..=next].try_into().unwrap()).reverse_bits() & visited;
+
+        if zeros != 0 {
+            return next - zeros.trailing_zeros() as usize;
+        }
+
+        match next.checked_sub(stride * 64usize.div_ceil(stride)) {
+            Some(moved) => next = moved,
+            None => return usize::MAX,
+        }
+    }
+
+    loop {
+        if cells[next] == 0 {
+            return next;
+        }
+
+        match next.checked_sub(stride) {
+            Some(moved) => next = moved,
+            None => return usize::MAX,
+        }
+    }
+}
+
+/// Bit `i` is set when cell `i` of the block is zero, comparing eight cells at a time
+fn zero_mask(block: &[u8; 64]) -> u64 {
+    const LOW_BITS: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+    // Moves the high bit of byte `i` to bit 56 + `i`
+    const GATHER: u64 = 0x0002_0408_1020_4081;
+
+    block.chunks_exact(8).enumerate().fold(0, |mask, (i, chunk)| {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        // The high bit of a byte is set exactly when the byte is zero, adding the low bits can't carry between bytes
+        let zeros = !(((word & LOW_BITS) + LOW_BITS) | word) & HIGH_BITS;
+        mask | (zeros.wrapping_mul(GATHER) >> 56) << (i * 8)
+    })
+}
+
+/// Bits of the cells in a block that a scan starting at bit 0 visits
+fn stride_mask(stride: usize) -> u64 {
+    (0..64).step_by(stride).fold(0, |mask, i| mask | 1 << i)
+}
@@ -1,11 +1,15 @@
 use crate::{basic_op::BasicOp, compound_op::CompoundOp};
 use anyhow::{anyhow, Result};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum InterpreterOp {
     CompoundOp(CompoundOp),
     LoopStart(usize),
     LoopEnd(usize),
+    /// Start of a loop whose body always leaves the cell it tests zero, so it's only tested here, jumping past
+    /// the end the same distance away as a loop's
+    IfStart(usize),
+    IfEnd(usize),
 }
 
 pub struct InterpreterOpAcc {
@@ -45,10 +49,16 @@ impl InterpreterOpAcc {
     }
 
     pub fn view(&self) -> Result<&Vec<InterpreterOp>> {
-        if self.loop_start_indices.len() != 0 {
+        if !self.loop_start_indices.is_empty() {
             return Err(anyhow!("Instruction '[' is missing match"));
         }
 
         Ok(&self.ops)
     }
 }
+
+impl Default for InterpreterOpAcc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
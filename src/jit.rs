@@ -0,0 +1,159 @@
+use crate::{
+    interpreter::{report_panic, RuntimeOptions},
+    interpreter_op::InterpreterOp,
+    x86_64::{Assembler, Inst, Mem, Reg},
+    x86_64_codegen::{self, CodegenOptions, Exits, CELL, INSTR, TAPE, TAPE_LEN, VALUE},
+};
+use anyhow::{anyhow, Result};
+
+const STATUS_DONE: i64 = 0;
+const STATUS_OUT_OF_BOUNDS: i64 = 1;
+const STATUS_PANIC: i64 = 2;
+
+/// Where the compiled code leaves the machine state once it stops running
+#[repr(C)]
+#[derive(Default)]
+struct JitState {
+    cell_i: usize,
+    instr_i: usize,
+    value: u64,
+}
+
+type EntryPoint = unsafe extern "sysv64" fn(tape: *mut u8, tape_len: usize, cell_i: usize, state: *mut JitState) -> u64;
+
+pub struct Jit {
+    code: Vec<u8>,
+    tape_size: usize,
+    tape_origin: usize,
+}
+
+impl Jit {
+    pub fn new(ops: &[InterpreterOp], runtime_options: RuntimeOptions) -> Result<Self> {
+        let mut asm = Assembler::new();
+
+        let exits = Exits {
+            done: asm.new_label(),
+            out_of_bounds: asm.new_label(),
+            panic: asm.new_label(),
+        };
+        let epilogue = asm.new_label();
+
+        // Prologue, the state pointer is kept on the stack until the epilogue
+        for reg in [Reg::Rbx, Reg::R12, Reg::R13, Reg::R14, Reg::R15, Reg::Rcx] {
+            asm.push(Inst::Push(reg));
+        }
+        asm.push(Inst::Mov(TAPE, Reg::Rdi));
+        asm.push(Inst::Mov(TAPE_LEN, Reg::Rsi));
+        asm.push(Inst::Mov(CELL, Reg::Rdx));
+        asm.push(Inst::MovImm(INSTR, 0));
+        asm.push(Inst::MovImm(VALUE, 0));
+
+        let options = CodegenOptions {
+            bounds_checks: runtime_options.bounds_checks,
+            eof_behavior: runtime_options.eof_behavior,
+        };
+        x86_64_codegen::lower(&mut asm, ops, options, exits)?;
+
+        for (label, status) in [
+            (exits.done, STATUS_DONE),
+            (exits.out_of_bounds, STATUS_OUT_OF_BOUNDS),
+            (exits.panic, STATUS_PANIC),
+        ] {
+            asm.push(Inst::Label(label));
+            asm.push(Inst::MovImm(Reg::Rax, status));
+            asm.push(Inst::Jump(epilogue));
+        }
+
+        asm.push(Inst::Label(epilogue));
+        asm.push(Inst::Pop(Reg::Rcx));
+        asm.push(Inst::Store64(Mem::new(Reg::Rcx, None, 0), CELL));
+        asm.push(Inst::Store64(Mem::new(Reg::Rcx, None, 8), INSTR));
+        asm.push(Inst::Store64(Mem::new(Reg::Rcx, None, 16), VALUE));
+        for reg in [Reg::R15, Reg::R14, Reg::R13, Reg::R12, Reg::Rbx] {
+            asm.push(Inst::Pop(reg));
+        }
+        asm.push(Inst::Ret);
+
+        Ok(Self {
+            code: asm.encode(),
+            tape_size: runtime_options.tape_size,
+            tape_origin: runtime_options.tape_origin,
+        })
+    }
+
+    /// Runs the compiled program on a fresh tape, returning the panic code if it entered a panic loop and 0
+    /// otherwise, like the interpreter
+    #[profiling::function]
+    pub fn run(&self) -> Result<i32> {
+        let mut cells = vec![0u8; self.tape_size];
+        let mut state = JitState::default();
+
+        let status = unsafe {
+            let code = ExecutableBuffer::new(&self.code)?;
+            let entry_point: EntryPoint = std::mem::transmute(code.as_ptr());
+            entry_point(cells.as_mut_ptr(), cells.len(), self.tape_origin, &mut state)
+        };
+
+        match status as i64 {
+            STATUS_DONE => Ok(0),
+            STATUS_OUT_OF_BOUNDS => Err(anyhow!(
+                "[error] Out of bounds memory access, instr_i = {}, cell_i = {}",
+                state.instr_i,
+                state.cell_i as isize
+            )),
+            STATUS_PANIC => {
+                report_panic(&cells, state.value as u8, state.instr_i, state.cell_i);
+                Ok(state.value as i32)
+            }
+            _ => Err(anyhow!("[error] Compiled code exited with unknown status {}", status)),
+        }
+    }
+}
+
+/// Page aligned memory mapping that holds machine code
+struct ExecutableBuffer {
+    pointer: *mut libc::c_void,
+    len: usize,
+}
+
+impl ExecutableBuffer {
+    unsafe fn new(code: &[u8]) -> Result<Self> {
+        let len = code.len().max(1);
+
+        let pointer = libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+
+        if pointer == libc::MAP_FAILED {
+            return Err(anyhow!("[error] Failed to map memory for compiled code"));
+        }
+
+        // Owned from here on so that the mapping is released on failure
+        let buffer = Self { pointer, len };
+
+        std::ptr::copy_nonoverlapping(code.as_ptr(), pointer as *mut u8, code.len());
+
+        if libc::mprotect(pointer, len, libc::PROT_READ | libc::PROT_EXEC) != 0 {
+            return Err(anyhow!("[error] Failed to make compiled code executable"));
+        }
+
+        Ok(buffer)
+    }
+
+    fn as_ptr(&self) -> *const u8 {
+        self.pointer as *const u8
+    }
+}
+
+impl Drop for ExecutableBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.pointer, self.len);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::RuntimeOptions,
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+};
+use anyhow::Result;
+use std::{collections::HashSet, ops::Range};
+
+/// Removes the work the program does on cells known to be zero, following which cells are zero from the
+/// start of the program, where the whole tape is, through the ops that zero cells
+///
+/// Loops and the ops standing in for loops are removed when the current cell is zero, since they can never
+/// be entered, and so is zeroing a cell that's already zero. Changing a zero cell sets it instead. The body
+/// of a loop can run again after its end, so nothing is known inside a loop except what it zeroes itself,
+/// and after a loop only the current cell is known to be zero. Cells off the tape are never known to be zero,
+/// so accesses to them are kept and still fail with bounds checks.
+pub fn known_zero(ops: &[InterpreterOp], options: RuntimeOptions) -> Result<Vec<InterpreterOp>> {
+    let mut kept = InterpreterOpAcc::new();
+    let mut tape = Tape::new(options);
+    let mut instr_i = 0;
+
+    while instr_i < ops.len() {
+        let op = match &ops[instr_i] {
+            InterpreterOp::LoopStart(distance) | InterpreterOp::IfStart(distance) if tape.is_zero(0) => {
+                instr_i += distance + 1;
+                continue;
+            }
+            InterpreterOp::LoopStart(_) | InterpreterOp::IfStart(_) => {
+                tape.forget();
+                CompoundOp::BasicOp(BasicOp::LoopStart)
+            }
+            InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => {
+                tape.forget();
+                tape.zero(0);
+                CompoundOp::BasicOp(BasicOp::LoopEnd)
+            }
+            InterpreterOp::CompoundOp(op) => match tape.simplify(op) {
+                Some(op) => op,
+                None => {
+                    instr_i += 1;
+                    continue;
+                }
+            },
+        };
+
+        kept.feed(op)?;
+        instr_i += 1;
+    }
+
+    Ok(kept.view()?.clone())
+}
+
+/// Cells known to be zero, relative to where the pointer started
+struct Tape {
+    pointer: i64,
+    /// Whether every cell except those in `cells` is zero, otherwise only those in `cells` are
+    zero_elsewhere: bool,
+    cells: HashSet<i64>,
+    /// Cells on the tape, while it's known where the pointer is on it
+    bounds: Option<Range<i64>>,
+}
+
+impl Tape {
+    fn new(options: RuntimeOptions) -> Self {
+        let origin = options.tape_origin as i64;
+
+        Self {
+            pointer: 0,
+            zero_elsewhere: true,
+            cells: HashSet::new(),
+            bounds: Some(-origin..options.tape_size as i64 - origin),
+        }
+    }
+
+    fn is_zero(&self, offset: i64) -> bool {
+        let cell = self.pointer + offset;
+
+        self.bounds.as_ref().is_none_or(|bounds| bounds.contains(&cell))
+            && self.cells.contains(&cell) != self.zero_elsewhere
+    }
+
+    fn zero(&mut self, offset: i64) {
+        if self.zero_elsewhere {
+            self.cells.remove(&(self.pointer + offset));
+        } else {
+            self.cells.insert(self.pointer + offset);
+        }
+    }
+
+    /// Marks the cell as holding a value that might not be zero
+    fn write(&mut self, offset: i64) {
+        if self.zero_elsewhere {
+            self.cells.insert(self.pointer + offset);
+        } else {
+            self.cells.remove(&(self.pointer + offset));
+        }
+    }
+
+    fn set(&mut self, offset: i64, value: u8) {
+        if value == 0 {
+            self.zero(offset);
+        } else {
+            self.write(offset);
+        }
+    }
+
+    /// Stops knowing anything, for ops whose effects depend on the tape
+    fn forget(&mut self) {
+        self.pointer = 0;
+        self.zero_elsewhere = false;
+        self.cells.clear();
+        self.bounds = None;
+    }
+
+    /// Op to run in place of `op` given the cells known to be zero, or `None` if it does nothing, updating
+    /// the cells for what it does
+    fn simplify(&mut self, op: &CompoundOp) -> Option<CompoundOp> {
+        match op {
+            CompoundOp::Zero | CompoundOp::Set(0) if self.is_zero(0) => return None,
+            CompoundOp::SetAt(offset, 0) if self.is_zero(*offset) => return None,
+            CompoundOp::MoveAdd(_)
+            | CompoundOp::MoveAdd2(..)
+            | CompoundOp::MulAddMany(_)
+            | CompoundOp::ScanRight(_)
+            | CompoundOp::ScanLeft(_)
+                if self.is_zero(0) =>
+            {
+                return None
+            }
+            CompoundOp::MoveAddAt(from, _) | CompoundOp::MoveAdd2At(from, ..) | CompoundOp::MulAddManyAt(from, _)
+                if self.is_zero(*from) =>
+            {
+                return None
+            }
+            CompoundOp::ZeroAdvance(amount) if (0..*amount as i64).all(|offset| self.is_zero(offset)) => {
+                self.pointer += *amount as i64;
+                return Some(CompoundOp::BasicOp(BasicOp::Shift(*amount as i64)));
+            }
+            CompoundOp::ZeroRetreat(amount) if (0..*amount as i64).all(|offset| self.is_zero(-offset)) => {
+                self.pointer -= *amount as i64;
+                return Some(CompoundOp::BasicOp(BasicOp::Shift(-(*amount as i64))));
+            }
+            CompoundOp::BasicOp(BasicOp::ChangeBy(amount)) if self.is_zero(0) => {
+                self.set(0, *amount);
+                return Some(CompoundOp::Set(*amount));
+            }
+            CompoundOp::ChangeByAt(offset, amount) if self.is_zero(*offset) => {
+                self.set(*offset, *amount);
+                return Some(CompoundOp::SetAt(*offset, *amount));
+            }
+            _ => (),
+        }
+
+        match op {
+            CompoundOp::BasicOp(BasicOp::Shift(amount)) => self.pointer += amount,
+            CompoundOp::BasicOp(BasicOp::ChangeBy(_) | BasicOp::Input(_)) => self.write(0),
+            CompoundOp::BasicOp(BasicOp::Output(_)) | CompoundOp::OutputAt(..) => (),
+            CompoundOp::Zero => self.zero(0),
+            CompoundOp::Set(value) => self.set(0, *value),
+            CompoundOp::ZeroAdvance(amount) => {
+                for _ in 0..*amount {
+                    self.zero(0);
+                    self.pointer += 1;
+                }
+            }
+            CompoundOp::ZeroRetreat(amount) => {
+                for _ in 0..*amount {
+                    self.zero(0);
+                    self.pointer -= 1;
+                }
+            }
+            CompoundOp::MoveAdd(to) | CompoundOp::MoveSet(to) => {
+                self.write(*to);
+                self.zero(0);
+            }
+            CompoundOp::MoveAdd2(to1, to2) => {
+                self.write(*to1);
+                self.write(*to2);
+                self.zero(0);
+            }
+            CompoundOp::MulAddMany(targets) => {
+                for (to, _) in targets {
+                    self.write(*to);
+                }
+                self.zero(0);
+            }
+            CompoundOp::Dupe(_) => {
+                self.write(0);
+                self.zero(1);
+                self.pointer += 1;
+            }
+            CompoundOp::PrintStatic(content) => self.set(0, *content.last().unwrap()),
+            CompoundOp::ScanRight(_) | CompoundOp::ScanLeft(_) => {
+                self.forget();
+                self.zero(0);
+            }
+            CompoundOp::SetCells(from, values) => {
+                for (offset, value) in (*from..).zip(values) {
+                    self.set(offset, *value);
+                }
+            }
+            CompoundOp::ChangeByAt(offset, _) | CompoundOp::InputAt(offset, _) => self.write(*offset),
+            CompoundOp::SetAt(offset, value) => self.set(*offset, *value),
+            CompoundOp::MoveAddAt(from, to) | CompoundOp::MoveSetAt(from, to) => {
+                self.write(*to);
+                self.zero(*from);
+            }
+            CompoundOp::MoveAdd2At(from, to1, to2) => {
+                self.write(*to1);
+                self.write(*to2);
+                self.zero(*from);
+            }
+            CompoundOp::MulAddManyAt(from, targets) => {
+                for (to, _) in targets {
+                    self.write(*to);
+                }
+                self.zero(*from);
+            }
+            CompoundOp::Affine(updates) => {
+                for update in updates {
+                    self.write(update.offset);
+                }
+            }
+            _ => self.forget(),
+        }
+
+        Some(op.clone())
+    }
+}
@@ -0,0 +1,35 @@
+//! A blazing fast interpreter for BrainF*ck programs
+//!
+//! Programs are parsed into [`interpreter_op::InterpreterOp`]s by [`parser::parse`] and then handed to
+//! a [`backend::Backend`], which either runs them or generates code for them. Additional backends can
+//! be added to a [`backend::Backends`] registry with [`backend::Backends::register`].
+
+pub mod backend;
+pub mod basic_op;
+pub mod block;
+pub mod bundle;
+pub mod compile_c;
+pub mod compound_op;
+pub mod expand;
+pub mod ifs;
+pub mod interpreter;
+pub mod interpreter_op;
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub mod jit;
+pub mod known_zero;
+pub mod opt_stats;
+pub mod parser;
+pub mod partial_eval;
+pub mod passes;
+pub mod patterns;
+pub mod rules;
+pub mod sink_shifts;
+pub mod transpile_asm;
+pub mod transpile_bf;
+pub mod transpile_c;
+pub mod transpile_elf;
+pub mod transpile_llvm;
+pub mod transpile_rust;
+pub mod transpile_wat;
+pub mod x86_64;
+pub mod x86_64_codegen;
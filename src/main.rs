@@ -1,111 +1,31 @@
-mod basic_op;
-mod compound_op;
-mod interpreter;
-mod interpreter_op;
-mod transpile_c;
-
-use anyhow::Result;
-use basic_op::{BasicOpAcc, BasicOp};
+use anyhow::{anyhow, Result};
+use blazing_fast_interpreter::{
+    backend::{BackendConfig, Backends},
+    bundle::Bundle,
+    compile_c::DEFAULT_CFLAGS,
+    interpreter::{EofBehavior, RuntimeOptions, CELL_COUNT},
+    parser::Parser,
+    passes::{Passes, MAX_LEVEL, PASSES},
+    patterns, rules,
+};
 use clap::{command, Arg, ArgAction};
-use compound_op::{CompoundOp, CompoundOpAcc};
-use interpreter::Interpreter;
-use interpreter_op::{InterpreterOp, InterpreterOpAcc};
 use std::{
     fs::File,
     io::{prelude::*, BufReader},
+    time::Instant,
 };
-use transpile_c::transpile_c;
-
-struct Parser {
-    basic_op_acc: BasicOpAcc,
-    compound_op_acc: CompoundOpAcc,
-    interpreter_op_acc: InterpreterOpAcc,
-
-    emit_simplified_filename: Option<File>,
-    emit_ops_file: Option<File>,
-}
-
-impl Parser {
-    pub fn new(emit_simplified_filename: Option<&str>, emit_ops_filename: Option<&str>) -> Result<Self> {
-        let emit_simplified_filename = emit_simplified_filename.and_then(|filename| Some(File::create(filename))).transpose()?;
-        let emit_ops_file = emit_ops_filename.and_then(|filename| Some(File::create(filename))).transpose()?;
-
-        Ok(Self {
-            basic_op_acc: BasicOpAcc::new(),
-            compound_op_acc: CompoundOpAcc::new(),
-            interpreter_op_acc: InterpreterOpAcc::new(),
-            emit_simplified_filename,
-            emit_ops_file,
-        })
-    }
-
-    pub fn feed(&mut self, byte: u8) -> Result<()> {
-        if let Some(basic_op) = self.basic_op_acc.feed_byte(byte)? {
-            self.log_simplified_op(&basic_op)?;
-
-            if let Some(compound_op) = self.compound_op_acc.feed(basic_op) {
-                self.log_compound_op(&compound_op)?;
-                self.interpreter_op_acc.feed(compound_op)?;
-            }
-        }
-
-        while let Some(basic_op) = self.basic_op_acc.continued() {
-            self.log_simplified_op(&basic_op)?;
-
-            if let Some(compound_op) = self.compound_op_acc.feed(basic_op) {
-                self.log_compound_op(&compound_op)?;
-                self.interpreter_op_acc.feed(compound_op)?;
-            }
-        }
-
-        Ok(())
-    }
-
-    pub fn flush(&mut self) -> Result<()> {
-        // Flush basic op accumulator
-        while let Some(basic_op) = self.basic_op_acc.finalize() {
-            self.log_simplified_op(&basic_op)?;
 
-            if let Some(compound_op) = self.compound_op_acc.feed(basic_op) {
-                self.log_compound_op(&compound_op)?;
-                self.interpreter_op_acc.feed(compound_op)?;
-            }
-        }
-
-        // Flush compound op accumulator
-        while let Some(compound_op) = self.compound_op_acc.finalize() {
-            self.log_compound_op(&compound_op)?;
-            self.interpreter_op_acc.feed(compound_op)?;
-        }
-
-        // Flush interpreter op accumulator
-        // (nothing to do)
-
-        Ok(())
-    }
-
-    fn log_simplified_op(&mut self, basic_op: &BasicOp) -> Result<()> {
-        // Write to output if requested
-        if let Some(emit_simplified_filename) = &mut self.emit_simplified_filename {
-            write!(emit_simplified_filename, "{}", basic_op)?;
-        }
-
-        Ok(())
-    }
-
-    fn log_compound_op(&mut self, compound_op: &CompoundOp) -> Result<()> {
-        // Write to output if requested
-        if let Some(emit_ops_file) = &mut self.emit_ops_file {
-            writeln!(emit_ops_file, "{:?}", compound_op)?;
-        }
-
-        Ok(())
-    }
-
-    pub fn view(&self) -> Result<&Vec<InterpreterOp>> {
-        self.interpreter_op_acc.view()
-    }
-}
+/// Flags that predate --backend, each selecting a backend and naming its output file
+const LEGACY_BACKEND_FLAGS: [(&str, &str); 8] = [
+    ("transpile-c", "c"),
+    ("compile", "c-compile"),
+    ("transpile-rust", "rust"),
+    ("emit-llvm", "llvm"),
+    ("emit-wat", "wat"),
+    ("emit-asm", "asm"),
+    ("emit-elf", "elf"),
+    ("emit-bf", "bf"),
+];
 
 fn main() -> Result<()> {
     // Starting the Tracy client is necessary before any invoking any of its APIs
@@ -115,46 +35,328 @@ fn main() -> Result<()> {
     // Good to call this on any threads that are created to get clearer profiling results
     profiling::register_thread!("Main Thread");
 
+    // A bundle already carries its optimized ops, so skip argument parsing and pattern matching
+    if let Some(bundle) = Bundle::load()? {
+        let config = BackendConfig {
+            runtime: bundle.options,
+            ..BackendConfig::default()
+        };
+        let backend = if bundle.jit { "jit" } else { "interpreter" };
+        return exit_with(Backends::builtin().run(backend, &bundle.ops, &config)?);
+    }
+
+    let backends = Backends::builtin();
+    let backend_help: String = backends
+        .iter()
+        .map(|backend| format!("\n  {:<12} {}", backend.name(), backend.description()))
+        .collect();
+
+    let pass_names = PASSES.iter().map(|(name, _, _)| *name).collect::<Vec<_>>().join(", ");
+
     let args = command!()
         .about("A blazing fast interpreter for running BrainF*ck programs")
-        .arg(Arg::new("filename").required(true))
+        .arg(Arg::new("filename").required_unless_present("list-rules"))
+        .arg(Arg::new("backend").long("backend").value_name("NAME").help(format!(
+            "Backend that runs or emits the program, the interpreter by default:{}",
+            backend_help
+        )))
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_name("OUT_FILE")
+                .help("File written by backends that generate code"),
+        )
         .arg(Arg::new("emit-ops").long("emit-ops").value_name("FILE"))
+        .arg(
+            Arg::new("list-rules")
+                .long("list-rules")
+                .action(ArgAction::SetTrue)
+                .help("List the rules used to recognise algorithms, in the order they're tried"),
+        )
+        .arg(
+            Arg::new("patterns")
+                .long("patterns")
+                .value_name("FILE")
+                .help("Rewrite patterns applied after the built-in rules, one `name: code => Op, Op, ...` per line"),
+        )
         .arg(Arg::new("emit-simplified").long("emit-simplified").value_name("FILE"))
+        .arg(
+            Arg::new("opt-level")
+                .short('O')
+                .value_name("LEVEL")
+                .value_parser(clap::value_parser!(u8).range(0..=MAX_LEVEL as i64))
+                .default_value("3")
+                .help("0 only merges repeated commands, 1 adds safe idioms, 2 arithmetic fusions, 3 unsound fusions"),
+        )
+        .arg(
+            Arg::new("enable-pass")
+                .long("enable-pass")
+                .value_name("NAME")
+                .action(ArgAction::Append)
+                .help(format!(
+                    "Apply a rule or pass left out by the optimization level, the passes being {}",
+                    pass_names
+                )),
+        )
+        .arg(
+            Arg::new("disable-pass")
+                .long("disable-pass")
+                .value_name("NAME")
+                .action(ArgAction::Append)
+                .help("Skip a rule or pass, applied after --enable-pass"),
+        )
+        .arg(
+            Arg::new("whole-program")
+                .long("whole-program")
+                .action(ArgAction::SetTrue)
+                .help("Rewrite the whole program until no rule applies instead of once through a window of ops"),
+        )
+        .arg(
+            Arg::new("opt-stats")
+                .long("opt-stats")
+                .action(ArgAction::SetTrue)
+                .help("Report how often each rewrite fired, how much smaller the program got and how long that took"),
+        )
         .arg(
             Arg::new("bounds-checks")
                 .long("bounds-checks")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("eof")
+                .long("eof")
+                .value_name("BEHAVIOR")
+                .value_parser(["zero", "unchanged", "max"])
+                .default_value("zero")
+                .help("Value stored by ',' once input is exhausted"),
+        )
+        .arg(
+            Arg::new("tape-size")
+                .long("tape-size")
+                .value_name("CELLS")
+                .value_parser(clap::value_parser!(usize))
+                .help("Number of cells on the tape, 25 million by default"),
+        )
+        .arg(
+            Arg::new("tape-origin")
+                .long("tape-origin")
+                .value_name("CELL")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("0")
+                .help("Cell the program starts on"),
+        )
+        .arg(
+            Arg::new("buffered-output")
+                .long("buffered-output")
+                .action(ArgAction::SetTrue)
+                .help("Only flush output before reading input and on exit instead of after every write"),
+        )
+        .arg(
+            Arg::new("jit")
+                .long("jit")
+                .action(ArgAction::SetTrue)
+                .help("Compile to native x86-64 code and run it instead of interpreting"),
+        )
         .arg(Arg::new("transpile-c").long("transpile-c").value_name("OUT_FILE"))
+        .arg(
+            Arg::new("compile")
+                .long("compile")
+                .value_name("OUT_FILE")
+                .help("Transpile to C and build an executable with the system C compiler ($CC or cc)"),
+        )
+        .arg(
+            Arg::new("run-native")
+                .long("run-native")
+                .action(ArgAction::SetTrue)
+                .help("Transpile to C, build it with the system C compiler and run it"),
+        )
+        .arg(
+            Arg::new("cflags")
+                .long("cflags")
+                .value_name("FLAGS")
+                .default_value(DEFAULT_CFLAGS)
+                .allow_hyphen_values(true)
+                .help("Flags passed to the C compiler by --compile and --run-native"),
+        )
+        .arg(
+            Arg::new("program-args")
+                .num_args(0..)
+                .last(true)
+                .help("Arguments passed to the program by --run-native"),
+        )
+        .arg(Arg::new("transpile-rust").long("transpile-rust").value_name("OUT_FILE"))
+        .arg(
+            Arg::new("unchecked")
+                .long("unchecked")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("bounds-checks")
+                .help("Skip bounds checks in transpiled Rust, matching the interpreter without --bounds-checks"),
+        )
+        .arg(
+            Arg::new("emit-llvm")
+                .long("emit-llvm")
+                .value_name("OUT_FILE")
+                .help("Write the program as textual LLVM IR instead of running it, which rejects --bounds-checks, --tape-size, --tape-origin and --buffered-output"),
+        )
+        .arg(
+            Arg::new("emit-wat")
+                .long("emit-wat")
+                .value_name("OUT_FILE")
+                .help("Write the program as a WebAssembly text module instead of running it"),
+        )
+        .arg(
+            Arg::new("emit-asm")
+                .long("emit-asm")
+                .value_name("OUT_FILE")
+                .help("Write the program as x86-64 GNU assembler source instead of running it"),
+        )
+        .arg(
+            Arg::new("emit-elf")
+                .long("emit-elf")
+                .value_name("OUT_FILE")
+                .help("Write the program as a static x86-64 Linux executable instead of running it"),
+        )
+        .arg(
+            Arg::new("emit-bf")
+                .long("emit-bf")
+                .value_name("OUT_FILE")
+                .help("Write the optimized program back out as plain Brainfuck instead of running it"),
+        )
+        .arg(
+            Arg::new("bundle")
+                .long("bundle")
+                .value_name("OUT_FILE")
+                .help("Write an executable that runs the optimized program without parsing it again"),
+        )
         .get_matches();
 
+    if args.get_flag("list-rules") {
+        for (depth, rule) in rules::all() {
+            let name = format!("{}{}", "  ".repeat(depth), rule.name);
+            println!(
+                "{:<36} -O{} {:>4} ops  {}",
+                name,
+                rule.level as u8,
+                rule.len,
+                rule.description.trim()
+            );
+        }
+
+        return Ok(());
+    }
+
     let filename = args.get_one::<String>("filename").unwrap();
     let emit_simplified_filename = args.get_one::<String>("emit-simplified").map(|x| x.as_str());
     let emit_ops_filename = args.get_one::<String>("emit-ops").map(|x| x.as_str());
-    let mut parser = Parser::new(emit_simplified_filename, emit_ops_filename)?;
+    let mut passes = Passes::at_level(*args.get_one::<u8>("opt-level").unwrap());
+
+    for name in args.get_many::<String>("enable-pass").unwrap_or_default() {
+        passes.enable(name)?;
+    }
+
+    for name in args.get_many::<String>("disable-pass").unwrap_or_default() {
+        passes.disable(name)?;
+    }
+
+    let patterns = match args.get_one::<String>("patterns") {
+        Some(patterns_filename) => patterns::load(patterns_filename, &passes)?,
+        None => Vec::new(),
+    };
+    let mut parser = Parser::new(emit_simplified_filename, emit_ops_filename, passes.clone(), patterns)?;
+
+    if args.get_flag("whole-program") {
+        parser.whole_program();
+    }
+
+    if args.get_flag("opt-stats") {
+        parser.collect_stats();
+    }
+
+    let parse_start = Instant::now();
 
     for byte in BufReader::new(File::open(filename)?).bytes() {
         parser.feed(byte?)?;
     }
 
     parser.flush()?;
+    let parse_time = parse_start.elapsed();
+
+    let options = RuntimeOptions {
+        bounds_checks: args.get_flag("bounds-checks"),
+        eof_behavior: EofBehavior::from_name(args.get_one::<String>("eof").unwrap()).unwrap(),
+        tape_size: args.get_one::<usize>("tape-size").copied().unwrap_or(CELL_COUNT),
+        tape_origin: *args.get_one::<usize>("tape-origin").unwrap(),
+        buffered_output: args.get_flag("buffered-output"),
+    };
 
-    if args.contains_id("transpile-c") {
-        return transpile_c(parser.view()?.iter(), args.get_one::<String>("transpile-c").unwrap());
+    if options.tape_origin >= options.tape_size {
+        return Err(anyhow!(
+            "[error] Tape origin {} is outside a tape of {} cells",
+            options.tape_origin,
+            options.tape_size
+        ));
     }
 
-    let interpreter = Interpreter::new(parser.view()?);
-
-    if !args.contains_id("emit-ops") && !args.contains_id("emit-simplified") {
-        if args.get_flag("bounds-checks") {
-            unsafe {
-                interpreter.interpret::<true>();
-            }
-        } else {
-            unsafe {
-                interpreter.interpret::<false>();
-            }
-        }
+    let legacy_backend = LEGACY_BACKEND_FLAGS.iter().find(|(flag, _)| args.contains_id(flag));
+    let backend = if let Some(backend) = args.get_one::<String>("backend") {
+        Some(backend.as_str())
+    } else if let Some((_, backend)) = legacy_backend {
+        Some(*backend)
+    } else if args.get_flag("run-native") {
+        Some("c-run")
+    } else if args.get_flag("jit") {
+        Some("jit")
+    } else {
+        None
+    };
+
+    let config = BackendConfig {
+        runtime: options,
+        output_filename: args
+            .get_one::<String>("output")
+            .or_else(|| legacy_backend.and_then(|(flag, _)| args.get_one::<String>(flag)))
+            .cloned(),
+        unchecked: args.get_flag("unchecked"),
+        cflags: args.get_one::<String>("cflags").unwrap().clone(),
+        program_args: args
+            .get_many::<String>("program-args")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+    };
+
+    if backend.is_none()
+        && !args.contains_id("bundle")
+        && (args.contains_id("emit-ops") || args.contains_id("emit-simplified"))
+    {
+        return Ok(());
+    }
+
+    let pass_start = Instant::now();
+    let ops = passes.run(parser.view()?, options)?;
+
+    if let Some(stats) = parser.stats() {
+        eprint!("{}", stats.report(ops.len(), parse_time, pass_start.elapsed()));
+    }
+
+    if args.contains_id("bundle") {
+        let jit = backend == Some("jit");
+        backends.check(if jit { "jit" } else { "interpreter" }, &config)?;
+
+        let bundle = Bundle { ops, jit, options };
+
+        return bundle.write(args.get_one::<String>("bundle").unwrap());
+    }
+
+    exit_with(backends.run(backend.unwrap_or("interpreter"), &ops, &config)?)
+}
+
+/// Finishes with the exit code returned by a backend, which is the panic code of programs that entered a panic
+/// loop and otherwise only non-zero for programs run natively
+fn exit_with(code: i32) -> Result<()> {
+    if code != 0 {
+        std::process::exit(code);
     }
 
     Ok(())
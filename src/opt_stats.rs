@@ -0,0 +1,152 @@
+use crate::{patterns::Pattern, rules};
+use slice_deque::SliceDeque;
+use std::{collections::HashSet, fmt::Write, time::Duration};
+
+/// Name under which the collapsing of linear loops is counted
+pub const LINEAR_LOOPS: &str = "linear_loops";
+
+/// How often each rewrite fired while the compound ops were recognised, and how many basic ops it replaced
+pub struct OptStats {
+    /// Every rewrite in the order they're first tried, those that never fired included
+    pub rewrites: Vec<Rewrite>,
+    /// Basic ops fed to the accumulator
+    pub basic_ops: u64,
+    /// Compound ops that left the accumulator
+    pub compound_ops: u64,
+    /// Time spent recognising compound ops, which happens while the program is parsed
+    pub rewrite_time: Duration,
+    /// Number of basic ops each op in the window stands for
+    weights: SliceDeque<u64>,
+}
+
+pub struct Rewrite {
+    pub name: String,
+    pub fired: u64,
+    /// Basic ops in all the code the rewrite matched, counting those earlier rewrites had already replaced
+    pub replaced: u64,
+}
+
+impl OptStats {
+    pub fn new(patterns: &[Pattern]) -> Self {
+        let rules = rules::all().into_iter().map(|(_, rule)| rule.name.to_string());
+        let patterns = patterns.iter().map(|pattern| pattern.name.clone());
+        // Follow-up rules are listed under every rule they follow, but counted once
+        let mut seen = HashSet::new();
+
+        Self {
+            rewrites: rules
+                .chain(patterns)
+                .chain([LINEAR_LOOPS.to_string()])
+                .filter(|name| seen.insert(name.clone()))
+                .map(|name| Rewrite {
+                    name,
+                    fired: 0,
+                    replaced: 0,
+                })
+                .collect(),
+            basic_ops: 0,
+            compound_ops: 0,
+            rewrite_time: Duration::ZERO,
+            weights: SliceDeque::new(),
+        }
+    }
+
+    /// A basic op joined the back of the window
+    pub(crate) fn push(&mut self) {
+        self.basic_ops += 1;
+        self.weights.push_back(1);
+    }
+
+    /// An op left the front of the window
+    pub(crate) fn pop(&mut self) {
+        self.compound_ops += 1;
+        self.weights.pop_front();
+    }
+
+    /// The ops in the window are being fed through the rewrites again, returning the weights they had
+    pub(crate) fn restart(&mut self) -> Vec<u64> {
+        let weights = self.weights.to_vec();
+        self.weights.clear();
+        weights
+    }
+
+    /// An op that was already in the window joined the back of it again
+    pub(crate) fn requeue(&mut self, weight: u64) {
+        self.weights.push_back(weight);
+    }
+
+    /// The last `matched` of the `len` ops in the window were rewritten, leaving `new_len` ops
+    ///
+    /// Replacements can merge into the ops before them, so whatever isn't known to be untouched is given to the
+    /// first op that's new, or to the last op if there's none.
+    pub(crate) fn rewrote_back(&mut self, name: &str, len: usize, matched: usize, new_len: usize) {
+        let kept = (len - matched).min(new_len);
+        let replaced = self.weights.iter().skip(len - matched).sum();
+        let moved = self.weights.iter().skip(kept).sum::<u64>();
+
+        self.weights.truncate_back(kept);
+
+        for _ in kept..new_len {
+            self.weights.push_back(0);
+        }
+
+        let receiver = kept.min(new_len.saturating_sub(1));
+
+        if let Some(weight) = self.weights.get_mut(receiver) {
+            *weight += moved;
+        }
+
+        self.count(name, replaced);
+    }
+
+    /// The first `matched` ops in the window were rewritten into one
+    pub(crate) fn rewrote_front(&mut self, name: &str, matched: usize) {
+        let replaced = self.weights.drain(..matched).sum();
+        self.weights.push_front(replaced);
+        self.count(name, replaced);
+    }
+
+    fn count(&mut self, name: &str, replaced: u64) {
+        if let Some(rewrite) = self.rewrites.iter_mut().find(|rewrite| rewrite.name == name) {
+            rewrite.fired += 1;
+            rewrite.replaced += replaced;
+        }
+    }
+
+    /// Table of the rewrites followed by how much the whole optimizer shrank the program and how long it took
+    /// Formats the counts along with where the time went, `parse_time` covering the rewrites made while parsing
+    /// and `pass_time` the passes over the whole program afterwards
+    pub fn report(&self, interpreter_ops: usize, parse_time: Duration, pass_time: Duration) -> String {
+        let mut report = format!("{:<36} {:>10} {:>12}\n", "rewrite", "fired", "basic ops");
+
+        for rewrite in &self.rewrites {
+            writeln!(
+                report,
+                "{:<36} {:>10} {:>12}",
+                rewrite.name, rewrite.fired, rewrite.replaced
+            )
+            .unwrap();
+        }
+
+        let reduction = 100.0 * (1.0 - interpreter_ops as f64 / self.basic_ops.max(1) as f64);
+        writeln!(report).unwrap();
+        writeln!(report, "{:<36} {:>10}", "basic ops", self.basic_ops).unwrap();
+        writeln!(report, "{:<36} {:>10}", "compound ops", self.compound_ops).unwrap();
+        writeln!(
+            report,
+            "{:<36} {:>10} ({:.1}% fewer)",
+            "interpreter ops", interpreter_ops, reduction
+        )
+        .unwrap();
+        writeln!(
+            report,
+            "{:<36} {:>10.3?}",
+            "parse time",
+            parse_time.saturating_sub(self.rewrite_time)
+        )
+        .unwrap();
+        writeln!(report, "{:<36} {:>10.3?}", "rewrite time", self.rewrite_time).unwrap();
+        writeln!(report, "{:<36} {:>10.3?}", "pass time", pass_time).unwrap();
+        report
+    }
+}
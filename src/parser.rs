@@ -0,0 +1,136 @@
+use crate::{
+    basic_op::{BasicOp, BasicOpAcc},
+    compound_op::{CompoundOp, CompoundOpAcc},
+    interpreter::RuntimeOptions,
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+    opt_stats::OptStats,
+    passes::Passes,
+    patterns::Pattern,
+};
+use anyhow::Result;
+use std::{fs::File, io::Write};
+
+/// Front end turning source bytes into interpreter ops, optionally logging the intermediate stages
+pub struct Parser {
+    basic_op_acc: BasicOpAcc,
+    compound_op_acc: CompoundOpAcc,
+    interpreter_op_acc: InterpreterOpAcc,
+
+    emit_simplified_filename: Option<File>,
+    emit_ops_file: Option<File>,
+}
+
+impl Parser {
+    pub fn new(
+        emit_simplified_filename: Option<&str>,
+        emit_ops_filename: Option<&str>,
+        passes: Passes,
+        patterns: Vec<Pattern>,
+    ) -> Result<Self> {
+        let emit_simplified_filename = emit_simplified_filename.map(File::create).transpose()?;
+        let emit_ops_file = emit_ops_filename.map(File::create).transpose()?;
+
+        Ok(Self {
+            basic_op_acc: BasicOpAcc::new(),
+            compound_op_acc: CompoundOpAcc::with_patterns(passes, patterns),
+            interpreter_op_acc: InterpreterOpAcc::new(),
+            emit_simplified_filename,
+            emit_ops_file,
+        })
+    }
+
+    /// Counts the rewrites applied to the program from now on
+    pub fn collect_stats(&mut self) {
+        self.compound_op_acc.collect_stats();
+    }
+
+    /// Optimizes the whole program at once when it's flushed rather than through a window as it's fed
+    pub fn whole_program(&mut self) {
+        self.compound_op_acc.whole_program();
+    }
+
+    pub fn stats(&self) -> Option<&OptStats> {
+        self.compound_op_acc.stats()
+    }
+
+    pub fn feed(&mut self, byte: u8) -> Result<()> {
+        if let Some(basic_op) = self.basic_op_acc.feed_byte(byte)? {
+            self.log_simplified_op(&basic_op)?;
+
+            if let Some(compound_op) = self.compound_op_acc.feed(basic_op) {
+                self.log_compound_op(&compound_op)?;
+                self.interpreter_op_acc.feed(compound_op)?;
+            }
+        }
+
+        while let Some(basic_op) = self.basic_op_acc.continued() {
+            self.log_simplified_op(&basic_op)?;
+
+            if let Some(compound_op) = self.compound_op_acc.feed(basic_op) {
+                self.log_compound_op(&compound_op)?;
+                self.interpreter_op_acc.feed(compound_op)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        // Flush basic op accumulator
+        while let Some(basic_op) = self.basic_op_acc.finalize() {
+            self.log_simplified_op(&basic_op)?;
+
+            if let Some(compound_op) = self.compound_op_acc.feed(basic_op) {
+                self.log_compound_op(&compound_op)?;
+                self.interpreter_op_acc.feed(compound_op)?;
+            }
+        }
+
+        // Flush compound op accumulator
+        while let Some(compound_op) = self.compound_op_acc.finalize() {
+            self.log_compound_op(&compound_op)?;
+            self.interpreter_op_acc.feed(compound_op)?;
+        }
+
+        // Flush interpreter op accumulator
+        // (nothing to do)
+
+        Ok(())
+    }
+
+    fn log_simplified_op(&mut self, basic_op: &BasicOp) -> Result<()> {
+        // Write to output if requested
+        if let Some(emit_simplified_filename) = &mut self.emit_simplified_filename {
+            write!(emit_simplified_filename, "{}", basic_op)?;
+        }
+
+        Ok(())
+    }
+
+    fn log_compound_op(&mut self, compound_op: &CompoundOp) -> Result<()> {
+        // Write to output if requested
+        if let Some(emit_ops_file) = &mut self.emit_ops_file {
+            writeln!(emit_ops_file, "{:?}", compound_op)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn view(&self) -> Result<&Vec<InterpreterOp>> {
+        self.interpreter_op_acc.view()
+    }
+}
+
+/// Parses a whole program into interpreter ops, optimized like the command line does by default for the
+/// default runtime options
+pub fn parse(source: &[u8]) -> Result<Vec<InterpreterOp>> {
+    let passes = Passes::default();
+    let mut parser = Parser::new(None, None, passes.clone(), Vec::new())?;
+
+    for &byte in source {
+        parser.feed(byte)?;
+    }
+
+    parser.flush()?;
+    passes.run(parser.view()?, RuntimeOptions::default())
+}
@@ -0,0 +1,226 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{scan_left, scan_right, Interpreter, RuntimeOptions},
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+};
+use anyhow::Result;
+
+/// Ops run ahead of time before giving up on reaching the first input
+const MAX_STEPS: usize = 1 << 26;
+
+/// Largest output and tape image the evaluated ops are replaced with
+const MAX_IMAGE: usize = 1 << 20;
+
+/// How many times longer than the program the ops resuming it may get
+const MAX_GROWTH: usize = 4;
+
+/// Runs the program ahead of time from the initial tape until it first reads input, replacing the ops it ran
+/// with the output they produced and the tape they left behind
+///
+/// The program resumes at the op the evaluation stopped at. When that's inside loops, the rest of each loop's
+/// body is followed by the whole loop again, so the ops still nest properly. A program that never reads input
+/// and finishes within the step bound is left as nothing but its output.
+pub fn partial_eval(ops: &[InterpreterOp], options: RuntimeOptions) -> Result<Vec<InterpreterOp>> {
+    let mut cells = vec![0u8; options.tape_size];
+    let mut output = Vec::new();
+    let origin = options.tape_origin;
+    let (instr_i, cell_i) = Interpreter::new(ops, options).run_prefix(&mut cells, origin, &mut output, MAX_STEPS)?;
+
+    if instr_i == 0 || output.len() > MAX_IMAGE {
+        return Ok(ops.to_vec());
+    }
+
+    let mut evaluated = Vec::new();
+
+    if !output.is_empty() {
+        evaluated.push(CompoundOp::PrintStatic(output.clone()));
+    }
+
+    if instr_i < ops.len() {
+        let rest = resume(ops, instr_i);
+
+        if rest.len() > MAX_GROWTH * ops.len() {
+            return Ok(ops.to_vec());
+        }
+
+        // `PrintStatic` leaves the last byte printed in the current cell, so that cell is part of the image too
+        let written = cells
+            .iter()
+            .position(|&cell| cell != 0)
+            .zip(cells.iter().rposition(|&cell| cell != 0));
+        let image = match written {
+            Some((first, last)) if output.is_empty() => Some((first, last)),
+            Some((first, last)) => Some((first.min(origin), last.max(origin))),
+            None if output.is_empty() => None,
+            None => Some((origin, origin)),
+        };
+
+        if let Some((first, last)) = image {
+            if last - first >= MAX_IMAGE {
+                return Ok(ops.to_vec());
+            }
+
+            evaluated.push(CompoundOp::SetCells(
+                first as i64 - origin as i64,
+                cells[first..=last].to_vec(),
+            ));
+        }
+
+        if cell_i != origin {
+            evaluated.push(CompoundOp::BasicOp(BasicOp::Shift(cell_i as i64 - origin as i64)));
+        }
+
+        evaluated.extend(rest);
+    }
+
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in evaluated {
+        acc.feed(op)?;
+    }
+
+    Ok(acc.view()?.clone())
+}
+
+/// Ops that carry on from the op at `instr_i` as if the program had just arrived there
+fn resume(ops: &[InterpreterOp], instr_i: usize) -> Vec<CompoundOp> {
+    let mut enclosing = Vec::new();
+
+    for (i, op) in ops[..instr_i].iter().enumerate() {
+        match op {
+            InterpreterOp::LoopStart(_) | InterpreterOp::IfStart(_) => enclosing.push(i),
+            InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => _ = enclosing.pop(),
+            InterpreterOp::CompoundOp(_) => (),
+        }
+    }
+
+    let mut rest = Vec::new();
+    let mut from = instr_i;
+
+    // The end of a loop's body checks the cell just like the start of the loop, so the loop can simply follow
+    for &start in enclosing.iter().rev() {
+        let end = match ops[start] {
+            InterpreterOp::LoopStart(distance) | InterpreterOp::IfStart(distance) => start + distance,
+            _ => unreachable!("Only loop starts are enclosing"),
+        };
+
+        rest.extend(ops[from..end].iter().map(compound_op));
+        rest.extend(ops[start..=end].iter().map(compound_op));
+        from = end + 1;
+    }
+
+    rest.extend(ops[from..].iter().map(compound_op));
+    rest
+}
+
+fn compound_op(op: &InterpreterOp) -> CompoundOp {
+    match op {
+        InterpreterOp::LoopStart(_) | InterpreterOp::IfStart(_) => CompoundOp::BasicOp(BasicOp::LoopStart),
+        InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => CompoundOp::BasicOp(BasicOp::LoopEnd),
+        InterpreterOp::CompoundOp(op) => op.clone(),
+    }
+}
+
+/// Whether the op can run ahead of time from `cell_i`, which it can't if it reads input, panics, or could
+/// touch cells off the tape
+pub(crate) fn can_evaluate(op: &InterpreterOp, cells: &[u8], cell_i: usize) -> bool {
+    let op = match op {
+        InterpreterOp::LoopStart(_)
+        | InterpreterOp::LoopEnd(_)
+        | InterpreterOp::IfStart(_)
+        | InterpreterOp::IfEnd(_) => return cell_i < cells.len(),
+        InterpreterOp::CompoundOp(op) => op,
+    };
+
+    let (lowest, highest) = match op {
+        CompoundOp::ScanRight(stride) => {
+            return cell_i < cells.len() && scan_right(cells, cell_i, *stride as usize) < cells.len()
+        }
+        CompoundOp::ScanLeft(stride) => {
+            return cell_i < cells.len() && scan_left(cells, cell_i, *stride as usize) < cells.len()
+        }
+        _ => match reach(op) {
+            Some(reach) => reach,
+            None => return false,
+        },
+    };
+
+    let cell_i = cell_i as i64;
+    cell_i + lowest >= 0 && cell_i + highest < cells.len() as i64
+}
+
+/// Lowest and highest cells relative to the pointer that the op touches or moves the pointer to, `None` for
+/// ops that read input or panic, or whose cells depend on the tape
+pub(crate) fn reach(op: &CompoundOp) -> Option<(i64, i64)> {
+    let reach = match op {
+        CompoundOp::BasicOp(BasicOp::Shift(amount)) | CompoundOp::MoveAdd(amount) | CompoundOp::MoveSet(amount) => {
+            around([*amount])
+        }
+        CompoundOp::BasicOp(BasicOp::ChangeBy(_) | BasicOp::Output(_))
+        | CompoundOp::Zero
+        | CompoundOp::Set(_)
+        | CompoundOp::PrintStatic(_) => (0, 0),
+        CompoundOp::ZeroAdvance(amount) => (0, *amount as i64),
+        CompoundOp::ZeroRetreat(amount) => (-(*amount as i64), 0),
+        CompoundOp::MoveAdd2(offset1, offset2) => around([*offset1, *offset2]),
+        CompoundOp::Dupe(offset) => around([*offset, 1]),
+        CompoundOp::Equals | CompoundOp::NotEquals | CompoundOp::BitNeg => (0, 1),
+        CompoundOp::ShiftLeftLogical => (-2, 0),
+        CompoundOp::ShiftRightLogical => (-2, 3),
+        CompoundOp::LessThan | CompoundOp::GreaterThan | CompoundOp::LessThanEqual | CompoundOp::GreaterThanEqual => {
+            (-2, 1)
+        }
+        CompoundOp::BitAnd => (-7, 2),
+        CompoundOp::WellBehavedDivMod(amount) => around([-2, 3, *amount]),
+        CompoundOp::MoveCellsStaticReverse(offset, count) => {
+            let count = *count as i64;
+            around([-count, offset + 1 - count, *offset])
+        }
+        CompoundOp::AddU32
+        | CompoundOp::MulU32
+        | CompoundOp::SubU32
+        | CompoundOp::LessThanU32
+        | CompoundOp::EqualsU32
+        | CompoundOp::AndU32
+        | CompoundOp::OrU32
+        | CompoundOp::XorU32
+        | CompoundOp::ShiftLeftU32
+        | CompoundOp::ShiftRightU32
+        | CompoundOp::DivModU32 => (-8, 0),
+        CompoundOp::MulAddMany(targets) => around(targets.iter().map(|(offset, _)| *offset)),
+        CompoundOp::ChangeByAt(offset, _) | CompoundOp::SetAt(offset, _) | CompoundOp::OutputAt(offset, _) => {
+            around([*offset])
+        }
+        CompoundOp::MoveAddAt(from, to) | CompoundOp::MoveSetAt(from, to) => around([*from, *to]),
+        CompoundOp::MoveAdd2At(from, to1, to2) => around([*from, *to1, *to2]),
+        CompoundOp::MulAddManyAt(from, targets) => {
+            around(std::iter::once(*from).chain(targets.iter().map(|(offset, _)| *offset)))
+        }
+        CompoundOp::SetCells(offset, values) => around([*offset, offset + values.len() as i64 - 1]),
+        CompoundOp::Affine(updates) => around(
+            updates
+                .iter()
+                .flat_map(|update| std::iter::once(update.offset).chain(update.terms.iter().map(|&(cell, _)| cell))),
+        ),
+        CompoundOp::BasicOp(BasicOp::Input(_) | BasicOp::LoopStart | BasicOp::LoopEnd)
+        | CompoundOp::InputAt(..)
+        | CompoundOp::Panic(_)
+        | CompoundOp::MoveCellDynamicU8(_)
+        | CompoundOp::MoveCellDynamicU16(_)
+        | CompoundOp::MoveCellDynamicU32(_)
+        | CompoundOp::CopyCellDynamicU8(_)
+        | CompoundOp::CopyCellDynamicU32(_)
+        | CompoundOp::ScanRight(_)
+        | CompoundOp::ScanLeft(_) => return None,
+    };
+
+    Some(reach)
+}
+
+/// Lowest and highest of the offsets and the pointer itself
+fn around(offsets: impl IntoIterator<Item = i64>) -> (i64, i64) {
+    offsets.into_iter().fold((0, 0), |(lowest, highest), offset| {
+        (lowest.min(offset), highest.max(offset))
+    })
+}
@@ -0,0 +1,112 @@
+use crate::{
+    ifs::ifs, interpreter::RuntimeOptions, interpreter_op::InterpreterOp, known_zero::known_zero,
+    partial_eval::partial_eval, rules, sink_shifts::sink_shifts,
+};
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+
+/// Lowest optimization level a rule or pass runs at, `-O0` running none of them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Idioms that only replace loops with what they always do
+    Safe = 1,
+    /// Fusions of whole arithmetic algorithms
+    Arithmetic = 2,
+    /// Fusions that assume the algorithm's scratch cells, which a program might not keep to
+    Unsound = 3,
+}
+
+/// Highest optimization level, which every rule and pass runs at
+pub const MAX_LEVEL: u8 = Level::Unsound as u8;
+
+/// Passes over the whole program besides the rules, each with its level and description
+pub const PASSES: &[(&str, Level, &str)] = &[
+    (
+        "linear_loops",
+        Level::Arithmetic,
+        "Loops only changing cells, collapsed into multiplications",
+    ),
+    (
+        "partial_eval",
+        Level::Arithmetic,
+        "Running the program ahead of time until it first reads input",
+    ),
+    ("known_zero", Level::Safe, "Dropping work on cells known to be zero"),
+    ("sink_shifts", Level::Safe, "Moving the pointer once per basic block"),
+    (
+        "ifs",
+        Level::Safe,
+        "Loops that run at most once, tested only on the way in",
+    ),
+];
+
+/// The rules and passes that are enabled, chosen by optimization level and then by name
+#[derive(Clone, Debug, Default)]
+pub struct Passes {
+    disabled: HashSet<&'static str>,
+}
+
+impl Passes {
+    /// Enables the rules and passes at or below the level
+    pub fn at_level(level: u8) -> Self {
+        let rules = rules::all().into_iter().map(|(_, rule)| (rule.name, rule.level));
+        let passes = PASSES.iter().map(|&(name, level, _)| (name, level));
+
+        Self {
+            disabled: rules
+                .chain(passes)
+                .filter(|&(_, at)| at as u8 > level)
+                .map(|(name, _)| name)
+                .collect(),
+        }
+    }
+
+    pub fn enable(&mut self, name: &str) -> Result<()> {
+        self.disabled.remove(find(name)?);
+        Ok(())
+    }
+
+    pub fn disable(&mut self, name: &str) -> Result<()> {
+        self.disabled.insert(find(name)?);
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name)
+    }
+
+    /// Runs the enabled passes over the parsed program
+    pub fn run(&self, ops: &[InterpreterOp], options: RuntimeOptions) -> Result<Vec<InterpreterOp>> {
+        let mut ops = ops.to_vec();
+
+        if self.is_enabled("partial_eval") {
+            ops = partial_eval(&ops, options)?;
+        }
+
+        if self.is_enabled("known_zero") {
+            ops = known_zero(&ops, options)?;
+        }
+
+        if self.is_enabled("sink_shifts") {
+            ops = sink_shifts(&ops)?;
+        }
+
+        // The other passes turn ifs back into loops
+        if self.is_enabled("ifs") {
+            ops = ifs(&ops)?;
+        }
+
+        Ok(ops)
+    }
+}
+
+/// Name of the rule or pass as it's stored
+fn find(name: &str) -> Result<&'static str> {
+    let rules = rules::all().into_iter().map(|(_, rule)| rule.name);
+    let passes = PASSES.iter().map(|&(name, _, _)| name);
+
+    rules
+        .chain(passes)
+        .find(|&known| known == name)
+        .ok_or_else(|| anyhow!("[error] Unknown rule or pass {}, see --list-rules", name))
+}
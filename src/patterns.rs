@@ -0,0 +1,475 @@
+use crate::{
+    basic_op::{BasicOp, BasicOpAcc},
+    compound_op::{AffineUpdate, CompoundOp, CompoundOpAcc, WINDOW_SIZE},
+    interpreter::{Interpreter, RuntimeOptions},
+    interpreter_op::InterpreterOp,
+    passes::Passes,
+    rules,
+};
+use anyhow::{anyhow, Result};
+use slice_deque::SliceDeque;
+use std::fs;
+
+/// Random tapes each pattern is checked against before it's accepted
+const TRIALS: usize = 256;
+
+/// Cells on either side of the pointer available to a pattern while it's checked
+const TAPE_RADIUS: usize = 8192;
+
+/// How far the ops of a replacement may reach from the pointer, so they stay on the checking tape
+const MAX_REACH: u64 = 4096;
+
+/// Steps the literal code may take on one tape before it's assumed not to finish
+const MAX_STEPS: usize = 1 << 20;
+
+/// A user-defined rewrite, replacing the ops some literal code optimizes to with ops known to be equivalent
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub name: String,
+    /// Ops the built-in rules turn the literal code into, which are matched at the end of the window
+    pub matched: Vec<CompoundOp>,
+    pub replacement: Vec<CompoundOp>,
+}
+
+/// Loads the patterns in a file, checking each against its literal code on random tapes
+///
+/// Each line holds one pattern written as `name: code => Op, Op, ...`, where the ops are `CompoundOp`s
+/// such as `MoveAdd(2)` or `Affine([1] = [1] + 2*[0], [0] = 0)`. Blank lines and lines starting with `#`
+/// are skipped.
+pub fn load(filename: &str, passes: &Passes) -> Result<Vec<Pattern>> {
+    let source = fs::read_to_string(filename)
+        .map_err(|err| anyhow!("[error] Could not read patterns from {}: {}", filename, err))?;
+
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(line_i, line)| {
+            parse_pattern(line, passes).map_err(|err| anyhow!("[error] {}:{}: {}", filename, line_i + 1, err))
+        })
+        .collect()
+}
+
+/// Replaces the end of `building` if it matches one of the patterns, trying them in order, returning the pattern
+/// applied
+pub(crate) fn apply<'a>(patterns: &'a [Pattern], building: &mut SliceDeque<CompoundOp>) -> Option<&'a Pattern> {
+    let pattern = patterns.iter().find(|pattern| building.ends_with(&pattern.matched))?;
+
+    building.truncate_back(building.len() - pattern.matched.len());
+
+    for op in &pattern.replacement {
+        rules::push(building, op.clone());
+    }
+
+    Some(pattern)
+}
+
+fn parse_pattern(line: &str, passes: &Passes) -> Result<Pattern> {
+    let (name, rest) = line
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Expected `name: code => ops`"))?;
+    let (code, meaning) = rest
+        .split_once("=>")
+        .ok_or_else(|| anyhow!("Expected `=>` between the code and its ops"))?;
+    let name = name.trim().to_string();
+
+    let literal = parse_literal(code)?;
+    let replacement = split_top_level(meaning)
+        .into_iter()
+        .map(parse_op)
+        .collect::<Result<Vec<_>>>()?;
+
+    let matched = optimize(&name, &literal, passes)?;
+
+    if matched.is_empty() {
+        return Err(anyhow!("Pattern {} has no code to match", name));
+    }
+
+    let reach = replacement
+        .iter()
+        .fold(0u64, |reach, op| reach.saturating_add(op_reach(op)));
+
+    if reach > MAX_REACH {
+        return Err(anyhow!(
+            "Pattern {} reaches too far from the pointer to be checked",
+            name
+        ));
+    }
+
+    check(&name, &literal, &replacement)?;
+
+    Ok(Pattern {
+        name,
+        matched,
+        replacement,
+    })
+}
+
+/// Parses Brainfuck code, which may only move the pointer and change cells
+fn parse_literal(code: &str) -> Result<Vec<BasicOp>> {
+    let mut acc = BasicOpAcc::new();
+    let mut ops = Vec::new();
+
+    for byte in code.trim().bytes() {
+        let op = acc
+            .feed_byte(byte)
+            .map_err(|_| anyhow!("Invalid character {:?} in the code", byte as char))?;
+        ops.extend(op);
+        ops.extend(std::iter::from_fn(|| acc.continued()));
+    }
+
+    ops.extend(std::iter::from_fn(|| acc.finalize()));
+
+    if ops
+        .iter()
+        .any(|op| matches!(op, BasicOp::Input(_) | BasicOp::Output(_)))
+    {
+        return Err(anyhow!("The code of a pattern can't read or write"));
+    }
+
+    jump_table(&ops)?;
+    Ok(ops)
+}
+
+/// Ops the enabled built-in rules turn the code into, as they appear at the end of the window
+fn optimize(name: &str, literal: &[BasicOp], passes: &Passes) -> Result<Vec<CompoundOp>> {
+    let mut acc = CompoundOpAcc::with_patterns(passes.clone(), Vec::new());
+
+    for &op in literal {
+        if acc.feed(op).is_some() {
+            return Err(anyhow!("Pattern {} is longer than the {} op window", name, WINDOW_SIZE));
+        }
+    }
+
+    Ok(acc.window().to_vec())
+}
+
+/// Splits at the commas that aren't inside parentheses
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(text[start..].trim());
+    parts
+}
+
+fn parse_op(text: &str) -> Result<CompoundOp> {
+    let (name, args) = match text.split_once('(') {
+        Some((name, args)) => {
+            let args = args
+                .strip_suffix(')')
+                .ok_or_else(|| anyhow!("Missing `)` after {}", text))?;
+            (name.trim(), split_top_level(args))
+        }
+        None => (text, Vec::new()),
+    };
+
+    if name == "Affine" {
+        return parse_affine(&args);
+    }
+
+    let op = match (name, &args[..]) {
+        ("Shift", [amount]) => CompoundOp::BasicOp(BasicOp::Shift(parse_int(amount)?)),
+        ("ChangeBy", [amount]) => CompoundOp::BasicOp(BasicOp::ChangeBy(wrap(parse_int(amount)?))),
+        ("Zero", []) => CompoundOp::Zero,
+        ("ZeroAdvance", [amount]) => CompoundOp::ZeroAdvance(parse_count(amount)?),
+        ("ZeroRetreat", [amount]) => CompoundOp::ZeroRetreat(parse_count(amount)?),
+        ("Set", [value]) => CompoundOp::Set(wrap(parse_int(value)?)),
+        ("MoveAdd", [offset]) => CompoundOp::MoveAdd(parse_int(offset)?),
+        ("MoveAdd2", [offset1, offset2]) => CompoundOp::MoveAdd2(parse_int(offset1)?, parse_int(offset2)?),
+        ("MoveSet", [offset]) => CompoundOp::MoveSet(parse_int(offset)?),
+        ("Dupe", [offset]) => CompoundOp::Dupe(parse_int(offset)?),
+        ("Equals", []) => CompoundOp::Equals,
+        ("NotEquals", []) => CompoundOp::NotEquals,
+        ("ShiftLeftLogical", []) => CompoundOp::ShiftLeftLogical,
+        ("ShiftRightLogical", []) => CompoundOp::ShiftRightLogical,
+        ("LessThan", []) => CompoundOp::LessThan,
+        ("GreaterThan", []) => CompoundOp::GreaterThan,
+        ("LessThanEqual", []) => CompoundOp::LessThanEqual,
+        ("GreaterThanEqual", []) => CompoundOp::GreaterThanEqual,
+        ("BitAnd", []) => CompoundOp::BitAnd,
+        ("BitNeg", []) => CompoundOp::BitNeg,
+        ("WellBehavedDivMod", [shift]) => CompoundOp::WellBehavedDivMod(parse_int(shift)?),
+        ("MoveCellsStaticReverse", [offset, count]) => {
+            CompoundOp::MoveCellsStaticReverse(parse_int(offset)?, parse_count(count)?)
+        }
+        ("AddU32", []) => CompoundOp::AddU32,
+        ("MulU32", []) => CompoundOp::MulU32,
+        ("SubU32", []) => CompoundOp::SubU32,
+        ("LessThanU32", []) => CompoundOp::LessThanU32,
+        ("EqualsU32", []) => CompoundOp::EqualsU32,
+        ("AndU32", []) => CompoundOp::AndU32,
+        ("OrU32", []) => CompoundOp::OrU32,
+        ("XorU32", []) => CompoundOp::XorU32,
+        ("ShiftLeftU32", []) => CompoundOp::ShiftLeftU32,
+        ("ShiftRightU32", []) => CompoundOp::ShiftRightU32,
+        ("DivModU32", []) => CompoundOp::DivModU32,
+        (
+            "Panic" | "PrintStatic" | "MoveCellDynamicU8" | "MoveCellDynamicU16" | "MoveCellDynamicU32"
+            | "CopyCellDynamicU8" | "CopyCellDynamicU32" | "ScanRight" | "ScanLeft" | "SetCells",
+            _,
+        ) => return Err(anyhow!("{} can't be used in a pattern", name)),
+        _ => return Err(anyhow!("Unknown op {}", text)),
+    };
+
+    Ok(op)
+}
+
+/// Parses updates written as `[offset] = constant + coefficient*[offset] - [offset] ...`
+fn parse_affine(args: &[&str]) -> Result<CompoundOp> {
+    let mut updates: Vec<AffineUpdate> = Vec::new();
+
+    for update in args {
+        let (target, expression) = update
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected `[offset] = value` in Affine, found {}", update))?;
+        let offset = parse_cell(target.trim())?;
+
+        if updates.iter().any(|update| update.offset == offset) {
+            return Err(anyhow!("Cell [{}] is updated twice in one Affine", offset));
+        }
+
+        let mut constant = 0u8;
+        let mut terms: Vec<(i64, u8)> = Vec::new();
+
+        for (negative, term) in split_terms(expression) {
+            let (coefficient, cell) = match term.split_once('*') {
+                Some((coefficient, cell)) => (wrap(parse_int(coefficient)?), Some(parse_cell(cell.trim())?)),
+                None if term.starts_with('[') => (1, Some(parse_cell(term)?)),
+                None => (wrap(parse_int(term)?), None),
+            };
+            let coefficient = if negative {
+                coefficient.wrapping_neg()
+            } else {
+                coefficient
+            };
+
+            match cell {
+                Some(cell) => match terms.iter_mut().find(|(existing, _)| *existing == cell) {
+                    Some((_, existing)) => *existing = existing.wrapping_add(coefficient),
+                    None => terms.push((cell, coefficient)),
+                },
+                None => constant = constant.wrapping_add(coefficient),
+            }
+        }
+
+        terms.retain(|&(_, coefficient)| coefficient != 0);
+        updates.push(AffineUpdate {
+            offset,
+            constant,
+            terms,
+        });
+    }
+
+    if updates.is_empty() {
+        return Err(anyhow!("Affine needs at least one update"));
+    }
+
+    Ok(CompoundOp::Affine(updates))
+}
+
+/// Splits an expression into its terms, each with whether it's subtracted
+fn split_terms(expression: &str) -> Vec<(bool, &str)> {
+    let mut terms = Vec::new();
+    let mut negative = false;
+    let mut start = 0;
+    let mut in_cell = false;
+
+    for (i, c) in expression.char_indices() {
+        match c {
+            '[' => in_cell = true,
+            ']' => in_cell = false,
+            '+' | '-' if !in_cell => {
+                let term = expression[start..i].trim();
+
+                // A sign at the start belongs to the first term
+                if !term.is_empty() {
+                    terms.push((negative, term));
+                    negative = false;
+                }
+
+                negative ^= c == '-';
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    terms.push((negative, expression[start..].trim()));
+    terms
+}
+
+fn parse_cell(text: &str) -> Result<i64> {
+    text.strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+        .ok_or_else(|| anyhow!("Expected a cell such as [1], found {}", text))
+        .and_then(|offset| parse_int(offset.trim()))
+}
+
+fn parse_int(text: &str) -> Result<i64> {
+    text.trim()
+        .parse()
+        .map_err(|_| anyhow!("Expected a number, found {}", text))
+}
+
+fn parse_count(text: &str) -> Result<u64> {
+    text.trim()
+        .parse()
+        .map_err(|_| anyhow!("Expected a count, found {}", text))
+}
+
+/// Reduces a number to a cell value, wrapping around like the cells do
+fn wrap(value: i64) -> u8 {
+    value.rem_euclid(256) as u8
+}
+
+/// Upper bound on how far an op moves the pointer and on the distance of the cells it touches
+fn op_reach(op: &CompoundOp) -> u64 {
+    let distance = match op {
+        CompoundOp::BasicOp(BasicOp::Shift(amount))
+        | CompoundOp::MoveAdd(amount)
+        | CompoundOp::MoveSet(amount)
+        | CompoundOp::Dupe(amount)
+        | CompoundOp::WellBehavedDivMod(amount) => amount.unsigned_abs(),
+        CompoundOp::ZeroAdvance(amount) | CompoundOp::ZeroRetreat(amount) => *amount,
+        CompoundOp::MoveAdd2(offset1, offset2) => offset1.unsigned_abs().saturating_add(offset2.unsigned_abs()),
+        CompoundOp::MoveCellsStaticReverse(offset, count) => offset.unsigned_abs().saturating_add(*count),
+        CompoundOp::Affine(updates) => updates
+            .iter()
+            .flat_map(|update| std::iter::once(update.offset).chain(update.terms.iter().map(|&(cell, _)| cell)))
+            .map(i64::unsigned_abs)
+            .max()
+            .unwrap_or(0),
+        _ => 0,
+    };
+
+    // The fixed layout ops such as AddU32 touch a handful of cells around the pointer
+    distance.saturating_add(16)
+}
+
+/// Runs the literal code and the replacement on the same random tapes, failing if they ever disagree
+fn check(name: &str, literal: &[BasicOp], replacement: &[CompoundOp]) -> Result<()> {
+    let jumps = jump_table(literal)?;
+    let ops: Vec<InterpreterOp> = replacement.iter().cloned().map(InterpreterOp::CompoundOp).collect();
+    let interpreter = Interpreter::new(&ops, RuntimeOptions::default());
+    let mut random = Random(0x9e37_79b9_7f4a_7c15);
+
+    for trial in 0..TRIALS {
+        let mut expected = vec![0u8; 2 * TAPE_RADIUS];
+
+        // Half the tapes only hold a few values, to cover cells that are equal, zero or about to wrap
+        for cell in expected.iter_mut() {
+            *cell = if trial % 2 == 0 {
+                random.next() as u8
+            } else {
+                [0, 1, 2, 255][random.next() as usize % 4]
+            };
+        }
+
+        let mut actual = expected.clone();
+        let expected_i = run_literal(literal, &jumps, &mut expected, TAPE_RADIUS)
+            .map_err(|err| anyhow!("The code of pattern {} can't be checked: {}", name, err))?;
+        let actual_i = unsafe { interpreter.run::<true>(&mut actual, TAPE_RADIUS, &mut std::io::sink()) }
+            .map_err(|err| anyhow!("The replacement of pattern {} can't be checked: {}", name, err))?;
+
+        if expected_i != actual_i {
+            return Err(anyhow!(
+                "Pattern {} leaves the pointer at {} instead of {} on a random tape",
+                name,
+                actual_i as i64 - TAPE_RADIUS as i64,
+                expected_i as i64 - TAPE_RADIUS as i64
+            ));
+        }
+
+        if let Some(cell_i) = (0..expected.len()).find(|&cell_i| expected[cell_i] != actual[cell_i]) {
+            return Err(anyhow!(
+                "Pattern {} leaves [{}] at {} instead of {} on a random tape",
+                name,
+                cell_i as i64 - TAPE_RADIUS as i64,
+                actual[cell_i],
+                expected[cell_i]
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Index of the matching bracket for every loop op
+fn jump_table(ops: &[BasicOp]) -> Result<Vec<usize>> {
+    let mut jumps = vec![0; ops.len()];
+    let mut starts = Vec::new();
+
+    for (i, op) in ops.iter().enumerate() {
+        match op {
+            BasicOp::LoopStart => starts.push(i),
+            BasicOp::LoopEnd => {
+                let start = starts
+                    .pop()
+                    .ok_or_else(|| anyhow!("Instruction ']' is missing match"))?;
+                jumps[start] = i;
+                jumps[i] = start;
+            }
+            _ => (),
+        }
+    }
+
+    if !starts.is_empty() {
+        return Err(anyhow!("Instruction '[' is missing match"));
+    }
+
+    Ok(jumps)
+}
+
+/// Runs basic ops directly, as the reference the replacement is compared against
+fn run_literal(ops: &[BasicOp], jumps: &[usize], cells: &mut [u8], mut cell_i: usize) -> Result<usize> {
+    let mut instr_i = 0;
+
+    for _ in 0..MAX_STEPS {
+        let Some(op) = ops.get(instr_i) else {
+            return Ok(cell_i);
+        };
+
+        match *op {
+            BasicOp::ChangeBy(amount) => cells[cell_i] = cells[cell_i].wrapping_add(amount),
+            BasicOp::Shift(amount) => {
+                cell_i = cell_i
+                    .checked_add_signed(amount as isize)
+                    .filter(|&cell_i| cell_i < cells.len())
+                    .ok_or_else(|| anyhow!("it moves more than {} cells from where it starts", TAPE_RADIUS))?;
+            }
+            BasicOp::LoopStart if cells[cell_i] == 0 => instr_i = jumps[instr_i],
+            BasicOp::LoopEnd if cells[cell_i] != 0 => instr_i = jumps[instr_i],
+            _ => (),
+        }
+
+        instr_i += 1;
+    }
+
+    Err(anyhow!("it doesn't finish within {} steps", MAX_STEPS))
+}
+
+/// Xorshift generator, seeded the same way every time so a pattern is either always accepted or never
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,3724 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    passes::{Level, Passes},
+};
+use slice_deque::SliceDeque;
+
+use BasicOp::*;
+use CompoundOp::*;
+
+/// A rewrite of the ops at the end of the accumulator into an equivalent, shorter sequence
+pub struct Rule {
+    pub name: &'static str,
+    /// Taken from the rule's doc comment
+    pub description: &'static str,
+    /// Number of ops the rule matches and replaces, which is the number of patterns it lists
+    pub len: usize,
+    /// Source of the patterns, in order
+    pub patterns: &'static [&'static str],
+    /// Source of the patterns matching the earlier ops the rule keeps
+    pub context: &'static [&'static str],
+    /// Lowest optimization level the rule is applied at
+    pub level: Level,
+    /// Rules tried once this one has been applied, for algorithms that end in its replacement
+    pub follow_ups: &'static [Rule],
+    apply: fn(&mut SliceDeque<CompoundOp>) -> bool,
+}
+
+impl Rule {
+    /// Applies only this rule to the end of `ops`, returning the rewritten ops if it matched
+    pub fn rewrite(&self, ops: &[CompoundOp]) -> Option<Vec<CompoundOp>> {
+        let mut building = SliceDeque::with_capacity(ops.len());
+
+        // `SliceDeque::extend` trips the standard library's debug checks
+        for op in ops {
+            building.push_back(op.clone());
+        }
+
+        if (self.apply)(&mut building) {
+            Some(building.to_vec())
+        } else {
+            None
+        }
+    }
+}
+
+/// Builds a table of rules, each written as `name (Level): [patterns] if guard => [replacement]`
+///
+/// The patterns match the most recent ops and are all replaced, so the number of ops removed always
+/// matches the pattern. `after [patterns]` before the patterns matches earlier ops that are kept, and a
+/// trailing `, then TABLE` names the rules to try once the rule has been applied. Replacement ops can
+/// also be `Option`s, with `None` leaving nothing behind.
+macro_rules! rules {
+    (@follow_ups) => {
+        &[]
+    };
+    (@follow_ups $follow_ups:ident) => {
+        $follow_ups
+    };
+    ($(
+        $(#[doc = $doc:literal])+
+        $name:ident ($level:ident): $(after [$($context:pat),+])? [$($op:pat),+ $(,)?] $(if $guard:expr)?
+            => [$($replacement:expr),+ $(,)?] $(, then $follow_ups:ident)?;
+    )+) => {
+        &[$(
+            Rule {
+                name: stringify!($name),
+                description: concat!($($doc),+),
+                len: [$(stringify!($op)),+].len(),
+                patterns: &[$(stringify!($op)),+],
+                context: &[$($(stringify!($context)),+)?],
+                level: Level::$level,
+                follow_ups: rules!(@follow_ups $($follow_ups)?),
+                apply: |building| {
+                    let replacement: Vec<Option<CompoundOp>> = match &building[..] {
+                        [.., $($($context,)+)? $($op),+] $(if $guard)? => vec![$(optional($replacement)),+],
+                        _ => return false,
+                    };
+
+                    building.truncate_back(building.len() - [$(stringify!($op)),+].len());
+
+                    for op in replacement.into_iter().flatten() {
+                        push(building, op);
+                    }
+
+                    true
+                },
+            },
+        )+]
+    };
+}
+
+/// Applies the first of the enabled rules that matches the end of `building`, returning it so its follow-up rules
+/// can be tried next
+pub(crate) fn apply(
+    rules: &'static [Rule],
+    passes: &Passes,
+    building: &mut SliceDeque<CompoundOp>,
+) -> Option<&'static Rule> {
+    rules
+        .iter()
+        .find(|rule| passes.is_enabled(rule.name) && (rule.apply)(building))
+}
+
+/// Every rule in the order they're tried, along with how deeply it's nested in follow-up rules
+pub fn all() -> Vec<(usize, &'static Rule)> {
+    fn visit(rules: &'static [Rule], depth: usize, all: &mut Vec<(usize, &'static Rule)>) {
+        for rule in rules {
+            all.push((depth, rule));
+            visit(rule.follow_ups, depth + 1, all);
+        }
+    }
+
+    let mut all = Vec::new();
+    visit(RULES, 0, &mut all);
+    all
+}
+
+/// Looks up a rule by name, including follow-up rules
+pub fn find(name: &str) -> Option<&'static Rule> {
+    all().into_iter().map(|(_, rule)| rule).find(|rule| rule.name == name)
+}
+
+/// Appends an op, merging it into the previous op where the two combine into one
+pub(crate) fn push(building: &mut SliceDeque<CompoundOp>, op: CompoundOp) {
+    match (building.back_mut(), op) {
+        // Zeroing twice is redundant
+        (Some(Zero), Zero) => {}
+        (Some(ZeroAdvance(existing_amount)), ZeroAdvance(amount)) => *existing_amount += amount,
+        (Some(ZeroRetreat(existing_amount)), ZeroRetreat(amount)) => *existing_amount += amount,
+        (Some(PrintStatic(content)), PrintStatic(string)) => content.extend(string),
+        // Earlier sets of the same cell are overwritten
+        (_, Set(value)) => {
+            while let Some(Set(_)) = building.back() {
+                building.truncate_back(building.len() - 1);
+            }
+
+            building.push_back(Set(value));
+        }
+        (_, op) => building.push_back(op),
+    }
+}
+
+fn optional(op: impl Into<Option<CompoundOp>>) -> Option<CompoundOp> {
+    op.into()
+}
+
+/// Shift left over from an algorithm, nothing if it's zero
+fn shift(amount: i64) -> Option<CompoundOp> {
+    (amount != 0).then_some(BasicOp(Shift(amount)))
+}
+
+/// Zero advance left over from an algorithm, nothing if it's zero
+fn zero_advance(amount: u64) -> Option<CompoundOp> {
+    (amount != 0).then_some(ZeroAdvance(amount))
+}
+
+/// Rules tried in order on every op fed to the accumulator, only the first that matches is applied
+pub const RULES: &[Rule] = rules! {
+    /// Loop over a cell known to be non-zero, which never exits
+    panic_loop (Safe): [Set(value), BasicOp(LoopStart), BasicOp(LoopEnd)] if *value != 0 => [Panic(*value)];
+
+    /// Loop that counts a cell down or up to zero
+    zero (Safe): [BasicOp(LoopStart), BasicOp(ChangeBy(1 | u8::MAX)), BasicOp(LoopEnd)] => [Zero];
+
+    /// Loop moving right until it finds a zero cell
+    scan_right (Safe): [BasicOp(LoopStart), BasicOp(Shift(stride)), BasicOp(LoopEnd)] if *stride > 0 =>
+        [ScanRight(*stride as u64)];
+
+    /// Loop moving left until it finds a zero cell
+    scan_left (Safe): [BasicOp(LoopStart), BasicOp(Shift(stride)), BasicOp(LoopEnd)] if *stride < 0 =>
+        [ScanLeft(stride.unsigned_abs())];
+
+    /// Zeroing a cell and then moving right
+    zero_advance (Safe): [Zero, BasicOp(Shift(1))] => [ZeroAdvance(1)];
+
+    /// Zeroing a cell and then moving left
+    zero_retreat (Safe): [Zero, BasicOp(Shift(-1))] => [ZeroRetreat(1)];
+
+    /// Zeroing a cell and then adding a constant to it
+    set (Safe): [Zero, BasicOp(ChangeBy(value))] => [Set(*value)];
+
+    /// u8 equality, leaving 1 in the left cell if the two cells are equal
+    equals (Arithmetic): [
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+    ] => [Equals];
+
+    /// u8 inequality, leaving 1 in the left cell if the two cells differ
+    not_equals (Arithmetic): [
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+    ] => [NotEquals];
+
+    /// u8 shift left logical
+    shift_left_logical (Arithmetic): [
+        ZeroRetreat(1),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+    ] => [ShiftLeftLogical];
+
+    /// u8 shift right logical
+    shift_right_logical (Arithmetic): [
+        ZeroAdvance(3),
+        Zero,
+        BasicOp(Shift(-4)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd2(2, 3),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(-1)),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+    ] => [ShiftRightLogical];
+
+    /// Loop moving a cell onto another by adding to it
+    move_add (Safe): [
+        BasicOp(LoopStart),
+        BasicOp(Shift(toward_amount)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(back_amount)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+    ] if *toward_amount == -*back_amount => [MoveAdd(*toward_amount)], then MOVE_ADD_RULES;
+
+    /// Loop moving a cell onto another by adding to it, decrementing first
+    move_add_reversed (Safe): [
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(toward_amount)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(back_amount)),
+        BasicOp(LoopEnd),
+    ] if *toward_amount == -*back_amount => [MoveAdd(*toward_amount)], then MOVE_ADD_RULES;
+
+    /// Loop moving a cell onto two others by adding to them
+    move_add_2 (Safe): [
+        BasicOp(LoopStart),
+        BasicOp(Shift(toward_amount1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(toward_amount2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(back_amount)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+    ] if *toward_amount1 + *toward_amount2 == -*back_amount =>
+        [MoveAdd2(*toward_amount1, *toward_amount1 + *toward_amount2)];
+
+    /// u8 bitwise negation
+    bit_neg (Unsound): [
+        MoveAdd(1),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+    ] => [BitNeg];
+
+    /// u8 division with remainder
+    // The final shift is relative to where the algorithm normally ends, two cells left of where it started
+    div_mod (Unsound): [
+        ZeroAdvance(3),
+        Zero,
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        ScanRight(3),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_5_plus_extra)),
+    ] => [WellBehavedDivMod(*neg_5_plus_extra + 5 - 2)];
+
+    /// Printing a constant
+    print_static (Safe): [Set(initial_letter), BasicOp(Output(letter_count))] =>
+        [PrintStatic(vec![*initial_letter; *letter_count as usize])];
+
+    /// Changing a printed constant and printing it again
+    print_static_continuation (Safe): after [PrintStatic(string)] [
+        BasicOp(ChangeBy(letter_change_amount)),
+        BasicOp(Output(letter_count)),
+    ] => [PrintStatic(vec![
+        string.last().unwrap().wrapping_add(*letter_change_amount);
+        *letter_count as usize
+    ])];
+
+    /// Moving a cell to a u8 index read from the tape
+    move_cell_dynamic_u8 (Unsound): [
+        ZeroAdvance(2),
+        Zero,
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-3)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(3)),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(neg_offset),
+        BasicOp(Shift(3)),
+        MoveAdd(-2),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-2),
+        BasicOp(Shift(neg_2_plus_extra)),
+    ] if *neg_2_plus_extra <= -2 && *neg_offset < 0 =>
+        [MoveCellDynamicU8(-*neg_offset as u64 - 1), shift(*neg_2_plus_extra + 2)];
+
+    /// Copying the cell at a u8 index read from the tape
+    copy_cell_dynamic_u8 (Unsound): [
+        Dupe(-1),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        Zero,
+        BasicOp(Shift(2)),
+        Zero,
+        BasicOp(Shift(neg_2_plus_neg_offset)),
+        MoveAdd2(move_offset, pos_2_plus_pos_offset),
+        BasicOp(Shift(shift_pos_2_plus_pos_offset)),
+        MoveAdd(move_neg_2_plus_neg_offset),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+    ] if *neg_2_plus_neg_offset == *move_neg_2_plus_neg_offset
+        && *pos_2_plus_pos_offset == *shift_pos_2_plus_pos_offset
+        && -*neg_2_plus_neg_offset == *pos_2_plus_pos_offset
+        && *pos_2_plus_pos_offset - 2 == *move_offset => [CopyCellDynamicU8(*move_offset as u64)];
+
+    /// Moving a cell to a u16 index read from the tape
+    move_cell_dynamic_u16 (Unsound): [
+        ZeroAdvance(six_plus_extra),
+        Zero,
+        BasicOp(Shift(-8)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-6)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-6)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-2)),
+        MoveAdd(2),
+        BasicOp(Shift(8)),
+        MoveAdd(-7),
+        BasicOp(Shift(-1)),
+        MoveAdd(-7),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(254)),
+        ZeroAdvance(4),
+        Zero,
+        BasicOp(Shift(-256)),
+        MoveAdd(256),
+        BasicOp(Shift(-1)),
+        MoveAdd(256),
+        BasicOp(Shift(-1)),
+        MoveAdd(256),
+        BasicOp(Shift(-1)),
+        MoveAdd(256),
+        BasicOp(Shift(-1)),
+        MoveAdd(256),
+        BasicOp(Shift(258)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(neg_offset_and_2),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(1)),
+        MoveAdd(-3),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd(-256),
+        BasicOp(Shift(-256)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_three_plus_extra)),
+    ] if *six_plus_extra >= 6 => [
+        zero_advance(*six_plus_extra - 6),
+        MoveCellDynamicU16((-*neg_offset_and_2 + 1) as u64),
+        shift(*neg_three_plus_extra + 3),
+    ];
+
+    /// Moving a cell to a u32 index read from the tape
+    move_cell_dynamic_u32 (Unsound): [
+        ZeroAdvance(amount_plus_11),
+        Zero,
+        BasicOp(Shift(-16)),
+        MoveSet(5),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(5)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-13)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(5)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-13)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(5)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-13)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(5)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-13)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(13)),
+        MoveAdd(-13),
+        BasicOp(Shift(-1)),
+        MoveAdd(-13),
+        BasicOp(Shift(-1)),
+        MoveAdd(-13),
+        BasicOp(Shift(-1)),
+        MoveAdd(-13),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(16777212)),
+        ZeroAdvance(8),
+        Zero,
+        BasicOp(Shift(-16777224)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(16777202)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(65533)),
+        ZeroAdvance(8),
+        Zero,
+        BasicOp(Shift(-65544)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(65531)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(254)),
+        ZeroAdvance(8),
+        Zero,
+        BasicOp(Shift(-264)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(250)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(8)),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-4)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(negative_offset_plus_sizeof_index_which_is_4),
+        BasicOp(Shift(5)),
+        MoveAdd(-5),
+        BasicOp(Shift(1)),
+        MoveAdd(-5),
+        BasicOp(Shift(1)),
+        MoveAdd(-5),
+        BasicOp(Shift(1)),
+        MoveAdd(-5),
+        BasicOp(Shift(-8)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd(-256),
+        BasicOp(Shift(1)),
+        MoveAdd(-256),
+        BasicOp(Shift(1)),
+        MoveAdd(-256),
+        BasicOp(Shift(-258)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd(-65536),
+        BasicOp(Shift(1)),
+        MoveAdd(-65536),
+        BasicOp(Shift(-65537)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd(-16777216),
+        BasicOp(Shift(-16777216)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(extra_plus_negative_seven)),
+    ] if *amount_plus_11 >= 11 => [
+        zero_advance(*amount_plus_11 - 11),
+        MoveCellDynamicU32((-*negative_offset_plus_sizeof_index_which_is_4 + 1) as u64),
+        shift(*extra_plus_negative_seven + 7),
+    ];
+
+    /// Copying the cell at a u32 index read from the tape
+    copy_cell_dynamic_u32 (Unsound): [
+        ZeroAdvance(seven_plus_extra),
+        Zero,
+        BasicOp(Shift(-11)),
+        MoveAdd2(4, 8),
+        BasicOp(Shift(1)),
+        MoveAdd2(4, 8),
+        BasicOp(Shift(1)),
+        MoveAdd2(4, 8),
+        BasicOp(Shift(1)),
+        MoveAdd2(4, 8),
+        BasicOp(Shift(5)),
+        MoveAdd(-8),
+        BasicOp(Shift(1)),
+        MoveAdd(-8),
+        BasicOp(Shift(1)),
+        MoveAdd(-8),
+        BasicOp(Shift(1)),
+        MoveAdd(-8),
+        BasicOp(Shift(-8)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(16777213)),
+        ZeroAdvance(7),
+        Zero,
+        BasicOp(Shift(-16777223)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(1)),
+        MoveAdd(16777216),
+        BasicOp(Shift(16777212)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(65534)),
+        ZeroAdvance(7),
+        Zero,
+        BasicOp(Shift(-65543)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(1)),
+        MoveAdd(65536),
+        BasicOp(Shift(65531)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(255)),
+        ZeroAdvance(7),
+        Zero,
+        BasicOp(Shift(-263)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(1)),
+        MoveAdd(256),
+        BasicOp(Shift(250)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(8)),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_offset)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(pos_offset)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(neg_offset_and_1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(pos_offset_and_1)),
+        MoveAdd(neg_offset_and_1_second),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(1)),
+        MoveAdd(-3),
+        BasicOp(Shift(1)),
+        MoveAdd(-3),
+        BasicOp(Shift(1)),
+        MoveAdd(-3),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        MoveAdd(-256),
+        BasicOp(Shift(2)),
+        MoveAdd(-256),
+        BasicOp(Shift(1)),
+        MoveAdd(-256),
+        BasicOp(Shift(1)),
+        MoveAdd(-256),
+        BasicOp(Shift(-258)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-3)),
+        MoveAdd(-65536),
+        BasicOp(Shift(3)),
+        MoveAdd(-65536),
+        BasicOp(Shift(1)),
+        MoveAdd(-65536),
+        BasicOp(Shift(-65537)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-4)),
+        MoveAdd(-16777216),
+        BasicOp(Shift(4)),
+        MoveAdd(-16777216),
+        BasicOp(Shift(-16777216)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(negative_3_plus_extra)),
+    ] if *seven_plus_extra >= 7
+        && *pos_offset == -*neg_offset
+        && *neg_offset - 1 == *neg_offset_and_1
+        && *pos_offset + 1 == *pos_offset_and_1
+        && *neg_offset_and_1 == *neg_offset_and_1_second => [
+        zero_advance(*seven_plus_extra - 7),
+        CopyCellDynamicU32(*pos_offset as u64 + 4),
+        shift(*negative_3_plus_extra + 3),
+    ];
+
+    /// Two moves of neighbouring cells by the same offset
+    move_cells_static_reverse (Arithmetic): [
+        MoveSet(offset),
+        BasicOp(Shift(-1)),
+        MoveSet(offset_again),
+        BasicOp(Shift(negative_1_plus_extra)),
+    ] if *offset == *offset_again => [MoveCellsStaticReverse(*offset, 2), shift(*negative_1_plus_extra + 1)];
+
+    /// Another neighbouring cell moved by the same offset as a reverse move
+    move_cells_static_reverse_extend (Arithmetic): [
+        MoveCellsStaticReverse(offset, count),
+        MoveSet(offset_again),
+        BasicOp(Shift(negative_1_plus_extra)),
+    ] if *offset == *offset_again =>
+        [MoveCellsStaticReverse(*offset, *count + 1), shift(*negative_1_plus_extra + 1)],
+        then MOVE_CELLS_STATIC_REVERSE_RULES;
+
+    /// u32 subtraction
+    sub_u32 (Unsound): [
+        BasicOp(Shift(six_plus_extra_shift)),
+        BasicOp(ChangeBy(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-10)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-10)),
+        MoveAdd2(11, 12),
+        BasicOp(Shift(12)),
+        MoveAdd(-12),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-10)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-10)),
+        MoveAdd2(11, 12),
+        BasicOp(Shift(12)),
+        MoveAdd(-12),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-10)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(8)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-9)),
+        MoveAdd(13),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(10)),
+        MoveAdd(-10),
+        BasicOp(Shift(-8)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-6)),
+        Zero,
+        BasicOp(Shift(neg_five_plus_extra_shift)),
+    ] => [
+        shift(*six_plus_extra_shift - 6),
+        SubU32,
+        shift(*neg_five_plus_extra_shift + 5),
+    ];
+
+    /// u32 less than
+    less_than_u32 (Unsound): [
+        BasicOp(Shift(six_plus_extra_shift)),
+        BasicOp(ChangeBy(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-10)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-10)),
+        MoveAdd2(11, 12),
+        BasicOp(Shift(12)),
+        MoveAdd(-12),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-10)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-10)),
+        MoveAdd2(11, 12),
+        BasicOp(Shift(12)),
+        MoveAdd(-12),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-10)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(8)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-9)),
+        MoveAdd(13),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(10)),
+        MoveAdd(-10),
+        BasicOp(Shift(-8)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-14)),
+        ZeroAdvance(3),
+        Zero,
+        BasicOp(Shift(5)),
+        MoveAdd(-8),
+        BasicOp(Shift(neg_five_plus_extra_shift)),
+    ] => [
+        shift(*six_plus_extra_shift - 6),
+        LessThanU32,
+        shift(*neg_five_plus_extra_shift + 5),
+    ];
+
+    /// u32 equality
+    equals_u32 (Unsound): [
+        BasicOp(Shift(neg_four_plus_extra_shift)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-8)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(8)),
+        Zero,
+        BasicOp(Shift(-8)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(7)),
+        Zero,
+        BasicOp(Shift(-7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(6)),
+        Zero,
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(5)),
+        Zero,
+        BasicOp(Shift(-5)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(5)),
+        MoveAdd(-8),
+        BasicOp(Shift(neg_five_plus_extra_shift)),
+    ] => [
+        shift(*neg_four_plus_extra_shift + 4),
+        EqualsU32,
+        shift(*neg_five_plus_extra_shift + 5),
+    ];
+
+    /// u32 bitwise and
+    and_u32 (Unsound): [
+        BasicOp(Shift(ten_plus_extra_shift)),
+        BasicOp(ChangeBy(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-18)),
+        MoveAdd(8),
+        BasicOp(Shift(4)),
+        MoveAdd(5),
+        BasicOp(Shift(-3)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(2)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(8)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(7)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(-7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(6)),
+        MoveAdd(-6),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(5)),
+        MoveAdd(-5),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        MoveAdd(3),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(Shift(3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-6)),
+        MoveAdd2(-7, 7),
+        BasicOp(Shift(7)),
+        MoveAdd(-7),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(7)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(-7)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(7)),
+        MoveAdd(-7),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_fifteen_plus_extra_shift)),
+    ] => [
+        shift(*ten_plus_extra_shift - 10),
+        AndU32,
+        shift(*neg_fifteen_plus_extra_shift + 15),
+    ];
+
+    /// u32 bitwise or
+    or_u32 (Unsound): [
+        BasicOp(Shift(ten_plus_extra_shift)),
+        BasicOp(ChangeBy(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-18)),
+        MoveAdd(8),
+        BasicOp(Shift(4)),
+        MoveAdd(5),
+        BasicOp(Shift(-3)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(2)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(8)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(7)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(-7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(6)),
+        MoveAdd(-6),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(5)),
+        MoveAdd(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-6)),
+        MoveAdd2(-7, 7),
+        BasicOp(Shift(7)),
+        MoveAdd(-7),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(7)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(-7)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(7)),
+        MoveAdd(-7),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_fifteen_plus_extra_shift)),
+    ] => [
+        shift(*ten_plus_extra_shift - 10),
+        OrU32,
+        shift(*neg_fifteen_plus_extra_shift + 15),
+    ];
+
+    /// u32 bitwise exclusive or
+    xor_u32 (Unsound): [
+        BasicOp(Shift(ten_plus_extra_shift)),
+        BasicOp(ChangeBy(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-18)),
+        MoveAdd(8),
+        BasicOp(Shift(4)),
+        MoveAdd(5),
+        BasicOp(Shift(-3)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(2)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(8)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(7)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(-7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(6)),
+        MoveAdd(-6),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(5)),
+        MoveAdd(-5),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-6)),
+        MoveAdd2(-7, 7),
+        BasicOp(Shift(7)),
+        MoveAdd(-7),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(7)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(-7)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(7)),
+        MoveAdd(-7),
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(7)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_fifteen_plus_extra_shift)),
+    ] => [
+        shift(*ten_plus_extra_shift - 10),
+        XorU32,
+        shift(*neg_fifteen_plus_extra_shift + 15),
+    ];
+
+    /// u32 shift left logical
+    shift_left_u32 (Unsound): [
+        BasicOp(Shift(neg_three_plus_extra_shift)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(3)),
+        Set(1),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(2)),
+        Set(1),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(32)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-9)),
+        MoveAdd2(8, 10),
+        BasicOp(Shift(10)),
+        MoveAdd(-10),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-9)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        Set(1),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-6),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-5)),
+        Set(1),
+        BasicOp(Shift(5)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-8)),
+        ZeroAdvance(4),
+        Zero,
+        BasicOp(Shift(4)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-8)),
+        MoveAdd(9),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(6)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(9)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-9)),
+        MoveAdd2(10, 11),
+        BasicOp(Shift(11)),
+        MoveAdd(-11),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-9)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(9)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-9)),
+        MoveAdd2(10, 11),
+        BasicOp(Shift(11)),
+        MoveAdd(-11),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-7),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        Zero,
+        BasicOp(Shift(-6)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_one_plus_extra_shift)),
+    ] => [
+        shift(*neg_three_plus_extra_shift + 3),
+        ShiftLeftU32,
+        shift(*neg_one_plus_extra_shift + 1),
+    ];
+
+    /// u32 shift right logical
+    shift_right_u32 (Unsound): [
+        BasicOp(Shift(neg_three_plus_extra_shift)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(3)),
+        Set(1),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(2)),
+        Set(1),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(32)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-9)),
+        MoveAdd2(8, 10),
+        BasicOp(Shift(10)),
+        MoveAdd(-10),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-9)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(6)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        Set(1),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-6),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        Zero,
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-5)),
+        Set(1),
+        BasicOp(Shift(5)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-8)),
+        ZeroAdvance(4),
+        Zero,
+        BasicOp(Shift(4)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(4)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-5)),
+        MoveAdd(6),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(9)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveAdd(-9),
+        BasicOp(Shift(3)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-12)),
+        BasicOp(ChangeBy(128)),
+        BasicOp(Shift(12)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(4)),
+        Zero,
+        BasicOp(Shift(-8)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(neg_one_plus_extra_shift)),
+    ] => [
+        shift(*neg_three_plus_extra_shift + 3),
+        ShiftRightU32,
+        shift(*neg_one_plus_extra_shift + 1),
+    ];
+
+    /// u32 division with remainder
+    div_mod_u32 (Unsound): [
+        BasicOp(Shift(twenty_three_plus_extra_shift)),
+        BasicOp(ChangeBy(32)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-28)),
+        MoveAdd2(29, 30),
+        BasicOp(Shift(30)),
+        MoveAdd(-30),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-28)),
+        MoveAdd2(28, 29),
+        BasicOp(Shift(29)),
+        MoveAdd(-29),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-27)),
+        MoveAdd2(27, 28),
+        BasicOp(Shift(28)),
+        MoveAdd(-28),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-26)),
+        MoveAdd2(26, 27),
+        BasicOp(Shift(27)),
+        MoveAdd(-27),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-32)),
+        ZeroAdvance(3),
+        Zero,
+        BasicOp(Shift(28)),
+        ZeroAdvance(1),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(8)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-35)),
+        MoveAdd(36),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(5)),
+        MoveAdd(-5),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(25)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-25)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(28)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-28)),
+        MoveAdd2(29, 30),
+        BasicOp(Shift(30)),
+        MoveAdd(-30),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-28)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(28)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-28)),
+        MoveAdd2(29, 30),
+        BasicOp(Shift(30)),
+        MoveAdd(-30),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-26),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        Zero,
+        BasicOp(Shift(-29)),
+        MoveAdd2(8, 26),
+        BasicOp(Shift(26)),
+        MoveAdd(-26),
+        BasicOp(Shift(-25)),
+        MoveAdd2(8, 25),
+        BasicOp(Shift(25)),
+        MoveAdd(-25),
+        BasicOp(Shift(-24)),
+        MoveAdd2(8, 24),
+        BasicOp(Shift(24)),
+        MoveAdd(-24),
+        BasicOp(Shift(-23)),
+        MoveAdd2(8, 23),
+        BasicOp(Shift(23)),
+        MoveAdd(-23),
+        BasicOp(Shift(-30)),
+        MoveAdd2(16, 30),
+        BasicOp(Shift(30)),
+        MoveAdd(-30),
+        BasicOp(Shift(-29)),
+        MoveAdd2(16, 29),
+        BasicOp(Shift(29)),
+        MoveAdd(-29),
+        BasicOp(Shift(-28)),
+        MoveAdd2(16, 28),
+        BasicOp(Shift(28)),
+        MoveAdd(-28),
+        BasicOp(Shift(-27)),
+        MoveAdd2(16, 27),
+        BasicOp(Shift(27)),
+        MoveAdd(-27),
+        BasicOp(Shift(-10)),
+        LessThanU32,
+        BasicOp(Shift(13)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-16)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(16)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-16)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(16)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-28)),
+        MoveAdd2(8, 30),
+        BasicOp(Shift(30)),
+        MoveAdd(-30),
+        BasicOp(Shift(-29)),
+        MoveAdd2(8, 29),
+        BasicOp(Shift(29)),
+        MoveAdd(-29),
+        BasicOp(Shift(-28)),
+        MoveAdd2(8, 28),
+        BasicOp(Shift(28)),
+        MoveAdd(-28),
+        BasicOp(Shift(-27)),
+        MoveAdd2(8, 27),
+        BasicOp(Shift(27)),
+        MoveAdd(-27),
+        BasicOp(Shift(-18)),
+        SubU32,
+        BasicOp(Shift(-11)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(32)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-23)),
+        MoveSet(-4),
+        BasicOp(Shift(-3)),
+        Zero,
+        BasicOp(Shift(4)),
+        MoveAdd(-4),
+        BasicOp(Shift(-3)),
+        Zero,
+        BasicOp(Shift(4)),
+        MoveAdd(-4),
+        BasicOp(Shift(-3)),
+        Zero,
+        BasicOp(Shift(4)),
+        MoveAdd(-4),
+        BasicOp(Shift(neg_eight_plus_extra_shift)),
+    ] => [
+        shift(*twenty_three_plus_extra_shift - 23),
+        DivModU32,
+        shift(*neg_eight_plus_extra_shift + 8),
+    ];
+};
+
+/// Rules for algorithms that end in a move add, tried after one is recognised
+const MOVE_ADD_RULES: &[Rule] = rules! {
+    /// Zeroing a cell and then moving another cell onto it
+    move_set (Safe): [
+        BasicOp(Shift(toward_amount_plus_extra)),
+        CompoundOp::Zero,
+        BasicOp(Shift(back_amount)),
+        CompoundOp::MoveAdd(move_add_toward_amount),
+    ] if toward_amount_plus_extra.abs() >= back_amount.abs()
+        && -*back_amount == *move_add_toward_amount =>
+        [shift(toward_amount_plus_extra + back_amount), MoveSet(*move_add_toward_amount)];
+
+    /// Moving a cell onto two zeroed cells and one of them back
+    dupe (Safe): [
+        ZeroAdvance(advance_amount),
+        Zero,
+        BasicOp(Shift(toward_shift)),
+        MoveAdd2(offset, offset_plus_1),
+        BasicOp(Shift(back_shift)),
+        MoveAdd(return_shift),
+    ] if -*toward_shift == *offset_plus_1
+        && *offset + 1 == *offset_plus_1
+        && -*toward_shift == *back_shift
+        && *toward_shift == *return_shift
+        && *advance_amount > 0 => [zero_advance(*advance_amount - 1), Dupe(-*offset)];
+
+    /// u8 less than
+    less_than (Arithmetic): [
+        ZeroAdvance(zero_advance_amount),
+        Zero,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd2(2, 3),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        Zero,
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+    ] if *zero_advance_amount > 0 => [zero_advance(*zero_advance_amount - 1), LessThan];
+
+    /// u8 greater than
+    greater_than (Arithmetic): [
+        ZeroAdvance(zero_advance_amount),
+        Zero,
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(2)),
+        ZeroRetreat(1),
+        MoveAdd2(1, 2),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-3)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+    ] if *zero_advance_amount > 0 => [zero_advance(*zero_advance_amount - 1), GreaterThan];
+
+    /// u8 less than or equal
+    less_than_equal (Unsound): [
+        Set(1),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(Shift(-3)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(2)),
+        ZeroRetreat(1),
+        MoveAdd2(1, 2),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-3)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+    ] => [LessThanEqual];
+
+    /// u8 greater than or equal
+    greater_than_equal (Arithmetic): [
+        Set(1),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd2(2, 3),
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        ZeroRetreat(1),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(3)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        Zero,
+        BasicOp(Shift(2)),
+        MoveAdd(-2),
+    ] => [GreaterThanEqual];
+
+    /// u8 bitwise and
+    bit_and (Unsound): [
+        Zero,
+        BasicOp(Shift(2)),
+        ZeroRetreat(1),
+        Set(248),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(8)),
+        BasicOp(Shift(-2)),
+        ZeroRetreat(4),
+        Set(2),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd2(1, 3),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(4)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(5)),
+        BasicOp(ChangeBy(254)),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(4)),
+        MoveAdd(-4),
+        BasicOp(Shift(-2)),
+        Set(2),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        MoveAdd2(1, 3),
+        BasicOp(Shift(1)),
+        MoveAdd(-1),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(254)),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-4)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(3)),
+        MoveAdd(-3),
+        BasicOp(Shift(2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        MoveAdd(-2),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        ZeroAdvance(1),
+        MoveAdd2(-1, -2),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(249)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        MoveAdd(-9),
+    ] => [BitAnd];
+};
+
+/// Rules for algorithms that end in a reverse move of several cells, tried after one is extended
+const MOVE_CELLS_STATIC_REVERSE_RULES: &[Rule] = rules! {
+    /// u32 addition
+    add_u32 (Unsound): [
+        ZeroAdvance(three_plus_extra_advance),
+        Zero,
+        BasicOp(Shift(-4)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(12)),
+        ZeroAdvance(8),
+        Dupe(-16),
+        Dupe(-16),
+        Dupe(-14),
+        Dupe(-14),
+        ZeroAdvance(2),
+        Dupe(-6),
+        Dupe(-6),
+        Dupe(-6),
+        Dupe(-6),
+        ZeroAdvance(1),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(6)),
+        ZeroAdvance(4),
+        Dupe(-8),
+        Dupe(-7),
+        ZeroAdvance(3),
+        Dupe(-5),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-3),
+        Dupe(-8),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-2),
+        Dupe(-6),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-3),
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-7, 2),
+        BasicOp(Shift(-5)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-1),
+        Dupe(-6),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-2),
+        Dupe(-2),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-8, 2),
+        BasicOp(Shift(-6)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        ZeroAdvance(4),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-8),
+        Dupe(-8),
+        Dupe(-14),
+        Dupe(-14),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(5)),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-5),
+        Dupe(-4),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Dupe(-5),
+        Dupe(-4),
+        BasicOp(Shift(-2)),
+        Equals,
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-6),
+        Dupe(-5),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-5),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-7),
+        Dupe(-7),
+        Dupe(-11),
+        Dupe(-11),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(5)),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-5),
+        Dupe(-4),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Dupe(-5),
+        Dupe(-4),
+        BasicOp(Shift(-2)),
+        Equals,
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-6),
+        Dupe(-5),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-5),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        Dupe(-6),
+        BasicOp(Shift(-1)),
+        MoveSet(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-14, 4),
+        BasicOp(Shift(-10)),
+        MoveCellsStaticReverse(-4, 3),
+        MoveSet(-4),
+        Dupe(-2),
+        Dupe(-2),
+        Dupe(-12),
+        Dupe(-12),
+        Dupe(-10),
+        Dupe(-10),
+        ZeroAdvance(1),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(6)),
+        ZeroAdvance(4),
+        Dupe(-8),
+        Dupe(-7),
+        ZeroAdvance(3),
+        Dupe(-5),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-3),
+        Dupe(-8),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-2),
+        Dupe(-6),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-3),
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-7, 2),
+        BasicOp(Shift(-5)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-1),
+        Dupe(-6),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-2),
+        Dupe(-2),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-8, 2),
+        BasicOp(Shift(-5)),
+        ZeroAdvance(1),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(6)),
+        ZeroAdvance(4),
+        Dupe(-8),
+        Dupe(-7),
+        ZeroAdvance(3),
+        Dupe(-5),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-3),
+        Dupe(-8),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-2),
+        Dupe(-6),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-3),
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-7, 2),
+        BasicOp(Shift(-5)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-1),
+        Dupe(-6),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-2),
+        Dupe(-2),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-8, 2),
+        BasicOp(Shift(-6)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-16, 4),
+        BasicOp(Shift(neg_twelve_plus_extra_shift)),
+    ] if *three_plus_extra_advance >= 3 => [
+        zero_advance(*three_plus_extra_advance - 3),
+        AddU32,
+        shift(*neg_twelve_plus_extra_shift + 12),
+    ];
+
+    /// u32 multiplication
+    mul_u32 (Unsound): [
+        ZeroAdvance(three_plus_extra_advance),
+        Zero,
+        BasicOp(Shift(-4)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(-1)),
+        MoveAdd(4),
+        BasicOp(Shift(12)),
+        ZeroAdvance(33),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        Dupe(-38),
+        Dupe(-2),
+        ShiftRightLogical,
+        Set(1),
+        BasicOp(Shift(6)),
+        BitAnd,
+        BasicOp(Shift(-8)),
+        ZeroRetreat(1),
+        NotEquals,
+        Set(32),
+        BasicOp(Shift(1)),
+        Set(24),
+        BasicOp(Shift(1)),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        Set(1),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        MoveCellDynamicU8(32),
+        Dupe(-1),
+        Set(1),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        ZeroAdvance(1),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        Dupe(-39),
+        Dupe(-2),
+        ShiftRightLogical,
+        Set(1),
+        BasicOp(Shift(6)),
+        BitAnd,
+        BasicOp(Shift(-8)),
+        ZeroRetreat(1),
+        NotEquals,
+        Set(32),
+        BasicOp(Shift(1)),
+        Set(16),
+        BasicOp(Shift(1)),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        Set(1),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        MoveCellDynamicU8(32),
+        Dupe(-1),
+        Set(1),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        ZeroAdvance(1),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        Dupe(-40),
+        Dupe(-2),
+        ShiftRightLogical,
+        Set(1),
+        BasicOp(Shift(6)),
+        BitAnd,
+        BasicOp(Shift(-8)),
+        ZeroRetreat(1),
+        NotEquals,
+        Set(32),
+        BasicOp(Shift(1)),
+        Set(8),
+        BasicOp(Shift(1)),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        Set(1),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        MoveCellDynamicU8(32),
+        Dupe(-1),
+        Set(1),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        ZeroAdvance(1),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        Dupe(-41),
+        Dupe(-2),
+        ShiftRightLogical,
+        Set(1),
+        BasicOp(Shift(6)),
+        BitAnd,
+        BasicOp(Shift(-8)),
+        ZeroRetreat(1),
+        NotEquals,
+        Set(32),
+        BasicOp(Shift(1)),
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        Set(1),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        MoveCellDynamicU8(32),
+        Dupe(-1),
+        Set(1),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        ZeroAdvance(7),
+        Zero,
+        MoveCellsStaticReverse(-4, 3),
+        MoveSet(-4),
+        ZeroAdvance(1),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(32),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        Dupe(-1),
+        CopyCellDynamicU8(37),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(4),
+        Dupe(-9),
+        Dupe(-9),
+        Dupe(-9),
+        Dupe(-9),
+        ZeroAdvance(4),
+        Dupe(-53),
+        Dupe(-53),
+        Dupe(-53),
+        Dupe(-53),
+        Set(31),
+        BasicOp(Shift(1)),
+        Dupe(-18),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ZeroAdvance(4),
+        Dupe(-5),
+        Set(8),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-1)),
+        Set(1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Dupe(-10),
+        Set(8),
+        BasicOp(Shift(1)),
+        Dupe(-8),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftRightLogical,
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        ZeroAdvance(1),
+        Dupe(-10),
+        Set(8),
+        BasicOp(Shift(1)),
+        Dupe(-9),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftRightLogical,
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        ZeroAdvance(1),
+        Dupe(-10),
+        Set(8),
+        BasicOp(Shift(1)),
+        Dupe(-10),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftRightLogical,
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-9),
+        Dupe(-9),
+        ShiftLeftLogical,
+        Dupe(-2),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-4),
+        Dupe(-10),
+        Dupe(-9),
+        ShiftLeftLogical,
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-5),
+        Dupe(-11),
+        Dupe(-9),
+        ShiftLeftLogical,
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-6),
+        Dupe(-12),
+        Dupe(-9),
+        ShiftLeftLogical,
+        BasicOp(Shift(-1)),
+        MoveSet(-7),
+        BasicOp(Shift(-2)),
+        ZeroRetreat(1),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Dupe(-6),
+        Set(16),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-1)),
+        Set(1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Dupe(-11),
+        Set(16),
+        BasicOp(Shift(1)),
+        Dupe(-9),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftRightLogical,
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        ZeroAdvance(1),
+        Dupe(-11),
+        Set(16),
+        BasicOp(Shift(1)),
+        Dupe(-10),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftRightLogical,
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-10),
+        Dupe(-9),
+        Set(8),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftLeftLogical,
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-4),
+        Dupe(-11),
+        Dupe(-9),
+        Set(8),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftLeftLogical,
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-5),
+        Dupe(-12),
+        Dupe(-9),
+        Set(8),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftLeftLogical,
+        BasicOp(Shift(-1)),
+        MoveSet(-6),
+        Zero,
+        MoveSet(-7),
+        BasicOp(Shift(-1)),
+        ZeroRetreat(1),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Dupe(-7),
+        Set(24),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-1)),
+        Set(1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Dupe(-12),
+        Set(24),
+        BasicOp(Shift(1)),
+        Dupe(-10),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftRightLogical,
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        Dupe(-11),
+        Dupe(-9),
+        Set(16),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftLeftLogical,
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-4),
+        Dupe(-12),
+        Dupe(-9),
+        Set(16),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftLeftLogical,
+        BasicOp(Shift(-1)),
+        MoveSet(-5),
+        Zero,
+        MoveSet(-6),
+        Zero,
+        MoveSet(-7),
+        ZeroRetreat(1),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        Dupe(-8),
+        Set(32),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-1)),
+        Set(1),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-12),
+        Dupe(-9),
+        Set(24),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(LoopEnd),
+        ShiftLeftLogical,
+        BasicOp(Shift(-1)),
+        MoveSet(-4),
+        Zero,
+        MoveSet(-5),
+        Zero,
+        MoveSet(-6),
+        Zero,
+        MoveSet(-7),
+        BasicOp(Shift(1)),
+        ZeroRetreat(1),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(3),
+        Zero,
+        MoveCellsStaticReverse(-8, 3),
+        MoveSet(-8),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-13, 4),
+        BasicOp(Shift(-8)),
+        ZeroAdvance(8),
+        Dupe(-16),
+        Dupe(-16),
+        Dupe(-14),
+        Dupe(-14),
+        ZeroAdvance(2),
+        Dupe(-6),
+        Dupe(-6),
+        Dupe(-6),
+        Dupe(-6),
+        ZeroAdvance(1),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(6)),
+        ZeroAdvance(4),
+        Dupe(-8),
+        Dupe(-7),
+        ZeroAdvance(3),
+        Dupe(-5),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-3),
+        Dupe(-8),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-2),
+        Dupe(-6),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-3),
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-7, 2),
+        BasicOp(Shift(-5)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-1),
+        Dupe(-6),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-2),
+        Dupe(-2),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-8, 2),
+        BasicOp(Shift(-6)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        ZeroAdvance(4),
+        ZeroRetreat(1),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-8),
+        Dupe(-8),
+        Dupe(-14),
+        Dupe(-14),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(5)),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-5),
+        Dupe(-4),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Dupe(-5),
+        Dupe(-4),
+        BasicOp(Shift(-2)),
+        Equals,
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-6),
+        Dupe(-5),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-5),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-7),
+        Dupe(-7),
+        Dupe(-11),
+        Dupe(-11),
+        ZeroRetreat(1),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(-1)),
+        MoveAdd(1),
+        BasicOp(Shift(5)),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-5),
+        Dupe(-4),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        ZeroAdvance(1),
+        Dupe(-5),
+        Dupe(-4),
+        BasicOp(Shift(-2)),
+        Equals,
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-6),
+        Dupe(-5),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-5),
+        BasicOp(Shift(-5)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        Dupe(-6),
+        BasicOp(Shift(-1)),
+        MoveSet(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-14, 4),
+        BasicOp(Shift(-10)),
+        MoveCellsStaticReverse(-4, 3),
+        MoveSet(-4),
+        Dupe(-2),
+        Dupe(-2),
+        Dupe(-12),
+        Dupe(-12),
+        Dupe(-10),
+        Dupe(-10),
+        ZeroAdvance(1),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(6)),
+        ZeroAdvance(4),
+        Dupe(-8),
+        Dupe(-7),
+        ZeroAdvance(3),
+        Dupe(-5),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-3),
+        Dupe(-8),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-2),
+        Dupe(-6),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-3),
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-7, 2),
+        BasicOp(Shift(-5)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-1),
+        Dupe(-6),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-2),
+        Dupe(-2),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-8, 2),
+        BasicOp(Shift(-5)),
+        ZeroAdvance(1),
+        Zero,
+        BasicOp(Shift(-2)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(-1)),
+        MoveAdd(2),
+        BasicOp(Shift(6)),
+        ZeroAdvance(4),
+        Dupe(-8),
+        Dupe(-7),
+        ZeroAdvance(3),
+        Dupe(-5),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        BasicOp(Shift(-1)),
+        MoveSet(-3),
+        ZeroAdvance(1),
+        Set(1),
+        BasicOp(Shift(1)),
+        Dupe(-3),
+        Dupe(-8),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(255)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        Dupe(-2),
+        Dupe(-6),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-3),
+        Dupe(-3),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-7, 2),
+        BasicOp(Shift(-5)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-1),
+        Dupe(-6),
+        Dupe(-5),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-1)),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-2),
+        Dupe(-2),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-8, 2),
+        BasicOp(Shift(-6)),
+        MoveSet(-2),
+        BasicOp(Shift(-1)),
+        MoveSet(-2),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-16, 4),
+        BasicOp(Shift(-12)),
+        MoveCellsStaticReverse(-5, 3),
+        MoveSet(-5),
+        Zero,
+        BasicOp(LoopEnd),
+        Dupe(-1),
+        Set(1),
+        MoveAdd(-1),
+        BasicOp(Shift(-2)),
+        ZeroAdvance(1),
+        MoveAdd(-1),
+        Dupe(-1),
+        Set(32),
+        BasicOp(Shift(1)),
+        LessThan,
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        Dupe(-4),
+        BasicOp(Shift(-1)),
+        MoveCellsStaticReverse(-48, 4),
+        BasicOp(Shift(neg_44_plus_extra_shift)),
+    ] if *three_plus_extra_advance >= 3 => [
+        zero_advance(*three_plus_extra_advance - 3),
+        MulU32,
+        shift(*neg_44_plus_extra_shift + 44),
+    ];
+};
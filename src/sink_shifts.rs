@@ -0,0 +1,73 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+};
+use anyhow::Result;
+
+/// Moves the pointer updates of each basic block to its end, leaving one `Shift` per block
+///
+/// Ops between the shifts are rewritten into their forms that act on a cell at an offset from the pointer.
+/// Ops without such a form need the pointer to be where the program expects, so the shifts before them are
+/// applied first.
+pub fn sink_shifts(ops: &[InterpreterOp]) -> Result<Vec<InterpreterOp>> {
+    let mut sunk = InterpreterOpAcc::new();
+    let mut pending = 0;
+
+    for op in ops {
+        match op {
+            InterpreterOp::LoopStart(_) | InterpreterOp::IfStart(_) => {
+                flush(&mut sunk, &mut pending)?;
+                sunk.feed(CompoundOp::BasicOp(BasicOp::LoopStart))?;
+            }
+            InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => {
+                flush(&mut sunk, &mut pending)?;
+                sunk.feed(CompoundOp::BasicOp(BasicOp::LoopEnd))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Shift(amount))) => pending += amount,
+            InterpreterOp::CompoundOp(op) if pending == 0 => sunk.feed(op.clone())?,
+            InterpreterOp::CompoundOp(op) => match at(op, pending) {
+                Some(op) => sunk.feed(op)?,
+                None => {
+                    flush(&mut sunk, &mut pending)?;
+                    sunk.feed(op.clone())?;
+                }
+            },
+        }
+    }
+
+    flush(&mut sunk, &mut pending)?;
+    Ok(sunk.view()?.clone())
+}
+
+/// Applies the pointer movement left over so far
+fn flush(sunk: &mut InterpreterOpAcc, pending: &mut i64) -> Result<()> {
+    if *pending != 0 {
+        sunk.feed(CompoundOp::BasicOp(BasicOp::Shift(*pending)))?;
+        *pending = 0;
+    }
+
+    Ok(())
+}
+
+/// Form of the op that acts on the cell at `offset` instead of the current cell, if it has one
+fn at(op: &CompoundOp, offset: i64) -> Option<CompoundOp> {
+    let op = match op {
+        CompoundOp::BasicOp(BasicOp::ChangeBy(amount)) => CompoundOp::ChangeByAt(offset, *amount),
+        CompoundOp::BasicOp(BasicOp::Input(count)) => CompoundOp::InputAt(offset, *count),
+        CompoundOp::BasicOp(BasicOp::Output(count)) => CompoundOp::OutputAt(offset, *count),
+        CompoundOp::Zero => CompoundOp::SetAt(offset, 0),
+        CompoundOp::Set(value) => CompoundOp::SetAt(offset, *value),
+        CompoundOp::MoveAdd(to) => CompoundOp::MoveAddAt(offset, offset + to),
+        CompoundOp::MoveAdd2(to1, to2) => CompoundOp::MoveAdd2At(offset, offset + to1, offset + to2),
+        CompoundOp::MoveSet(to) => CompoundOp::MoveSetAt(offset, offset + to),
+        CompoundOp::MulAddMany(targets) => CompoundOp::MulAddManyAt(
+            offset,
+            targets.iter().map(|(to, factor)| (offset + to, *factor)).collect(),
+        ),
+        CompoundOp::SetCells(from, values) => CompoundOp::SetCells(offset + from, values.clone()),
+        _ => return None,
+    };
+
+    Some(op)
+}
@@ -0,0 +1,47 @@
+use crate::{
+    interpreter::{EofBehavior, CELL_COUNT},
+    interpreter_op::InterpreterOp,
+    x86_64::Assembler,
+    x86_64_codegen::{self, CodegenOptions, TAPE},
+};
+use anyhow::Result;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Emits GNU assembler source for a static x86-64 Linux executable without libc
+///
+/// Assemble and link with `as program.s -o program.o && ld program.o -o program`.
+pub fn transpile_asm(
+    ops: &[InterpreterOp],
+    output_filename: &str,
+    bounds_checks: bool,
+    eof_behavior: EofBehavior,
+) -> Result<()> {
+    let mut asm = Assembler::new();
+    let options = CodegenOptions {
+        bounds_checks,
+        eof_behavior,
+    };
+    x86_64_codegen::lower_executable(&mut asm, ops, options)?;
+
+    let file = File::create(output_filename)?;
+    let mut f = BufWriter::new(file);
+
+    writeln!(f, "    .intel_syntax noprefix")?;
+    writeln!(f, "    .section .bss")?;
+    writeln!(f, "tape:")?;
+    writeln!(f, "    .skip {}", CELL_COUNT)?;
+    writeln!(f)?;
+    writeln!(f, "    .text")?;
+    writeln!(f, "    .globl _start")?;
+    writeln!(f, "_start:")?;
+    writeln!(f, "    lea {}, [rip + tape]", TAPE)?;
+
+    for inst in asm.insts() {
+        writeln!(f, "{}", inst)?;
+    }
+
+    Ok(())
+}
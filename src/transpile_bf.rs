@@ -0,0 +1,129 @@
+use crate::{basic_op::BasicOp, compound_op::CompoundOp, expand::expand, interpreter_op::InterpreterOp};
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use BasicOp::*;
+use CompoundOp::*;
+
+const LINE_WIDTH: usize = 80;
+
+/// Emits plain Brainfuck without the digit-prefix run-length syntax
+///
+/// Every compound op recognised by a rule is expanded into that rule's patterns by [`expand`], so feeding the
+/// output back through the parser reproduces the same op stream.
+pub fn transpile_bf<'a>(ops: impl Iterator<Item = &'a InterpreterOp>, output_filename: &str) -> Result<()> {
+    let file = File::create(output_filename)?;
+    let mut bf = Brainfuck {
+        f: BufWriter::new(file),
+        column: 0,
+    };
+
+    for op in ops {
+        match op {
+            InterpreterOp::LoopStart(_) | InterpreterOp::IfStart(_) => bf.emit('[', 1)?,
+            InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => bf.emit(']', 1)?,
+            InterpreterOp::CompoundOp(op) => bf.compound_op(op)?,
+        }
+    }
+
+    if bf.column != 0 {
+        writeln!(bf.f)?;
+    }
+
+    Ok(())
+}
+
+struct Brainfuck {
+    f: BufWriter<File>,
+    column: usize,
+}
+
+impl Brainfuck {
+    fn emit(&mut self, c: char, count: u64) -> Result<()> {
+        for _ in 0..count {
+            write!(self.f, "{}", c)?;
+            self.column += 1;
+
+            if self.column == LINE_WIDTH {
+                writeln!(self.f)?;
+                self.column = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn basic_op(&mut self, op: &BasicOp) -> Result<()> {
+        match op {
+            ChangeBy(amount) if *amount <= 128 => self.emit('+', *amount as u64),
+            ChangeBy(amount) => self.emit('-', 256 - *amount as u64),
+            Shift(amount) if *amount >= 0 => self.emit('>', *amount as u64),
+            Shift(amount) => self.emit('<', amount.unsigned_abs()),
+            Input(count) => self.emit(',', *count),
+            Output(count) => self.emit('.', *count),
+            LoopStart => self.emit('[', 1),
+            LoopEnd => self.emit(']', 1),
+        }
+    }
+
+    fn compound_op(&mut self, op: &CompoundOp) -> Result<()> {
+        match op {
+            BasicOp(op) => self.basic_op(op),
+            MulAddMany(targets) => {
+                let mut ops = vec![BasicOp(LoopStart)];
+                let mut offset = 0;
+
+                for (target, factor) in targets {
+                    ops.push(BasicOp(Shift(*target - offset)));
+                    ops.push(BasicOp(ChangeBy(*factor)));
+                    offset = *target;
+                }
+
+                ops.extend([BasicOp(Shift(-offset)), BasicOp(ChangeBy(u8::MAX)), BasicOp(LoopEnd)]);
+                self.ops(&ops)
+            }
+            // Offset forms move to their cell and back, the parser merges the moves between them again
+            ChangeByAt(offset, amount) => self.at(*offset, BasicOp(ChangeBy(*amount))),
+            SetAt(offset, value) => self.at(*offset, Set(*value)),
+            InputAt(offset, count) => self.at(*offset, BasicOp(Input(*count))),
+            OutputAt(offset, count) => self.at(*offset, BasicOp(Output(*count))),
+            MoveAddAt(from, to) => self.at(*from, MoveAdd(*to - *from)),
+            MoveAdd2At(from, to1, to2) => self.at(*from, MoveAdd2(*to1 - *from, *to2 - *from)),
+            MoveSetAt(from, to) => self.at(*from, MoveSet(*to - *from)),
+            MulAddManyAt(from, targets) => self.at(
+                *from,
+                MulAddMany(targets.iter().map(|(to, factor)| (*to - *from, *factor)).collect()),
+            ),
+            SetCells(offset, values) => {
+                for (cell, value) in (*offset..).zip(values) {
+                    self.at(cell, Set(*value))?;
+                }
+
+                Ok(())
+            }
+            // Updating several cells at once needs scratch cells, and which ones are free isn't known here
+            Affine(_) => Err(anyhow!(
+                "[error] Affine ops from --patterns cannot be written back as Brainfuck"
+            )),
+            op => match expand(op) {
+                Some(ops) => self.ops(&ops),
+                None => Err(anyhow!("[error] {:?} cannot be written back as Brainfuck", op)),
+            },
+        }
+    }
+
+    fn at(&mut self, offset: i64, op: CompoundOp) -> Result<()> {
+        self.ops(&[BasicOp(Shift(offset)), op, BasicOp(Shift(-offset))])
+    }
+
+    fn ops(&mut self, ops: &[CompoundOp]) -> Result<()> {
+        for op in ops {
+            self.compound_op(op)?;
+        }
+
+        Ok(())
+    }
+}
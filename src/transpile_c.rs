@@ -1,81 +1,158 @@
-use crate::{basic_op::BasicOp, compound_op::CompoundOp, interpreter::CELL_COUNT, interpreter_op::InterpreterOp};
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{EofBehavior, RuntimeOptions},
+    interpreter_op::InterpreterOp,
+};
 use anyhow::{anyhow, Result};
 use std::{
     fs::File,
     io::{BufWriter, Write},
 };
 
-pub fn transpile_c<'a>(ops: impl Iterator<Item = &'a InterpreterOp>, output_filename: &str) -> Result<()> {
+/// Emits a C program that behaves like the interpreter configured with `options`
+///
+/// Every tape access goes through `at()`/`span()`, which only check bounds when requested.
+pub fn transpile_c<'a>(
+    ops: impl Iterator<Item = &'a InterpreterOp>,
+    output_filename: &str,
+    options: RuntimeOptions,
+) -> Result<()> {
     let file = File::create(output_filename)?;
-    let mut f = BufWriter::new(file);
-
-    f.write(b"#include <stdio.h>\n")?;
-    f.write(b"#include <stdlib.h>\n")?;
-    f.write(b"#include <string.h>\n")?;
-    f.write(b"#include <stdint.h>\n")?;
-    f.write(b"static inline void put(unsigned char c){ putchar((char) c); }\n")?;
-    f.write(
-        b"static inline unsigned char get(void){ char c = getc(stdin); return c != EOF ? (unsigned char) c : 0; }\n",
+    write_c(ops, BufWriter::new(file), options)?;
+    Ok(())
+}
+
+/// Writes the C program to `writer` and returns the first line (starting at 1) generated for each op
+pub fn write_c<'a>(
+    ops: impl Iterator<Item = &'a InterpreterOp>,
+    writer: impl Write,
+    options: RuntimeOptions,
+) -> Result<Vec<usize>> {
+    let mut f = LineCounter { inner: writer, line: 1 };
+    let mut op_lines = Vec::new();
+
+    // For memrchr
+    f.write_all(b"#define _GNU_SOURCE\n")?;
+    f.write_all(b"#include <stdio.h>\n")?;
+    f.write_all(b"#include <stdlib.h>\n")?;
+    f.write_all(b"#include <string.h>\n")?;
+    f.write_all(b"#include <stdint.h>\n")?;
+    f.write_all(format!("#define TAPE_SIZE ((size_t) {})\n", options.tape_size).as_bytes())?;
+    f.write_all(b"static unsigned char *m;\n")?;
+
+    if options.bounds_checks {
+        f.write_all(b"static void out_of_bounds(void){ fflush(stdout); fputs(\"[error] Out of bounds memory access\\n\", stderr); exit(1); }\n")?;
+        f.write_all(b"static inline size_t at(size_t x){ if (x >= TAPE_SIZE) out_of_bounds(); return x; }\n")?;
+        f.write_all(
+            b"static inline size_t span(size_t x, size_t n){ if (x > TAPE_SIZE || n > TAPE_SIZE - x) out_of_bounds(); return x; }\n",
+        )?;
+    } else {
+        f.write_all(b"static inline size_t at(size_t x){ return x; }\n")?;
+        f.write_all(b"static inline size_t span(size_t x, size_t n){ (void) n; return x; }\n")?;
+    }
+
+    f.write_all(b"static inline void put(unsigned char c){ putchar(c); }\n")?;
+    // A scan that finds no zero cell runs off the tape, which at() reports when bounds are checked
+    f.write_all(
+        b"static inline size_t scan_right(size_t x){ unsigned char *p = memchr(m + at(x), 0, TAPE_SIZE - x); return p ? (size_t) (p - m) : at(TAPE_SIZE); }\n",
+    )?;
+    f.write_all(
+        b"static inline size_t scan_left(size_t x){ unsigned char *p = memrchr(m, 0, at(x) + 1); return p ? (size_t) (p - m) : at((size_t) -1); }\n",
+    )?;
+
+    let eof_value = match options.eof_behavior {
+        EofBehavior::Zero => "0",
+        EofBehavior::Unchanged => "current",
+        EofBehavior::Max => "255",
+    };
+    f.write_all(
+        format!(
+            "static inline unsigned char get(unsigned char current){{ fflush(stdout); int c = getchar(); return c != EOF ? (unsigned char) c : {}; }}\n",
+            eof_value
+        )
+        .as_bytes(),
     )?;
 
-    f.write(b"int main(){\n")?;
-    f.write(format!("unsigned char *m = malloc({});\n", CELL_COUNT).as_bytes())?;
-    f.write(b"size_t i = 0;\n")?;
-    f.write(format!("memset(m, 0, {});\n", CELL_COUNT).as_bytes())?;
+    f.write_all(b"int main(){\n")?;
+
+    if options.buffered_output {
+        f.write_all(b"setvbuf(stdout, NULL, _IOFBF, 1 << 16);\n")?;
+    }
+
+    f.write_all(b"m = calloc(TAPE_SIZE, 1);\n")?;
+    f.write_all(b"if (!m) { fputs(\"[error] Failed to allocate the tape\\n\", stderr); return 1; }\n")?;
+    f.write_all(format!("size_t i = {};\n", options.tape_origin).as_bytes())?;
 
     for op in ops {
+        op_lines.push(f.line);
+
         match op {
             InterpreterOp::LoopStart(_) => {
-                f.write(b"while(m[i]){\n")?;
+                f.write_all(b"while(m[at(i)]){\n")?;
             }
-            InterpreterOp::LoopEnd(_) => {
-                f.write(b"}\n")?;
+            InterpreterOp::IfStart(_) => {
+                f.write_all(b"if(m[at(i)]){\n")?;
+            }
+            InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => {
+                f.write_all(b"}\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::ChangeBy(amount))) => {
-                f.write(format!("m[i] += {};\n", amount).as_bytes())?;
+                f.write_all(format!("m[at(i)] += {};\n", amount).as_bytes())?;
             }
             InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Shift(amount))) => {
                 if *amount >= 0 {
-                    f.write(format!("i += {};\n", amount).as_bytes())?;
+                    f.write_all(format!("i += {};\n", amount).as_bytes())?;
                 } else {
-                    f.write(format!("i -= {};\n", -amount).as_bytes())?;
+                    f.write_all(format!("i -= {};\n", -amount).as_bytes())?;
                 }
             }
             InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Input(count))) => {
                 for _ in 0..*count {
-                    f.write(b"m[i] = get();\n")?;
+                    f.write_all(b"m[at(i)] = get(m[at(i)]);\n")?;
                 }
             }
             InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Output(count))) => {
                 for _ in 0..*count {
-                    f.write(b"put(m[i]);\n")?;
+                    f.write_all(b"put(m[at(i)]);\n")?;
+                }
+
+                if !options.buffered_output {
+                    f.write_all(b"fflush(stdout);\n")?;
                 }
-                f.write(b"fflush(stdout);\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::LoopStart | BasicOp::LoopEnd)) => {
                 return Err(anyhow!("[error] Cannot transpile unprocessed loop instruction"));
             }
             InterpreterOp::CompoundOp(CompoundOp::Panic(value)) => {
-                f.write(format!("m[i] = {};\n", value).as_bytes())?;
-                f.write(b"exit(m[i]);\n")?;
+                f.write_all(format!("m[at(i)] = {};\n", value).as_bytes())?;
+                f.write_all(b"fflush(stdout);\n")?;
+                f.write_all(
+                    format!(
+                        "fputs(\"[PANIC] Program entered panic loop with error code {}\\n\", stderr);\n",
+                        value
+                    )
+                    .as_bytes(),
+                )?;
+                f.write_all(format!("exit({});\n", value).as_bytes())?;
             }
             InterpreterOp::CompoundOp(CompoundOp::Zero) => {
-                f.write(b"m[i] = 0;\n")?;
+                f.write_all(b"m[at(i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::ZeroAdvance(amount)) => {
-                f.write(format!("memset(&m[i], 0, {});\n", *amount).as_bytes())?;
-                f.write(format!("i += {};\n", *amount).as_bytes())?;
+                f.write_all(format!("memset(&m[span(i, {0})], 0, {0});\n", *amount).as_bytes())?;
+                f.write_all(format!("i += {};\n", *amount).as_bytes())?;
             }
             InterpreterOp::CompoundOp(CompoundOp::ZeroRetreat(amount)) => {
-                f.write(format!("memset(&m[i - {}], 0, {});\n", *amount - 1, *amount).as_bytes())?;
-                f.write(format!("i -= {};\n", *amount).as_bytes())?;
+                f.write_all(format!("memset(&m[span(i - {}, {1})], 0, {1});\n", *amount - 1, *amount).as_bytes())?;
+                f.write_all(format!("i -= {};\n", *amount).as_bytes())?;
             }
             InterpreterOp::CompoundOp(CompoundOp::Set(value)) => {
-                f.write(format!("m[i] = {};\n", value).as_bytes())?;
+                f.write_all(format!("m[at(i)] = {};\n", value).as_bytes())?;
             }
             InterpreterOp::CompoundOp(CompoundOp::Dupe(offset)) => {
-                f.write(format!("m[i] = m[i + {}];\n", *offset).as_bytes())?;
-                f.write("m[++i] = 0;\n".as_bytes())?;
+                f.write_all(format!("m[at(i)] = m[at(i + {})];\n", *offset).as_bytes())?;
+                f.write_all(b"m[at(++i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::BitAnd) => {
                 // Warning: Unsound
@@ -83,164 +160,339 @@ pub fn transpile_c<'a>(ops: impl Iterator<Item = &'a InterpreterOp>, output_file
                 // a b ? ? ? ? ? ?
                 //               ^
 
-                f.write(b"m[i - 7] &= m[i - 6];\n")?;
-                f.write(b"m[i - 6] = 0;\n")?;
-                f.write(b"i += 2;\n")?;
+                f.write_all(b"m[at(i - 7)] &= m[at(i - 6)];\n")?;
+                f.write_all(b"m[at(i - 6)] = 0;\n")?;
+                f.write_all(b"i += 2;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::WellBehavedDivMod(shift_amount)) => {
-                f.write(b"if(m[i - 1] == 0){\n")?;
-                f.write(b"m[i] = 0;\n")?;
-                f.write(b"m[i + 1] = 0;\n")?;
-                f.write(b"} else {\n")?;
-                f.write(b"m[i] = m[i - 2] % m[i - 1];\n")?;
-                f.write(b"m[i + 1] = m[i - 2] / m[i - 1];\n")?;
-                f.write(b"}\n")?;
-                f.write(b"m[i - 1] = m[i - 1] - m[i + 1];\n")?;
-                f.write(b"m[i - 2] = 0;\n")?;
-                f.write(b"m[i + 2] = 0;\n")?;
-                f.write(b"m[i + 3] = 0;\n")?;
-                f.write(format!("i += {};\n", shift_amount).as_bytes())?;
+                f.write_all(b"{\n")?;
+                f.write_all(b"const unsigned char n = m[at(i - 2)], d = m[at(i - 1)];\n")?;
+                f.write_all(b"memset(&m[span(i - 2, 6)], 0, 6);\n")?;
+                f.write_all(b"if(d != 0){\n")?;
+                f.write_all(b"m[at(i)] = n % d;\n")?;
+                f.write_all(b"m[at(i + 1)] = n / d;\n")?;
+                f.write_all(b"}\n")?;
+                f.write_all(b"m[at(i - 1)] = d - m[at(i)];\n")?;
+                f.write_all(b"}\n")?;
+
+                if *shift_amount >= 0 {
+                    f.write_all(format!("i += {};\n", shift_amount).as_bytes())?;
+                } else {
+                    f.write_all(format!("i -= {};\n", -shift_amount).as_bytes())?;
+                }
             }
             InterpreterOp::CompoundOp(CompoundOp::BitNeg) => {
-                f.write(b"m[i] = ~m[i];\n")?;
-                f.write(b"m[++i] = 0;\n")?;
+                f.write_all(b"m[at(i)] = ~m[at(i)];\n")?;
+                f.write_all(b"m[at(++i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::Equals) => {
-                f.write(b"m[i] = (m[i] == m[i + 1]);\n")?;
-                f.write(b"m[++i] = 0;\n")?;
+                f.write_all(b"m[at(i)] = (m[at(i)] == m[at(i + 1)]);\n")?;
+                f.write_all(b"m[at(++i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::NotEquals) => {
-                f.write(b"m[i] = (m[i] != m[i + 1]);\n")?;
-                f.write(b"m[++i] = 0;\n")?;
+                f.write_all(b"m[at(i)] = (m[at(i)] != m[at(i + 1)]);\n")?;
+                f.write_all(b"m[at(++i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::ShiftLeftLogical) => {
-                f.write(b"m[i - 2] = (m[i - 1] >= 8) ? 0 : m[i - 2] << m[i - 1];\n")?;
-                f.write(b"m[i - 1] = 0;\n")?;
-                f.write(b"m[i--] = 0;\n")?;
+                f.write_all(b"m[at(i - 2)] = (m[at(i - 1)] >= 8) ? 0 : m[at(i - 2)] << m[at(i - 1)];\n")?;
+                f.write_all(b"m[at(i - 1)] = 0;\n")?;
+                f.write_all(b"m[at(i--)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::ShiftRightLogical) => {
-                f.write(b"m[i - 2] = (m[i - 1] >= 8) ? 0 : m[i - 2] >> m[i - 1];\n")?;
-                f.write(b"memset(&m[--i], 0, 5);\n")?;
+                f.write_all(b"m[at(i - 2)] = (m[at(i - 1)] >= 8) ? 0 : m[at(i - 2)] >> m[at(i - 1)];\n")?;
+                f.write_all(b"memset(&m[span(--i, 5)], 0, 5);\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::LessThan) => {
-                f.write(b"m[i - 2] = m[i - 2] < m[i - 1];\n")?;
-                f.write(b"memset(&m[i - 1], 0, 3);\n")?;
+                f.write_all(b"m[at(i - 2)] = m[at(i - 2)] < m[at(i - 1)];\n")?;
+                f.write_all(b"memset(&m[span(i - 1, 3)], 0, 3);\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::GreaterThan) => {
-                f.write(b"m[i - 2] = m[i - 2] > m[i - 1];\n")?;
-                f.write(b"memset(&m[i - 1], 0, 3);\n")?;
+                f.write_all(b"m[at(i - 2)] = m[at(i - 2)] > m[at(i - 1)];\n")?;
+                f.write_all(b"memset(&m[span(i - 1, 3)], 0, 3);\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::LessThanEqual) => {
-                f.write(b"m[i - 2] = m[i - 2] <= m[i - 1];\n")?;
-                f.write(b"memset(&m[i - 1], 0, 3);\n")?;
+                f.write_all(b"m[at(i - 2)] = m[at(i - 2)] <= m[at(i - 1)];\n")?;
+                f.write_all(b"memset(&m[span(i - 1, 3)], 0, 3);\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::GreaterThanEqual) => {
-                f.write(b"m[i - 2] = m[i - 2] >= m[i - 1];\n")?;
-                f.write(b"memset(&m[i - 1], 0, 3);\n")?;
+                f.write_all(b"m[at(i - 2)] = m[at(i - 2)] >= m[at(i - 1)];\n")?;
+                f.write_all(b"memset(&m[span(i - 1, 3)], 0, 3);\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::MoveAdd(offset)) => {
-                f.write(format!("m[i + {}] += m[i];\n", offset).as_bytes())?;
-                f.write(b"m[i] = 0;\n")?;
+                f.write_all(format!("m[at(i + {})] += m[at(i)];\n", offset).as_bytes())?;
+                f.write_all(b"m[at(i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::MoveSet(offset)) => {
-                f.write(format!("m[i + {}] = m[i];\n", offset).as_bytes())?;
-                f.write(b"m[i] = 0;\n")?;
+                f.write_all(format!("m[at(i + {})] = m[at(i)];\n", offset).as_bytes())?;
+                f.write_all(b"m[at(i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::MoveAdd2(offset1, offset2)) => {
-                f.write(format!("m[i + {}] += m[i];\n", offset1).as_bytes())?;
-                f.write(format!("m[i + {}] += m[i];\n", offset2).as_bytes())?;
-                f.write(b"m[i] = 0;\n")?;
+                f.write_all(format!("m[at(i + {})] += m[at(i)];\n", offset1).as_bytes())?;
+                f.write_all(format!("m[at(i + {})] += m[at(i)];\n", offset2).as_bytes())?;
+                f.write_all(b"m[at(i)] = 0;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::PrintStatic(content)) => {
                 for c in content {
-                    f.write(format!("put({});\n", *c).as_bytes())?;
+                    f.write_all(format!("put({});\n", *c).as_bytes())?;
                 }
-                f.write("fflush(stdout);\n".as_bytes())?;
-                f.write(format!("m[i] = {};\n", *content.last().unwrap()).as_bytes())?;
+
+                if !options.buffered_output {
+                    f.write_all(b"fflush(stdout);\n")?;
+                }
+
+                f.write_all(format!("m[at(i)] = {};\n", *content.last().unwrap()).as_bytes())?;
             }
             InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU8(offset)) => {
                 // Warning: Unsound
-                f.write(format!("m[i - {} + m[i - 1]] = m[i - 2];\n", 3 + offset).as_bytes())?;
-                f.write(b"m[i - 2] = m[i - 1];\n")?;
-                f.write(b"i -= 2;\n")?;
+                f.write_all(b"{\n")?;
+                f.write_all(b"const unsigned char value = m[at(i - 2)], index = m[at(i - 1)];\n")?;
+                f.write_all(format!("m[at(i - {} + index)] = value;\n", 3 + offset).as_bytes())?;
+                f.write_all(b"m[at(i - 2)] = index;\n")?;
+                f.write_all(b"}\n")?;
+                f.write_all(b"i -= 2;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU16(offset)) => {
                 // Warning: Unsound
-                f.write(
+                f.write_all(b"{\n")?;
+                f.write_all(b"const unsigned char value = m[at(i - 3)], low = m[at(i - 2)], high = m[at(i - 1)];\n")?;
+                f.write_all(
                     format!(
-                        "m[  i - {} + ((size_t) m[i - 2] | ((size_t) m[i - 1] << 8))  ] = m[i - 3];\n",
+                        "m[at(i - {} + ((size_t) low | ((size_t) high << 8)))] = value;\n",
                         *offset
                     )
                     .as_bytes(),
                 )?;
-                f.write(b"m[i - 3] = m[i - 2];\n")?;
-                f.write(b"m[i - 2] = m[i - 1];\n")?;
-                f.write(b"i -= 3;\n")?;
+                f.write_all(b"m[at(i - 3)] = low;\n")?;
+                f.write_all(b"m[at(i - 2)] = high;\n")?;
+                f.write_all(b"}\n")?;
+                f.write_all(b"i -= 3;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU8(offset)) => {
                 // Warning: Unsound
-                f.write(format!("m[i - 1] = m[  i - {} + m[i - 1]  ];\n", *offset + 1).as_bytes())?;
+                f.write_all(format!("m[at(i - 1)] = m[at(i - {} + m[at(i - 1)])];\n", *offset + 1).as_bytes())?;
             }
             InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU32(offset)) => {
                 // Warning: Unsound
-                f.write(
+                f.write_all(b"{\n")?;
+                f.write_all(b"const unsigned char value = m[at(i - 5)], b0 = m[at(i - 4)], b1 = m[at(i - 3)], b2 = m[at(i - 2)], b3 = m[at(i - 1)];\n")?;
+                f.write_all(
                     format!(
-                        "m[  i - {} + ((size_t) m[i - 4] | ((size_t) m[i - 3] << 8) | ((size_t) m[i - 2] << 16) | ((size_t) m[i - 1] << 24))  ] = m[i - 5];\n",
+                        "m[at(i - {} + ((size_t) b0 | ((size_t) b1 << 8) | ((size_t) b2 << 16) | ((size_t) b3 << 24)))] = value;\n",
                         *offset
                     )
                     .as_bytes(),
                 )?;
-                f.write(b"m[i - 5] = m[i - 4];\n")?;
-                f.write(b"m[i - 4] = m[i - 3];\n")?;
-                f.write(b"m[i - 3] = m[i - 2];\n")?;
-                f.write(b"m[i - 2] = m[i - 1];\n")?;
-                f.write(b"i -= 5;\n")?;
+                f.write_all(b"m[at(i - 5)] = b0;\n")?;
+                f.write_all(b"m[at(i - 4)] = b1;\n")?;
+                f.write_all(b"m[at(i - 3)] = b2;\n")?;
+                f.write_all(b"m[at(i - 2)] = b3;\n")?;
+                f.write_all(b"}\n")?;
+                f.write_all(b"i -= 5;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU32(offset)) => {
                 // Warning: Unsound
 
-                f.write(
-                    format!("m[i - 4] = m[  i - {} + ((size_t) m[i - 4] | ((size_t) m[i - 3] << 8) | ((size_t) m[i - 2] << 16) | ((size_t) m[i - 1] << 24))  ];\n", *offset)
+                f.write_all(
+                    format!("m[at(i - 4)] = m[at(i - {} + ((size_t) m[at(i - 4)] | ((size_t) m[at(i - 3)] << 8) | ((size_t) m[at(i - 2)] << 16) | ((size_t) m[at(i - 1)] << 24)))];\n", *offset)
                     .as_bytes()
                     )?;
 
-                f.write(b"i -= 3;\n")?;
+                f.write_all(b"i -= 3;\n")?;
             }
             InterpreterOp::CompoundOp(CompoundOp::MoveCellsStaticReverse(offset, count)) => {
-                f.write(
+                f.write_all(
                     format!(
-                        "memmove(&m[i + {}], &m[i - {}], {});\n",
+                        "memmove(&m[span(i + {}, {2})], &m[span(i - {}, {2})], {2});\n",
                         *offset - *count as i64 + 1,
                         *count - 1,
                         *count
                     )
                     .as_bytes(),
                 )?;
-                f.write(format!("i -= {};\n", *count).as_bytes())?;
+                f.write_all(format!("memset(&m[at(i - {})], 0, {});\n", *count - 1, *count).as_bytes())?;
+                f.write_all(format!("i -= {};\n", *count).as_bytes())?;
             }
             InterpreterOp::CompoundOp(op @ CompoundOp::AddU32 | op @ CompoundOp::MulU32) => {
-                f.write(b"{\n")?;
-                f.write(b"const uint32_t a = (uint32_t) m[i - 8] | (uint32_t) m[i - 7] << 8 | (uint32_t) m[i - 6] << 16 | (uint32_t) m[i - 5] << 24;\n")?;
-                f.write(b"const uint32_t b = (uint32_t) m[i - 4] | (uint32_t) m[i - 3] << 8 | (uint32_t) m[i - 2] << 16 | (uint32_t) m[i - 1] << 24;\n")?;
+                f.write_all(b"{\n")?;
+                f.write_all(b"const uint32_t a = (uint32_t) m[at(i - 8)] | (uint32_t) m[at(i - 7)] << 8 | (uint32_t) m[at(i - 6)] << 16 | (uint32_t) m[at(i - 5)] << 24;\n")?;
+                f.write_all(b"const uint32_t b = (uint32_t) m[at(i - 4)] | (uint32_t) m[at(i - 3)] << 8 | (uint32_t) m[at(i - 2)] << 16 | (uint32_t) m[at(i - 1)] << 24;\n")?;
 
                 match *op {
-                    CompoundOp::AddU32 => f.write(b"const uint32_t result = a + b;\n")?,
-                    CompoundOp::MulU32 => f.write(b"const uint32_t result = a * b;\n")?,
+                    CompoundOp::AddU32 => f.write_all(b"const uint32_t result = a + b;\n")?,
+                    CompoundOp::MulU32 => f.write_all(b"const uint32_t result = a * b;\n")?,
                     _ => unimplemented!(),
                 };
 
-                f.write(b"m[i - 8] = result & 0xFF;\n")?;
-                f.write(b"m[i - 7] = (result >> 8) & 0xFF;\n")?;
-                f.write(b"m[i - 6] = (result >> 16 & 0xFF);\n")?;
-                f.write(b"m[i - 5] = (result >> 24 & 0xFF);\n")?;
-                f.write(b"}\n")?;
-                f.write(b"i -= 5;\n")?;
+                f.write_all(b"m[at(i - 8)] = result & 0xFF;\n")?;
+                f.write_all(b"m[at(i - 7)] = (result >> 8) & 0xFF;\n")?;
+                f.write_all(b"m[at(i - 6)] = (result >> 16 & 0xFF);\n")?;
+                f.write_all(b"m[at(i - 5)] = (result >> 24 & 0xFF);\n")?;
+                f.write_all(b"}\n")?;
+                f.write_all(b"i -= 5;\n")?;
+            }
+            InterpreterOp::CompoundOp(
+                op @ (CompoundOp::SubU32
+                | CompoundOp::LessThanU32
+                | CompoundOp::EqualsU32
+                | CompoundOp::AndU32
+                | CompoundOp::OrU32
+                | CompoundOp::XorU32
+                | CompoundOp::ShiftLeftU32
+                | CompoundOp::ShiftRightU32
+                | CompoundOp::DivModU32),
+            ) => {
+                f.write_all(b"{\n")?;
+                f.write_all(b"const uint32_t a = (uint32_t) m[at(i - 8)] | (uint32_t) m[at(i - 7)] << 8 | (uint32_t) m[at(i - 6)] << 16 | (uint32_t) m[at(i - 5)] << 24;\n")?;
+                f.write_all(b"const uint32_t b = (uint32_t) m[at(i - 4)] | (uint32_t) m[at(i - 3)] << 8 | (uint32_t) m[at(i - 2)] << 16 | (uint32_t) m[at(i - 1)] << 24;\n")?;
+
+                // Shifting by the width or more and dividing by zero are undefined in C
+                let (result, rest) = match op {
+                    CompoundOp::SubU32 => ("a - b", "0"),
+                    CompoundOp::LessThanU32 => ("a < b", "0"),
+                    CompoundOp::EqualsU32 => ("a == b", "0"),
+                    CompoundOp::AndU32 => ("a & b", "0"),
+                    CompoundOp::OrU32 => ("a | b", "0"),
+                    CompoundOp::XorU32 => ("a ^ b", "0"),
+                    CompoundOp::ShiftLeftU32 => ("b < 32 ? a << b : 0", "0"),
+                    CompoundOp::ShiftRightU32 => ("b < 32 ? a >> b : 0", "0"),
+                    _ => ("b ? a / b : 0", "b ? a % b : 0"),
+                };
+
+                f.write_all(format!("const uint32_t result = {};\n", result).as_bytes())?;
+                f.write_all(format!("const uint32_t rest = {};\n", rest).as_bytes())?;
+
+                for (i, value) in ["result", "rest"].into_iter().enumerate() {
+                    for byte in 0..4 {
+                        f.write_all(
+                            format!(
+                                "m[at(i - {})] = ({} >> {}) & 0xFF;\n",
+                                8 - 4 * i - byte,
+                                value,
+                                8 * byte
+                            )
+                            .as_bytes(),
+                        )?;
+                    }
+                }
+
+                f.write_all(b"}\n")?;
+                f.write_all(b"i -= 5;\n")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddMany(targets)) => {
+                for (offset, factor) in targets {
+                    f.write_all(format!("m[at(i + {})] += {} * m[at(i)];\n", offset, factor).as_bytes())?;
+                }
+
+                f.write_all(b"m[at(i)] = 0;\n")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(1)) => {
+                f.write_all(b"i = scan_right(i);\n")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(1)) => {
+                f.write_all(b"i = scan_left(i);\n")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(stride)) => {
+                f.write_all(format!("while(m[at(i)]) i += {};\n", stride).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(stride)) => {
+                f.write_all(format!("while(m[at(i)]) i -= {};\n", stride).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetCells(offset, values)) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                f.write_all(
+                    format!("{{ static const unsigned char values[] = {{{}}};\n", values.join(", ")).as_bytes(),
+                )?;
+                f.write_all(
+                    format!(
+                        "memcpy(m + span(i + {}, sizeof(values)), values, sizeof(values)); }}\n",
+                        offset
+                    )
+                    .as_bytes(),
+                )?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ChangeByAt(offset, amount)) => {
+                f.write_all(format!("m[at(i + {})] += {};\n", offset, amount).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetAt(offset, value)) => {
+                f.write_all(format!("m[at(i + {})] = {};\n", offset, value).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::InputAt(offset, count)) => {
+                for _ in 0..*count {
+                    f.write_all(format!("m[at(i + {0})] = get(m[at(i + {0})]);\n", offset).as_bytes())?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::OutputAt(offset, count)) => {
+                for _ in 0..*count {
+                    f.write_all(format!("put(m[at(i + {})]);\n", offset).as_bytes())?;
+                }
+
+                if !options.buffered_output {
+                    f.write_all(b"fflush(stdout);\n")?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAddAt(from, to)) => {
+                f.write_all(format!("m[at(i + {})] += m[at(i + {})];\n", to, from).as_bytes())?;
+                f.write_all(format!("m[at(i + {})] = 0;\n", from).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2At(from, to1, to2)) => {
+                f.write_all(format!("m[at(i + {})] += m[at(i + {})];\n", to1, from).as_bytes())?;
+                f.write_all(format!("m[at(i + {})] += m[at(i + {})];\n", to2, from).as_bytes())?;
+                f.write_all(format!("m[at(i + {})] = 0;\n", from).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSetAt(from, to)) => {
+                f.write_all(format!("m[at(i + {})] = m[at(i + {})];\n", to, from).as_bytes())?;
+                f.write_all(format!("m[at(i + {})] = 0;\n", from).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddManyAt(from, targets)) => {
+                for (offset, factor) in targets {
+                    f.write_all(format!("m[at(i + {})] += {} * m[at(i + {})];\n", offset, factor, from).as_bytes())?;
+                }
+
+                f.write_all(format!("m[at(i + {})] = 0;\n", from).as_bytes())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Affine(updates)) => {
+                f.write_all(b"{\n")?;
+
+                for (update_i, update) in updates.iter().enumerate() {
+                    let mut value = update.constant.to_string();
+
+                    for (offset, coefficient) in &update.terms {
+                        value += &format!(" + {} * m[at(i + {})]", coefficient, offset);
+                    }
+
+                    f.write_all(format!("const unsigned char a{} = {};\n", update_i, value).as_bytes())?;
+                }
+
+                for (update_i, update) in updates.iter().enumerate() {
+                    f.write_all(format!("m[at(i + {})] = a{};\n", update.offset, update_i).as_bytes())?;
+                }
+
+                f.write_all(b"}\n")?;
             }
         }
     }
 
-    f.write(b"free(m);\n")?;
-    f.write(b"return 0;\n")?;
-    f.write(b"}\n")?;
-    Ok(())
+    f.write_all(b"free(m);\n")?;
+    f.write_all(b"return 0;\n")?;
+    f.write_all(b"}\n")?;
+    f.flush()?;
+    Ok(op_lines)
+}
+
+/// Keeps track of the line being written so compiler diagnostics can be traced back to ops
+struct LineCounter<W: Write> {
+    inner: W,
+    line: usize,
+}
+
+impl<W: Write> Write for LineCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.line += buf[..written].iter().filter(|&&byte| byte == b'\n').count();
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
 }
@@ -0,0 +1,111 @@
+use crate::{
+    interpreter::{EofBehavior, CELL_COUNT},
+    interpreter_op::InterpreterOp,
+    x86_64::{Assembler, Inst},
+    x86_64_codegen::{self, CodegenOptions, TAPE},
+};
+use anyhow::Result;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+const BASE_ADDRESS: u64 = 0x400000;
+const PAGE_SIZE: u64 = 0x1000;
+
+const ELF_HEADER_SIZE: u16 = 64;
+const PROGRAM_HEADER_SIZE: u16 = 56;
+const PROGRAM_HEADER_COUNT: u16 = 2;
+
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+/// Writes a static x86-64 Linux executable without going through an assembler or linker
+///
+/// The file holds a single segment with the headers and code, the tape is a second
+/// zero-filled segment that takes no space on disk.
+pub fn transpile_elf(
+    ops: &[InterpreterOp],
+    output_filename: &str,
+    bounds_checks: bool,
+    eof_behavior: EofBehavior,
+) -> Result<()> {
+    let headers_size = (ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE * PROGRAM_HEADER_COUNT) as u64;
+    let entry = BASE_ADDRESS + headers_size;
+
+    // The tape address is loaded as a 32-bit immediate either way, so the code size doesn't depend on it
+    let code_size = encode(ops, BASE_ADDRESS, bounds_checks, eof_behavior)?.len() as u64;
+    let text_size = headers_size + code_size;
+    let tape_address = (BASE_ADDRESS + text_size).div_ceil(PAGE_SIZE) * PAGE_SIZE;
+    let code = encode(ops, tape_address, bounds_checks, eof_behavior)?;
+
+    let file = File::create(output_filename)?;
+    let mut f = BufWriter::new(file);
+
+    // ELF header
+    f.write_all(&[0x7F, b'E', b'L', b'F'])?;
+    f.write_all(&[2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0])?; // 64-bit, little endian, version 1, System V
+    f.write_all(&2u16.to_le_bytes())?; // Executable
+    f.write_all(&0x3Eu16.to_le_bytes())?; // x86-64
+    f.write_all(&1u32.to_le_bytes())?;
+    f.write_all(&entry.to_le_bytes())?;
+    f.write_all(&(ELF_HEADER_SIZE as u64).to_le_bytes())?; // Program headers follow immediately
+    f.write_all(&0u64.to_le_bytes())?; // No section headers
+    f.write_all(&0u32.to_le_bytes())?;
+    f.write_all(&ELF_HEADER_SIZE.to_le_bytes())?;
+    f.write_all(&PROGRAM_HEADER_SIZE.to_le_bytes())?;
+    f.write_all(&PROGRAM_HEADER_COUNT.to_le_bytes())?;
+    f.write_all(&[0; 6])?;
+
+    // Headers and code
+    write_program_header(&mut f, PF_R | PF_X, 0, BASE_ADDRESS, text_size, text_size)?;
+
+    // Tape
+    write_program_header(&mut f, PF_R | PF_W, 0, tape_address, 0, CELL_COUNT as u64)?;
+
+    f.write_all(&code)?;
+    f.flush()?;
+    drop(f);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(output_filename, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+fn encode(ops: &[InterpreterOp], tape_address: u64, bounds_checks: bool, eof_behavior: EofBehavior) -> Result<Vec<u8>> {
+    let mut asm = Assembler::new();
+
+    asm.push(Inst::MovImm(TAPE, tape_address as i64));
+
+    let options = CodegenOptions {
+        bounds_checks,
+        eof_behavior,
+    };
+    x86_64_codegen::lower_executable(&mut asm, ops, options)?;
+    Ok(asm.encode())
+}
+
+fn write_program_header(
+    f: &mut impl Write,
+    flags: u32,
+    offset: u64,
+    address: u64,
+    file_size: u64,
+    memory_size: u64,
+) -> Result<()> {
+    f.write_all(&PT_LOAD.to_le_bytes())?;
+    f.write_all(&flags.to_le_bytes())?;
+    f.write_all(&offset.to_le_bytes())?;
+    f.write_all(&address.to_le_bytes())?;
+    f.write_all(&address.to_le_bytes())?;
+    f.write_all(&file_size.to_le_bytes())?;
+    f.write_all(&memory_size.to_le_bytes())?;
+    f.write_all(&PAGE_SIZE.to_le_bytes())?;
+    Ok(())
+}
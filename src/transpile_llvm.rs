@@ -0,0 +1,806 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{EofBehavior, CELL_COUNT},
+    interpreter_op::InterpreterOp,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Emits textual LLVM IR for a program that links against libc
+///
+/// Only the EOF behavior is configurable, the tape is always the default size with the program starting on
+/// its first cell, accesses aren't bounds checked and output is flushed after every write. The backend
+/// rejects `--bounds-checks`, custom tape settings and `--buffered-output` rather than ignoring them.
+pub fn transpile_llvm<'a>(
+    ops: impl Iterator<Item = &'a InterpreterOp>,
+    output_filename: &str,
+    eof_behavior: EofBehavior,
+) -> Result<()> {
+    let file = File::create(output_filename)?;
+    let mut f = Llvm {
+        f: BufWriter::new(file),
+        next_value: 0,
+        next_label: 0,
+        loops: Vec::new(),
+        statics: Vec::new(),
+    };
+
+    f.write_prelude(eof_behavior)?;
+
+    f.line("define i32 @main() {")?;
+    f.line("entry:")?;
+    f.line(&format!("  %m = call ptr @calloc(i64 {}, i64 1)", CELL_COUNT))?;
+    f.line("  %i = alloca i64")?;
+    f.line("  store i64 0, ptr %i")?;
+
+    for op in ops {
+        match op {
+            InterpreterOp::LoopStart(_) => {
+                let label = f.label();
+                f.loops.push(label);
+                f.line(&format!("  br label %loop{}", label))?;
+                f.line(&format!("loop{}:", label))?;
+                let value = f.load(0)?;
+                let condition = f.value();
+                f.line(&format!("  {} = icmp ne i8 {}, 0", condition, value))?;
+                f.line(&format!(
+                    "  br i1 {}, label %body{}, label %end{}",
+                    condition, label, label
+                ))?;
+                f.line(&format!("body{}:", label))?;
+            }
+            InterpreterOp::LoopEnd(_) => {
+                let label = f
+                    .loops
+                    .pop()
+                    .ok_or_else(|| anyhow!("[error] Instruction ']' is missing match"))?;
+                f.line(&format!("  br label %loop{}", label))?;
+                f.line(&format!("end{}:", label))?;
+            }
+            InterpreterOp::IfStart(_) => {
+                let label = f.label();
+                f.loops.push(label);
+                let value = f.load(0)?;
+                let condition = f.value();
+                f.line(&format!("  {} = icmp ne i8 {}, 0", condition, value))?;
+                f.line(&format!(
+                    "  br i1 {}, label %then{}, label %end{}",
+                    condition, label, label
+                ))?;
+                f.line(&format!("then{}:", label))?;
+            }
+            InterpreterOp::IfEnd(_) => {
+                let label = f
+                    .loops
+                    .pop()
+                    .ok_or_else(|| anyhow!("[error] Instruction ']' is missing match"))?;
+                f.line(&format!("  br label %end{}", label))?;
+                f.line(&format!("end{}:", label))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::ChangeBy(amount))) => {
+                let value = f.load(0)?;
+                let result = f.value();
+                f.line(&format!("  {} = add i8 {}, {}", result, value, amount))?;
+                f.store(0, &result)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Shift(amount))) => {
+                f.shift(*amount)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Input(count))) => {
+                for _ in 0..*count {
+                    let value = f.load(0)?;
+                    let result = f.value();
+                    f.line(&format!("  {} = call i8 @get(i8 {})", result, value))?;
+                    f.store(0, &result)?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Output(count))) => {
+                let value = f.load(0)?;
+                for _ in 0..*count {
+                    f.line(&format!("  call void @put(i8 {})", value))?;
+                }
+                f.line("  call i32 @fflush(ptr null)")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::LoopStart | BasicOp::LoopEnd)) => {
+                return Err(anyhow!("[error] Cannot transpile unprocessed loop instruction"));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Panic(value)) => {
+                let report = format!("[PANIC] Program entered panic loop with error code {}\n", value).into_bytes();
+                let name = format!("@static{}", f.statics.len());
+                f.store(0, &value.to_string())?;
+                f.line("  call i32 @fflush(ptr null)")?;
+                f.line(&format!("  call i64 @write(i32 2, ptr {}, i64 {})", name, report.len()))?;
+                f.line(&format!("  call void @exit(i32 {})", value))?;
+                f.statics.push((name, report));
+                f.line("  unreachable")?;
+
+                // Code after a panic is unreachable but still needs a block to live in
+                let label = f.label();
+                f.line(&format!("panicked{}:", label))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Zero) => {
+                f.store(0, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroAdvance(amount)) => {
+                f.memset(0, *amount)?;
+                f.shift(*amount as i64)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroRetreat(amount)) => {
+                f.memset(1 - *amount as i64, *amount)?;
+                f.shift(-(*amount as i64))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Set(value)) => {
+                f.store(0, &value.to_string())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Dupe(offset)) => {
+                let value = f.load(*offset)?;
+                f.store(0, &value)?;
+                f.store(1, "0")?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitAnd) => {
+                // Warning: Unsound
+
+                // a b ? ? ? ? ? ?
+                //               ^
+
+                let a = f.load(-7)?;
+                let b = f.load(-6)?;
+                let result = f.value();
+                f.line(&format!("  {} = and i8 {}, {}", result, a, b))?;
+                f.store(-7, &result)?;
+                f.store(-6, "0")?;
+                f.shift(2)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::WellBehavedDivMod(shift_amount)) => {
+                let n = f.load(-2)?;
+                let d = f.load(-1)?;
+                let is_zero = f.value();
+                let divisor = f.value();
+                let quotient = f.value();
+                let remainder = f.value();
+                let n_div_d = f.value();
+                let n_mod_d = f.value();
+                let difference = f.value();
+
+                // Division by zero is undefined behavior in LLVM, so divide by 1 and select zero instead
+                f.line(&format!("  {} = icmp eq i8 {}, 0", is_zero, d))?;
+                f.line(&format!("  {} = select i1 {}, i8 1, i8 {}", divisor, is_zero, d))?;
+                f.line(&format!("  {} = udiv i8 {}, {}", quotient, n, divisor))?;
+                f.line(&format!("  {} = urem i8 {}, {}", remainder, n, divisor))?;
+                f.line(&format!("  {} = select i1 {}, i8 0, i8 {}", n_div_d, is_zero, quotient))?;
+                f.line(&format!(
+                    "  {} = select i1 {}, i8 0, i8 {}",
+                    n_mod_d, is_zero, remainder
+                ))?;
+                f.line(&format!("  {} = sub i8 {}, {}", difference, d, n_mod_d))?;
+
+                f.store(-2, "0")?;
+                f.store(-1, &difference)?;
+                f.store(0, &n_mod_d)?;
+                f.store(1, &n_div_d)?;
+                f.store(2, "0")?;
+                f.store(3, "0")?;
+                f.shift(*shift_amount)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitNeg) => {
+                let value = f.load(0)?;
+                let result = f.value();
+                f.line(&format!("  {} = xor i8 {}, -1", result, value))?;
+                f.store(0, &result)?;
+                f.store(1, "0")?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Equals) => {
+                f.compare("eq", 0, 1)?;
+                f.store(1, "0")?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::NotEquals) => {
+                f.compare("ne", 0, 1)?;
+                f.store(1, "0")?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftLeftLogical) => {
+                f.logical_shift("shl")?;
+                f.store(-1, "0")?;
+                f.store(0, "0")?;
+                f.shift(-1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftRightLogical) => {
+                f.logical_shift("lshr")?;
+                f.memset(-1, 5)?;
+                f.shift(-1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::LessThan) => f.compare_and_clear("ult")?,
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThan) => f.compare_and_clear("ugt")?,
+            InterpreterOp::CompoundOp(CompoundOp::LessThanEqual) => f.compare_and_clear("ule")?,
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThanEqual) => f.compare_and_clear("uge")?,
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd(offset)) => {
+                let value = f.load(0)?;
+                f.add_to(*offset, &value)?;
+                f.store(0, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSet(offset)) => {
+                let value = f.load(0)?;
+                f.store(*offset, &value)?;
+                f.store(0, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2(offset1, offset2)) => {
+                let value = f.load(0)?;
+                f.add_to(*offset1, &value)?;
+                f.add_to(*offset2, &value)?;
+                f.store(0, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::PrintStatic(content)) => {
+                let name = format!("@static{}", f.statics.len());
+                f.line(&format!("  call void @put_static(ptr {}, i64 {})", name, content.len()))?;
+                f.line("  call i32 @fflush(ptr null)")?;
+                f.store(0, &content.last().unwrap().to_string())?;
+                f.statics.push((name, content.clone()));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                let value = f.load(-2)?;
+                let index = f.load(-1)?;
+                let wide_index = f.zext("i8", &index)?;
+                let destination = f.dynamic_pointer(-3 - *offset as i64, &wide_index)?;
+                f.line(&format!("  store i8 {}, ptr {}", value, destination))?;
+                f.store(-2, &index)?;
+                f.shift(-2)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU16(offset)) => {
+                // Warning: Unsound
+                let value = f.load(-3)?;
+                let index_pointer = f.pointer(-2)?;
+                let index = f.value();
+                f.line(&format!("  {} = call i16 @load_u16(ptr {})", index, index_pointer))?;
+                let wide_index = f.zext("i16", &index)?;
+                let destination = f.dynamic_pointer(-(*offset as i64), &wide_index)?;
+                f.line(&format!("  store i8 {}, ptr {}", value, destination))?;
+                let moved_pointer = f.pointer(-3)?;
+                f.line(&format!("  call void @store_u16(ptr {}, i16 {})", moved_pointer, index))?;
+                f.shift(-3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                let index = f.load(-1)?;
+                let wide_index = f.zext("i8", &index)?;
+                let source = f.dynamic_pointer(-1 - *offset as i64, &wide_index)?;
+                let value = f.value();
+                f.line(&format!("  {} = load i8, ptr {}", value, source))?;
+                f.store(-1, &value)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                let value = f.load(-5)?;
+                let index = f.load_u32(-4)?;
+                let wide_index = f.zext("i32", &index)?;
+                let destination = f.dynamic_pointer(-(*offset as i64), &wide_index)?;
+                f.line(&format!("  store i8 {}, ptr {}", value, destination))?;
+                f.store_u32(-5, &index)?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                let index = f.load_u32(-4)?;
+                let wide_index = f.zext("i32", &index)?;
+                let source = f.dynamic_pointer(-(*offset as i64), &wide_index)?;
+                let value = f.value();
+                f.line(&format!("  {} = load i8, ptr {}", value, source))?;
+                f.store(-4, &value)?;
+                f.shift(-3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellsStaticReverse(offset, count)) => {
+                let count = *count as i64;
+                let source = f.pointer(1 - count)?;
+                let destination = f.pointer(*offset + 1 - count)?;
+                f.line(&format!(
+                    "  call void @llvm.memmove.p0.p0.i64(ptr {}, ptr {}, i64 {}, i1 false)",
+                    destination, source, count
+                ))?;
+                f.memset(1 - count, count as u64)?;
+                f.shift(-count)?;
+            }
+            InterpreterOp::CompoundOp(op @ CompoundOp::AddU32 | op @ CompoundOp::MulU32) => {
+                // Warning: Unsound
+                let a = f.load_u32(-8)?;
+                let b = f.load_u32(-4)?;
+                let result = f.value();
+
+                match *op {
+                    CompoundOp::AddU32 => f.line(&format!("  {} = add i32 {}, {}", result, a, b))?,
+                    CompoundOp::MulU32 => f.line(&format!("  {} = mul i32 {}, {}", result, a, b))?,
+                    _ => unimplemented!(),
+                };
+
+                f.store_u32(-8, &result)?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(
+                op @ (CompoundOp::SubU32
+                | CompoundOp::LessThanU32
+                | CompoundOp::EqualsU32
+                | CompoundOp::AndU32
+                | CompoundOp::OrU32
+                | CompoundOp::XorU32
+                | CompoundOp::ShiftLeftU32
+                | CompoundOp::ShiftRightU32
+                | CompoundOp::DivModU32),
+            ) => {
+                let a = f.load_u32(-8)?;
+                let b = f.load_u32(-4)?;
+                let result = f.value();
+                let mut rest = "0".to_string();
+
+                match op {
+                    CompoundOp::SubU32 | CompoundOp::AndU32 | CompoundOp::OrU32 | CompoundOp::XorU32 => {
+                        let instruction = match op {
+                            CompoundOp::SubU32 => "sub",
+                            CompoundOp::AndU32 => "and",
+                            CompoundOp::OrU32 => "or",
+                            _ => "xor",
+                        };
+                        f.line(&format!("  {} = {} i32 {}, {}", result, instruction, a, b))?;
+                    }
+                    CompoundOp::LessThanU32 | CompoundOp::EqualsU32 => {
+                        let condition = match op {
+                            CompoundOp::LessThanU32 => "ult",
+                            _ => "eq",
+                        };
+                        let test = f.value();
+                        f.line(&format!("  {} = icmp {} i32 {}, {}", test, condition, a, b))?;
+                        f.line(&format!("  {} = zext i1 {} to i32", result, test))?;
+                    }
+                    CompoundOp::ShiftLeftU32 | CompoundOp::ShiftRightU32 => {
+                        // Shifting by the width or more is poison, which select doesn't pass on when it picks 0
+                        let instruction = match op {
+                            CompoundOp::ShiftLeftU32 => "shl",
+                            _ => "lshr",
+                        };
+                        let shifted = f.value();
+                        let in_range = f.value();
+                        f.line(&format!("  {} = {} i32 {}, {}", shifted, instruction, a, b))?;
+                        f.line(&format!("  {} = icmp ult i32 {}, 32", in_range, b))?;
+                        f.line(&format!(
+                            "  {} = select i1 {}, i32 {}, i32 0",
+                            result, in_range, shifted
+                        ))?;
+                    }
+                    _ => {
+                        // Dividing by zero is undefined, so it divides by one and throws the results away instead
+                        let by_zero = f.value();
+                        let divisor = f.value();
+                        let quotient = f.value();
+                        let remainder = f.value();
+                        rest = f.value();
+                        f.line(&format!("  {} = icmp eq i32 {}, 0", by_zero, b))?;
+                        f.line(&format!("  {} = select i1 {}, i32 1, i32 {}", divisor, by_zero, b))?;
+                        f.line(&format!("  {} = udiv i32 {}, {}", quotient, a, divisor))?;
+                        f.line(&format!("  {} = urem i32 {}, {}", remainder, a, divisor))?;
+                        f.line(&format!(
+                            "  {} = select i1 {}, i32 0, i32 {}",
+                            result, by_zero, quotient
+                        ))?;
+                        f.line(&format!("  {} = select i1 {}, i32 0, i32 {}", rest, by_zero, remainder))?;
+                    }
+                }
+
+                f.store_u32(-8, &result)?;
+                f.store_u32(-4, &rest)?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddMany(targets)) => {
+                let value = f.load(0)?;
+
+                for (offset, factor) in targets {
+                    let product = f.value();
+                    f.line(&format!("  {} = mul i8 {}, {}", product, value, factor))?;
+                    f.add_to(*offset, &product)?;
+                }
+
+                f.store(0, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(1)) => {
+                let pointer = f.pointer(0)?;
+                let index = f.value();
+                let remaining = f.value();
+                let found = f.value();
+                f.line(&format!("  {} = load i64, ptr %i", index))?;
+                f.line(&format!("  {} = sub i64 {}, {}", remaining, CELL_COUNT, index))?;
+                f.line(&format!(
+                    "  {} = call ptr @memchr(ptr {}, i32 0, i64 {})",
+                    found, pointer, remaining
+                ))?;
+                f.move_to(&found)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(1)) => {
+                let index = f.value();
+                let length = f.value();
+                let found = f.value();
+                f.line(&format!("  {} = load i64, ptr %i", index))?;
+                f.line(&format!("  {} = add i64 {}, 1", length, index))?;
+                f.line(&format!(
+                    "  {} = call ptr @memrchr(ptr %m, i32 0, i64 {})",
+                    found, length
+                ))?;
+                f.move_to(&found)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(stride)) => {
+                f.scan(*stride as i64)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(stride)) => {
+                f.scan(-(*stride as i64))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetCells(offset, values)) => {
+                let name = format!("@static{}", f.statics.len());
+                let pointer = f.pointer(*offset)?;
+                f.line(&format!(
+                    "  call void @llvm.memcpy.p0.p0.i64(ptr {}, ptr {}, i64 {}, i1 false)",
+                    pointer,
+                    name,
+                    values.len()
+                ))?;
+                f.statics.push((name, values.clone()));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ChangeByAt(offset, amount)) => {
+                f.add_to(*offset, &amount.to_string())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetAt(offset, value)) => {
+                f.store(*offset, &value.to_string())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::InputAt(offset, count)) => {
+                for _ in 0..*count {
+                    let value = f.load(*offset)?;
+                    let result = f.value();
+                    f.line(&format!("  {} = call i8 @get(i8 {})", result, value))?;
+                    f.store(*offset, &result)?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::OutputAt(offset, count)) => {
+                let value = f.load(*offset)?;
+                for _ in 0..*count {
+                    f.line(&format!("  call void @put(i8 {})", value))?;
+                }
+                f.line("  call i32 @fflush(ptr null)")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAddAt(from, to)) => {
+                let value = f.load(*from)?;
+                f.add_to(*to, &value)?;
+                f.store(*from, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2At(from, to1, to2)) => {
+                let value = f.load(*from)?;
+                f.add_to(*to1, &value)?;
+                f.add_to(*to2, &value)?;
+                f.store(*from, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSetAt(from, to)) => {
+                let value = f.load(*from)?;
+                f.store(*to, &value)?;
+                f.store(*from, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddManyAt(from, targets)) => {
+                let value = f.load(*from)?;
+
+                for (offset, factor) in targets {
+                    let product = f.value();
+                    f.line(&format!("  {} = mul i8 {}, {}", product, value, factor))?;
+                    f.add_to(*offset, &product)?;
+                }
+
+                f.store(*from, "0")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Affine(updates)) => {
+                let mut values = Vec::new();
+
+                for update in updates {
+                    let mut value = update.constant.to_string();
+
+                    for (offset, coefficient) in &update.terms {
+                        let cell = f.load(*offset)?;
+                        let product = f.value();
+                        let sum = f.value();
+                        f.line(&format!("  {} = mul i8 {}, {}", product, cell, coefficient))?;
+                        f.line(&format!("  {} = add i8 {}, {}", sum, value, product))?;
+                        value = sum;
+                    }
+
+                    values.push(value);
+                }
+
+                for (update, value) in updates.iter().zip(values) {
+                    f.store(update.offset, &value)?;
+                }
+            }
+        }
+    }
+
+    f.line("  call void @free(ptr %m)")?;
+    f.line("  ret i32 0")?;
+    f.line("}")?;
+
+    for (name, content) in std::mem::take(&mut f.statics) {
+        let escaped: String = content.iter().map(|c| format!("\\{:02X}", c)).collect();
+        f.line(&format!(
+            "{} = private unnamed_addr constant [{} x i8] c\"{}\"",
+            name,
+            content.len(),
+            escaped
+        ))?;
+    }
+
+    Ok(())
+}
+
+struct Llvm {
+    f: BufWriter<File>,
+    next_value: usize,
+    next_label: usize,
+    loops: Vec<usize>,
+    statics: Vec<(String, Vec<u8>)>,
+}
+
+impl Llvm {
+    fn line(&mut self, line: &str) -> Result<()> {
+        self.f.write_all(line.as_bytes())?;
+        self.f.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn value(&mut self) -> String {
+        self.next_value += 1;
+        format!("%v{}", self.next_value)
+    }
+
+    fn label(&mut self) -> usize {
+        self.next_label += 1;
+        self.next_label
+    }
+
+    fn write_prelude(&mut self, eof_behavior: EofBehavior) -> Result<()> {
+        let eof_value = match eof_behavior {
+            EofBehavior::Zero => "0",
+            EofBehavior::Unchanged => "%current",
+            EofBehavior::Max => "-1",
+        };
+
+        self.line("declare i32 @getchar()")?;
+        self.line("declare i32 @putchar(i32)")?;
+        self.line("declare i32 @fflush(ptr)")?;
+        self.line("declare ptr @calloc(i64, i64)")?;
+        self.line("declare void @free(ptr)")?;
+        self.line("declare void @exit(i32)")?;
+        self.line("declare i64 @write(i32, ptr, i64)")?;
+        self.line("declare ptr @memchr(ptr, i32, i64)")?;
+        self.line("declare ptr @memrchr(ptr, i32, i64)")?;
+        self.line("declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)")?;
+        self.line("declare void @llvm.memmove.p0.p0.i64(ptr, ptr, i64, i1)")?;
+        self.line("declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)")?;
+        self.line("")?;
+        self.line("define internal void @put(i8 %c) {")?;
+        self.line("  %wide = zext i8 %c to i32")?;
+        self.line("  call i32 @putchar(i32 %wide)")?;
+        self.line("  ret void")?;
+        self.line("}")?;
+        self.line("")?;
+        self.line("define internal i8 @get(i8 %current) {")?;
+        self.line("  %c = call i32 @getchar()")?;
+        self.line("  %eof = icmp eq i32 %c, -1")?;
+        self.line("  %byte = trunc i32 %c to i8")?;
+        self.line(&format!("  %result = select i1 %eof, i8 {}, i8 %byte", eof_value))?;
+        self.line("  ret i8 %result")?;
+        self.line("}")?;
+        self.line("")?;
+        self.line("define internal void @put_static(ptr %s, i64 %n) {")?;
+        self.line("entry:")?;
+        self.line("  br label %cond")?;
+        self.line("cond:")?;
+        self.line("  %k = phi i64 [0, %entry], [%next, %body]")?;
+        self.line("  %more = icmp ult i64 %k, %n")?;
+        self.line("  br i1 %more, label %body, label %done")?;
+        self.line("body:")?;
+        self.line("  %p = getelementptr i8, ptr %s, i64 %k")?;
+        self.line("  %c = load i8, ptr %p")?;
+        self.line("  call void @put(i8 %c)")?;
+        self.line("  %next = add i64 %k, 1")?;
+        self.line("  br label %cond")?;
+        self.line("done:")?;
+        self.line("  ret void")?;
+        self.line("}")?;
+        self.line("")?;
+
+        // Multi-cell integers are little-endian on the tape regardless of the target,
+        // LLVM folds these back into single loads and stores where it can
+        self.line("define internal i16 @load_u16(ptr %p) alwaysinline {")?;
+        self.line("  %p1 = getelementptr i8, ptr %p, i64 1")?;
+        self.line("  %b0 = load i8, ptr %p")?;
+        self.line("  %b1 = load i8, ptr %p1")?;
+        self.line("  %w0 = zext i8 %b0 to i16")?;
+        self.line("  %w1 = zext i8 %b1 to i16")?;
+        self.line("  %s1 = shl i16 %w1, 8")?;
+        self.line("  %r = or i16 %w0, %s1")?;
+        self.line("  ret i16 %r")?;
+        self.line("}")?;
+        self.line("")?;
+        self.line("define internal void @store_u16(ptr %p, i16 %v) alwaysinline {")?;
+        self.line("  %p1 = getelementptr i8, ptr %p, i64 1")?;
+        self.line("  %b0 = trunc i16 %v to i8")?;
+        self.line("  %s1 = lshr i16 %v, 8")?;
+        self.line("  %b1 = trunc i16 %s1 to i8")?;
+        self.line("  store i8 %b0, ptr %p")?;
+        self.line("  store i8 %b1, ptr %p1")?;
+        self.line("  ret void")?;
+        self.line("}")?;
+        self.line("")?;
+        self.line("define internal i32 @load_u32(ptr %p) alwaysinline {")?;
+        self.line("  %lo = call i16 @load_u16(ptr %p)")?;
+        self.line("  %p2 = getelementptr i8, ptr %p, i64 2")?;
+        self.line("  %hi = call i16 @load_u16(ptr %p2)")?;
+        self.line("  %wlo = zext i16 %lo to i32")?;
+        self.line("  %whi = zext i16 %hi to i32")?;
+        self.line("  %shi = shl i32 %whi, 16")?;
+        self.line("  %r = or i32 %wlo, %shi")?;
+        self.line("  ret i32 %r")?;
+        self.line("}")?;
+        self.line("")?;
+        self.line("define internal void @store_u32(ptr %p, i32 %v) alwaysinline {")?;
+        self.line("  %lo = trunc i32 %v to i16")?;
+        self.line("  %shi = lshr i32 %v, 16")?;
+        self.line("  %hi = trunc i32 %shi to i16")?;
+        self.line("  %p2 = getelementptr i8, ptr %p, i64 2")?;
+        self.line("  call void @store_u16(ptr %p, i16 %lo)")?;
+        self.line("  call void @store_u16(ptr %p2, i16 %hi)")?;
+        self.line("  ret void")?;
+        self.line("}")?;
+        self.line("")?;
+        Ok(())
+    }
+
+    /// Computes the address of the cell at `offset` from the current cell
+    fn pointer(&mut self, offset: i64) -> Result<String> {
+        let index = self.value();
+        self.line(&format!("  {} = load i64, ptr %i", index))?;
+
+        let index = if offset != 0 {
+            let moved = self.value();
+            self.line(&format!("  {} = add i64 {}, {}", moved, index, offset))?;
+            moved
+        } else {
+            index
+        };
+
+        let pointer = self.value();
+        self.line(&format!("  {} = getelementptr i8, ptr %m, i64 {}", pointer, index))?;
+        Ok(pointer)
+    }
+
+    /// Computes the address of the cell at `offset + index` from the current cell
+    fn dynamic_pointer(&mut self, offset: i64, index: &str) -> Result<String> {
+        let base = self.pointer(offset)?;
+        let pointer = self.value();
+        self.line(&format!(
+            "  {} = getelementptr i8, ptr {}, i64 {}",
+            pointer, base, index
+        ))?;
+        Ok(pointer)
+    }
+
+    fn load(&mut self, offset: i64) -> Result<String> {
+        let pointer = self.pointer(offset)?;
+        let value = self.value();
+        self.line(&format!("  {} = load i8, ptr {}", value, pointer))?;
+        Ok(value)
+    }
+
+    fn store(&mut self, offset: i64, value: &str) -> Result<()> {
+        let pointer = self.pointer(offset)?;
+        self.line(&format!("  store i8 {}, ptr {}", value, pointer))
+    }
+
+    fn load_u32(&mut self, offset: i64) -> Result<String> {
+        let pointer = self.pointer(offset)?;
+        let value = self.value();
+        self.line(&format!("  {} = call i32 @load_u32(ptr {})", value, pointer))?;
+        Ok(value)
+    }
+
+    fn store_u32(&mut self, offset: i64, value: &str) -> Result<()> {
+        let pointer = self.pointer(offset)?;
+        self.line(&format!("  call void @store_u32(ptr {}, i32 {})", pointer, value))
+    }
+
+    fn add_to(&mut self, offset: i64, value: &str) -> Result<()> {
+        let existing = self.load(offset)?;
+        let result = self.value();
+        self.line(&format!("  {} = add i8 {}, {}", result, existing, value))?;
+        self.store(offset, &result)
+    }
+
+    fn zext(&mut self, from: &str, value: &str) -> Result<String> {
+        let result = self.value();
+        self.line(&format!("  {} = zext {} {} to i64", result, from, value))?;
+        Ok(result)
+    }
+
+    fn memset(&mut self, offset: i64, count: u64) -> Result<()> {
+        let pointer = self.pointer(offset)?;
+        self.line(&format!(
+            "  call void @llvm.memset.p0.i64(ptr {}, i8 0, i64 {}, i1 false)",
+            pointer, count
+        ))
+    }
+
+    fn shift(&mut self, amount: i64) -> Result<()> {
+        let index = self.value();
+        let moved = self.value();
+        self.line(&format!("  {} = load i64, ptr %i", index))?;
+        self.line(&format!("  {} = add i64 {}, {}", moved, index, amount))?;
+        self.line(&format!("  store i64 {}, ptr %i", moved))
+    }
+
+    /// Makes the cell `pointer` points to the current cell
+    fn move_to(&mut self, pointer: &str) -> Result<()> {
+        let address = self.value();
+        let start = self.value();
+        let index = self.value();
+        self.line(&format!("  {} = ptrtoint ptr {} to i64", address, pointer))?;
+        self.line(&format!("  {} = ptrtoint ptr %m to i64", start))?;
+        self.line(&format!("  {} = sub i64 {}, {}", index, address, start))?;
+        self.line(&format!("  store i64 {}, ptr %i", index))
+    }
+
+    /// Moves by `stride` until the current cell is zero
+    fn scan(&mut self, stride: i64) -> Result<()> {
+        let label = self.label();
+        self.line(&format!("  br label %scan{}", label))?;
+        self.line(&format!("scan{}:", label))?;
+        let value = self.load(0)?;
+        let condition = self.value();
+        self.line(&format!("  {} = icmp ne i8 {}, 0", condition, value))?;
+        self.line(&format!(
+            "  br i1 {}, label %step{}, label %scanned{}",
+            condition, label, label
+        ))?;
+        self.line(&format!("step{}:", label))?;
+        self.shift(stride)?;
+        self.line(&format!("  br label %scan{}", label))?;
+        self.line(&format!("scanned{}:", label))
+    }
+
+    /// Stores whether `a <predicate> b` holds into the cell of `a`
+    fn compare(&mut self, predicate: &str, a_offset: i64, b_offset: i64) -> Result<()> {
+        let a = self.load(a_offset)?;
+        let b = self.load(b_offset)?;
+        let condition = self.value();
+        let result = self.value();
+        self.line(&format!("  {} = icmp {} i8 {}, {}", condition, predicate, a, b))?;
+        self.line(&format!("  {} = zext i1 {} to i8", result, condition))?;
+        self.store(a_offset, &result)
+    }
+
+    fn compare_and_clear(&mut self, predicate: &str) -> Result<()> {
+        self.compare(predicate, -2, -1)?;
+        self.memset(-1, 3)
+    }
+
+    fn logical_shift(&mut self, instruction: &str) -> Result<()> {
+        let a = self.load(-2)?;
+        let b = self.load(-1)?;
+        let too_far = self.value();
+        let amount = self.value();
+        let shifted = self.value();
+        let result = self.value();
+
+        // Shifting by the bit width or more is poison in LLVM
+        self.line(&format!("  {} = icmp uge i8 {}, 8", too_far, b))?;
+        self.line(&format!("  {} = select i1 {}, i8 0, i8 {}", amount, too_far, b))?;
+        self.line(&format!("  {} = {} i8 {}, {}", shifted, instruction, a, amount))?;
+        self.line(&format!("  {} = select i1 {}, i8 0, i8 {}", result, too_far, shifted))?;
+        self.store(-2, &result)
+    }
+}
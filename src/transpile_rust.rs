@@ -0,0 +1,574 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{EofBehavior, CELL_COUNT},
+    interpreter_op::InterpreterOp,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Emits a standalone Rust program
+///
+/// Tape accesses are indexed safely, reporting out of bounds accesses and exiting with status 1 like the
+/// other backends with `--bounds-checks`, unless `unchecked` is set, in which case they behave like the
+/// interpreter without `--bounds-checks`.
+pub fn transpile_rust<'a>(
+    ops: impl Iterator<Item = &'a InterpreterOp>,
+    output_filename: &str,
+    unchecked: bool,
+    eof_behavior: EofBehavior,
+) -> Result<()> {
+    let file = File::create(output_filename)?;
+    let mut f = Rust {
+        f: BufWriter::new(file),
+        depth: 1,
+    };
+
+    let eof_value = match eof_behavior {
+        EofBehavior::Zero => "0",
+        EofBehavior::Unchanged => "current",
+        EofBehavior::Max => "255",
+    };
+
+    f.raw("#![allow(dead_code, unreachable_code, unused_assignments, unused_mut, unused_variables)]")?;
+    f.raw("use std::io::{Read, Write};")?;
+    f.raw("")?;
+
+    let (get, get_mut) = if unchecked {
+        (
+            "unsafe { self.0.get_unchecked(index) }",
+            "unsafe { self.0.get_unchecked_mut(index) }",
+        )
+    } else {
+        (
+            "self.0.get(index).unwrap_or_else(|| out_of_bounds())",
+            "self.0.get_mut(index).unwrap_or_else(|| out_of_bounds())",
+        )
+    };
+
+    f.raw("fn out_of_bounds() -> ! {")?;
+    f.raw("    eprintln!(\"[error] Out of bounds memory access\");")?;
+    f.raw("    std::process::exit(1)")?;
+    f.raw("}")?;
+    f.raw("")?;
+    f.raw("struct Tape(Vec<u8>);")?;
+    f.raw("")?;
+    f.raw("impl<I: std::slice::SliceIndex<[u8]>> std::ops::Index<I> for Tape {")?;
+    f.raw("    type Output = I::Output;")?;
+    f.raw("    fn index(&self, index: I) -> &I::Output {")?;
+    f.raw(&format!("        {}", get))?;
+    f.raw("    }")?;
+    f.raw("}")?;
+    f.raw("")?;
+    f.raw("impl<I: std::slice::SliceIndex<[u8]>> std::ops::IndexMut<I> for Tape {")?;
+    f.raw("    fn index_mut(&mut self, index: I) -> &mut I::Output {")?;
+    f.raw(&format!("        {}", get_mut))?;
+    f.raw("    }")?;
+    f.raw("}")?;
+    f.raw("")?;
+
+    f.raw("fn get(input: &mut impl Read, current: u8) -> u8 {")?;
+    f.raw("    let mut byte = [0u8];")?;
+    f.raw("    match input.read(&mut byte) {")?;
+    f.raw("        Ok(1) => byte[0],")?;
+    f.raw(&format!("        _ => {},", eof_value))?;
+    f.raw("    }")?;
+    f.raw("}")?;
+    f.raw("")?;
+    f.raw("fn put(output: &mut impl Write, bytes: &[u8]) {")?;
+    f.raw("    let _ = output.write_all(bytes);")?;
+    f.raw("    let _ = output.flush();")?;
+    f.raw("}")?;
+    f.raw("")?;
+    f.raw("fn main() {")?;
+    f.raw(&format!("    let mut m = Tape(vec![0u8; {}]);", CELL_COUNT))?;
+    f.raw("    let mut i: usize = 0;")?;
+    f.raw("    let mut input = std::io::stdin().lock();")?;
+    f.raw("    let mut output = std::io::stdout().lock();")?;
+
+    for op in ops {
+        match op {
+            InterpreterOp::LoopStart(_) => {
+                f.line(&format!("while {} != 0 {{", f.cell(0)))?;
+                f.depth += 1;
+            }
+            InterpreterOp::IfStart(_) => {
+                f.line(&format!("if {} != 0 {{", f.cell(0)))?;
+                f.depth += 1;
+            }
+            InterpreterOp::LoopEnd(_) | InterpreterOp::IfEnd(_) => {
+                f.depth -= 1;
+                f.line("}")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::ChangeBy(amount))) => {
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(0), amount))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Shift(amount))) => {
+                f.shift(*amount)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Input(count))) => {
+                for _ in 0..*count {
+                    f.line(&format!("{0} = get(&mut input, {0});", f.cell(0)))?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Output(count))) => {
+                f.line(&format!("put(&mut output, &[{}; {}]);", f.cell(0), count))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::LoopStart | BasicOp::LoopEnd)) => {
+                return Err(anyhow!("[error] Cannot transpile unprocessed loop instruction"));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Panic(value)) => {
+                f.line(&format!("{} = {};", f.cell(0), value))?;
+                f.line("let _ = output.flush();")?;
+                f.line(&format!(
+                    "eprintln!(\"[PANIC] Program entered panic loop with error code {}\");",
+                    value
+                ))?;
+                f.line(&format!("std::process::exit({});", value))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Zero) => {
+                f.line(&format!("{} = 0;", f.cell(0)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroAdvance(amount)) => {
+                f.line(&format!("{}.fill(0);", f.range(0, *amount as i64)))?;
+                f.shift(*amount as i64)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroRetreat(amount)) => {
+                // The start is computed as `i + 1 - amount` so that it only underflows when out of bounds
+                f.line(&format!("{}.fill(0);", f.range(1 - *amount as i64, 1)))?;
+                f.shift(-(*amount as i64))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Set(value)) => {
+                f.line(&format!("{} = {};", f.cell(0), value))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Dupe(offset)) => {
+                f.line(&format!("{} = {};", f.cell(0), f.cell(*offset)))?;
+                f.line(&format!("{} = 0;", f.cell(1)))?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitAnd) => {
+                // Warning: Unsound
+
+                // a b ? ? ? ? ? ?
+                //               ^
+
+                f.line(&format!("{} &= {};", f.cell(-7), f.cell(-6)))?;
+                f.line(&format!("{} = 0;", f.cell(-6)))?;
+                f.shift(2)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::WellBehavedDivMod(shift_amount)) => {
+                f.line("{")?;
+                f.line(&format!("    let n = {};", f.cell(-2)))?;
+                f.line(&format!("    let d = {};", f.cell(-1)))?;
+                f.line("    let n_div_d = n.checked_div(d).unwrap_or(0);")?;
+                f.line("    let n_mod_d = n.checked_rem(d).unwrap_or(0);")?;
+                f.line(&format!("    {} = 0;", f.cell(-2)))?;
+                f.line(&format!("    {} = d.wrapping_sub(n_mod_d);", f.cell(-1)))?;
+                f.line(&format!("    {} = n_mod_d;", f.cell(0)))?;
+                f.line(&format!("    {} = n_div_d;", f.cell(1)))?;
+                f.line(&format!("    {} = 0;", f.cell(2)))?;
+                f.line(&format!("    {} = 0;", f.cell(3)))?;
+                f.line("}")?;
+                f.shift(*shift_amount)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitNeg) => {
+                f.line(&format!("{0} = !{0};", f.cell(0)))?;
+                f.line(&format!("{} = 0;", f.cell(1)))?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Equals) => {
+                f.line(&format!("{0} = ({0} == {1}) as u8;", f.cell(0), f.cell(1)))?;
+                f.line(&format!("{} = 0;", f.cell(1)))?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::NotEquals) => {
+                f.line(&format!("{0} = ({0} != {1}) as u8;", f.cell(0), f.cell(1)))?;
+                f.line(&format!("{} = 0;", f.cell(1)))?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftLeftLogical) => {
+                f.line(&format!(
+                    "{0} = {0}.checked_shl({1} as u32).unwrap_or(0);",
+                    f.cell(-2),
+                    f.cell(-1)
+                ))?;
+                f.line(&format!("{}.fill(0);", f.range(-1, 1)))?;
+                f.shift(-1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftRightLogical) => {
+                f.line(&format!(
+                    "{0} = {0}.checked_shr({1} as u32).unwrap_or(0);",
+                    f.cell(-2),
+                    f.cell(-1)
+                ))?;
+                f.line(&format!("{}.fill(0);", f.range(-1, 4)))?;
+                f.shift(-1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::LessThan) => f.compare_and_clear("<")?,
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThan) => f.compare_and_clear(">")?,
+            InterpreterOp::CompoundOp(CompoundOp::LessThanEqual) => f.compare_and_clear("<=")?,
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThanEqual) => f.compare_and_clear(">=")?,
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd(offset)) => {
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(*offset), f.cell(0)))?;
+                f.line(&format!("{} = 0;", f.cell(0)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSet(offset)) => {
+                f.line(&format!("{} = {};", f.cell(*offset), f.cell(0)))?;
+                f.line(&format!("{} = 0;", f.cell(0)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2(offset1, offset2)) => {
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(*offset1), f.cell(0)))?;
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(*offset2), f.cell(0)))?;
+                f.line(&format!("{} = 0;", f.cell(0)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::PrintStatic(content)) => {
+                let escaped: String = content
+                    .iter()
+                    .flat_map(|c| std::ascii::escape_default(*c))
+                    .map(char::from)
+                    .collect();
+                f.line(&format!("put(&mut output, b\"{}\");", escaped))?;
+                f.line(&format!("{} = {};", f.cell(0), content.last().unwrap()))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                f.line("{")?;
+                f.line(&format!("    let value = {};", f.cell(-2)))?;
+                f.line(&format!("    let index = {};", f.cell(-1)))?;
+                f.line(&format!(
+                    "    {} = value;",
+                    f.cell_at(&format!("i - {} + index as usize", 3 + offset))
+                ))?;
+                f.line(&format!("    {} = index;", f.cell(-2)))?;
+                f.line("}")?;
+                f.shift(-2)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU16(offset)) => {
+                // Warning: Unsound
+                f.line("{")?;
+                f.line(&format!("    let bytes = [{}, {}];", f.cell(-2), f.cell(-1)))?;
+                f.line(&format!("    let value = {};", f.cell(-3)))?;
+                f.line("    let index = u16::from_le_bytes(bytes) as usize;")?;
+                f.line(&format!(
+                    "    {} = value;",
+                    f.cell_at(&format!("i - {} + index", offset))
+                ))?;
+                f.line(&format!("    {}.copy_from_slice(&bytes);", f.range(-3, -1)))?;
+                f.line("}")?;
+                f.shift(-3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                f.line(&format!(
+                    "{0} = {1};",
+                    f.cell(-1),
+                    f.cell_at(&format!("i - {} + {} as usize", 1 + offset, f.cell(-1)))
+                ))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                f.line("{")?;
+                f.line(&format!(
+                    "    let bytes = [{}, {}, {}, {}];",
+                    f.cell(-4),
+                    f.cell(-3),
+                    f.cell(-2),
+                    f.cell(-1)
+                ))?;
+                f.line(&format!("    let value = {};", f.cell(-5)))?;
+                f.line("    let index = u32::from_le_bytes(bytes) as usize;")?;
+                f.line(&format!(
+                    "    {} = value;",
+                    f.cell_at(&format!("i - {} + index", offset))
+                ))?;
+                f.line(&format!("    {}.copy_from_slice(&bytes);", f.range(-5, -1)))?;
+                f.line("}")?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                f.line("{")?;
+                f.line(&format!(
+                    "    let bytes = [{}, {}, {}, {}];",
+                    f.cell(-4),
+                    f.cell(-3),
+                    f.cell(-2),
+                    f.cell(-1)
+                ))?;
+                f.line("    let index = u32::from_le_bytes(bytes) as usize;")?;
+                f.line(&format!(
+                    "    {} = {};",
+                    f.cell(-4),
+                    f.cell_at(&format!("i - {} + index", offset))
+                ))?;
+                f.line("}")?;
+                f.shift(-3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellsStaticReverse(offset, count)) => {
+                let count = *count as i64;
+                f.line(&format!(
+                    "m[..].copy_within({}..{}, {});",
+                    f.index(1 - count),
+                    f.index(1),
+                    f.index(*offset + 1 - count)
+                ))?;
+                f.line(&format!("{}.fill(0);", f.range(1 - count, 1)))?;
+                f.shift(-count)?;
+            }
+            InterpreterOp::CompoundOp(op @ CompoundOp::AddU32 | op @ CompoundOp::MulU32) => {
+                // Warning: Unsound
+                let function = match *op {
+                    CompoundOp::AddU32 => "wrapping_add",
+                    CompoundOp::MulU32 => "wrapping_mul",
+                    _ => unimplemented!(),
+                };
+
+                f.line("{")?;
+                f.line(&format!(
+                    "    let a = u32::from_le_bytes([{}, {}, {}, {}]);",
+                    f.cell(-8),
+                    f.cell(-7),
+                    f.cell(-6),
+                    f.cell(-5)
+                ))?;
+                f.line(&format!(
+                    "    let b = u32::from_le_bytes([{}, {}, {}, {}]);",
+                    f.cell(-4),
+                    f.cell(-3),
+                    f.cell(-2),
+                    f.cell(-1)
+                ))?;
+                f.line(&format!(
+                    "    {}.copy_from_slice(&a.{}(b).to_le_bytes());",
+                    f.range(-8, -4),
+                    function
+                ))?;
+                f.line("}")?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(
+                op @ (CompoundOp::SubU32
+                | CompoundOp::LessThanU32
+                | CompoundOp::EqualsU32
+                | CompoundOp::AndU32
+                | CompoundOp::OrU32
+                | CompoundOp::XorU32
+                | CompoundOp::ShiftLeftU32
+                | CompoundOp::ShiftRightU32
+                | CompoundOp::DivModU32),
+            ) => {
+                let (result, rest) = match op {
+                    CompoundOp::SubU32 => ("a.wrapping_sub(b)", "0"),
+                    CompoundOp::LessThanU32 => ("(a < b) as u32", "0"),
+                    CompoundOp::EqualsU32 => ("(a == b) as u32", "0"),
+                    CompoundOp::AndU32 => ("a & b", "0"),
+                    CompoundOp::OrU32 => ("a | b", "0"),
+                    CompoundOp::XorU32 => ("a ^ b", "0"),
+                    CompoundOp::ShiftLeftU32 => ("a.checked_shl(b).unwrap_or(0)", "0"),
+                    CompoundOp::ShiftRightU32 => ("a.checked_shr(b).unwrap_or(0)", "0"),
+                    _ => ("a.checked_div(b).unwrap_or(0)", "a.checked_rem(b).unwrap_or(0)"),
+                };
+
+                f.line("{")?;
+                f.line(&format!(
+                    "    let a = u32::from_le_bytes([{}, {}, {}, {}]);",
+                    f.cell(-8),
+                    f.cell(-7),
+                    f.cell(-6),
+                    f.cell(-5)
+                ))?;
+                f.line(&format!(
+                    "    let b = u32::from_le_bytes([{}, {}, {}, {}]);",
+                    f.cell(-4),
+                    f.cell(-3),
+                    f.cell(-2),
+                    f.cell(-1)
+                ))?;
+                f.line(&format!("    let rest: u32 = {};", rest))?;
+                f.line(&format!(
+                    "    {}.copy_from_slice(&({}).to_le_bytes());",
+                    f.range(-8, -4),
+                    result
+                ))?;
+                f.line(&format!("    {}.copy_from_slice(&rest.to_le_bytes());", f.range(-4, 0)))?;
+                f.line("}")?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddMany(targets)) => {
+                for (offset, factor) in targets {
+                    f.line(&format!(
+                        "{0} = {0}.wrapping_add({1}.wrapping_mul({2}));",
+                        f.cell(*offset),
+                        f.cell(0),
+                        factor
+                    ))?;
+                }
+
+                f.line(&format!("{} = 0;", f.cell(0)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(1)) => {
+                // A scan that finds no zero cell runs off the tape
+                f.line("i += m[i..].iter().position(|&cell| cell == 0).unwrap_or_else(|| out_of_bounds());")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(1)) => {
+                f.line("i = m[..=i].iter().rposition(|&cell| cell == 0).unwrap_or_else(|| out_of_bounds());")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(stride)) => {
+                f.line(&format!("while {} != 0 {{", f.cell(0)))?;
+                f.depth += 1;
+                f.shift(*stride as i64)?;
+                f.depth -= 1;
+                f.line("}")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(stride)) => {
+                f.line(&format!("while {} != 0 {{", f.cell(0)))?;
+                f.depth += 1;
+                f.shift(-(*stride as i64))?;
+                f.depth -= 1;
+                f.line("}")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetCells(offset, values)) => {
+                let escaped: String = values
+                    .iter()
+                    .flat_map(|c| std::ascii::escape_default(*c))
+                    .map(char::from)
+                    .collect();
+                f.line(&format!(
+                    "{}.copy_from_slice(b\"{}\");",
+                    f.range(*offset, *offset + values.len() as i64),
+                    escaped
+                ))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ChangeByAt(offset, amount)) => {
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(*offset), amount))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetAt(offset, value)) => {
+                f.line(&format!("{} = {};", f.cell(*offset), value))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::InputAt(offset, count)) => {
+                for _ in 0..*count {
+                    f.line(&format!("{0} = get(&mut input, {0});", f.cell(*offset)))?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::OutputAt(offset, count)) => {
+                f.line(&format!("put(&mut output, &[{}; {}]);", f.cell(*offset), count))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAddAt(from, to)) => {
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(*to), f.cell(*from)))?;
+                f.line(&format!("{} = 0;", f.cell(*from)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2At(from, to1, to2)) => {
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(*to1), f.cell(*from)))?;
+                f.line(&format!("{0} = {0}.wrapping_add({1});", f.cell(*to2), f.cell(*from)))?;
+                f.line(&format!("{} = 0;", f.cell(*from)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSetAt(from, to)) => {
+                f.line(&format!("{} = {};", f.cell(*to), f.cell(*from)))?;
+                f.line(&format!("{} = 0;", f.cell(*from)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddManyAt(from, targets)) => {
+                for (offset, factor) in targets {
+                    f.line(&format!(
+                        "{0} = {0}.wrapping_add({1}.wrapping_mul({2}));",
+                        f.cell(*offset),
+                        f.cell(*from),
+                        factor
+                    ))?;
+                }
+
+                f.line(&format!("{} = 0;", f.cell(*from)))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Affine(updates)) => {
+                f.line("{")?;
+
+                for (update_i, update) in updates.iter().enumerate() {
+                    let mut value = format!("{}u8", update.constant);
+
+                    for (offset, coefficient) in &update.terms {
+                        value = format!(
+                            "{}.wrapping_add({}.wrapping_mul({}))",
+                            value,
+                            f.cell(*offset),
+                            coefficient
+                        );
+                    }
+
+                    f.line(&format!("    let a{} = {};", update_i, value))?;
+                }
+
+                for (update_i, update) in updates.iter().enumerate() {
+                    f.line(&format!("    {} = a{};", f.cell(update.offset), update_i))?;
+                }
+
+                f.line("}")?;
+            }
+        }
+    }
+
+    f.raw("}")?;
+    Ok(())
+}
+
+struct Rust {
+    f: BufWriter<File>,
+    depth: usize,
+}
+
+impl Rust {
+    fn raw(&mut self, line: &str) -> Result<()> {
+        self.f.write_all(line.as_bytes())?;
+        self.f.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Writes a statement of the main function, indented by block depth
+    fn line(&mut self, line: &str) -> Result<()> {
+        let indent = 4 * self.depth;
+        self.f
+            .write_all(format!("{:indent$}{}\n", "", line, indent = indent).as_bytes())?;
+        Ok(())
+    }
+
+    /// Index expression of the cell at `offset` from the current cell
+    fn index(&self, offset: i64) -> String {
+        match offset {
+            0 => "i".to_string(),
+            offset if offset > 0 => format!("i + {}", offset),
+            offset => format!("i - {}", -offset),
+        }
+    }
+
+    fn cell(&self, offset: i64) -> String {
+        self.cell_at(&self.index(offset))
+    }
+
+    fn cell_at(&self, index: &str) -> String {
+        format!("m[{}]", index)
+    }
+
+    /// Cells from `start` up to but excluding `end`, both relative to the current cell
+    fn range(&self, start: i64, end: i64) -> String {
+        format!("m[{}..{}]", self.index(start), self.index(end))
+    }
+
+    fn shift(&mut self, amount: i64) -> Result<()> {
+        if amount >= 0 {
+            self.line(&format!("i = i.wrapping_add({});", amount))
+        } else {
+            self.line(&format!("i = i.wrapping_sub({});", -amount))
+        }
+    }
+
+    fn compare_and_clear(&mut self, operator: &str) -> Result<()> {
+        self.line(&format!(
+            "{0} = ({0} {1} {2}) as u8;",
+            self.cell(-2),
+            operator,
+            self.cell(-1)
+        ))?;
+        self.line(&format!("{}.fill(0);", self.range(-1, 2)))
+    }
+}
@@ -0,0 +1,656 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{EofBehavior, CELL_COUNT},
+    interpreter_op::InterpreterOp,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+const PAGE_SIZE: usize = 65536;
+
+/// Emits a self-contained WebAssembly text module
+///
+/// The host provides `env.get` returning the next input byte or -1 on EOF,
+/// `env.put` taking the byte to output and `env.panic` taking the code of a panic
+/// loop, which should report it and exit with that code like the other backends.
+/// The program itself is the exported `main`.
+pub fn transpile_wat<'a>(
+    ops: impl Iterator<Item = &'a InterpreterOp>,
+    output_filename: &str,
+    eof_behavior: EofBehavior,
+) -> Result<()> {
+    let file = File::create(output_filename)?;
+    let mut f = Wat {
+        f: BufWriter::new(file),
+        next_label: 0,
+        loops: Vec::new(),
+        data: Vec::new(),
+    };
+
+    let eof_value = match eof_behavior {
+        EofBehavior::Zero => "i32.const 0",
+        EofBehavior::Unchanged => "local.get $current",
+        EofBehavior::Max => "i32.const 255",
+    };
+
+    f.raw("(module")?;
+    f.raw("  (import \"env\" \"get\" (func $get (result i32)))")?;
+    f.raw("  (import \"env\" \"put\" (func $put (param i32)))")?;
+    f.raw("  (import \"env\" \"panic\" (func $panic (param i32)))")?;
+    f.raw(&format!(
+        "  (memory (export \"memory\") {})",
+        CELL_COUNT.div_ceil(PAGE_SIZE)
+    ))?;
+    f.raw("  (func $input (param $current i32) (result i32)")?;
+    f.raw("    (local $c i32)")?;
+    f.raw("    call $get")?;
+    f.raw("    local.tee $c")?;
+    f.raw("    i32.const 0")?;
+    f.raw("    i32.lt_s")?;
+    f.raw("    if (result i32)")?;
+    f.raw(&format!("      {}", eof_value))?;
+    f.raw("    else")?;
+    f.raw("      local.get $c")?;
+    f.raw("    end)")?;
+    f.raw("  (func (export \"main\")")?;
+    f.raw("    (local $i i32) (local $a i32) (local $b i32) (local $c i32)")?;
+
+    for op in ops {
+        match op {
+            InterpreterOp::LoopStart(_) => {
+                let label = f.next_label;
+                f.next_label += 1;
+                f.line(&format!("block $end{}", label))?;
+                f.line(&format!("loop $loop{}", label))?;
+                f.loops.push(label);
+                f.load(0)?;
+                f.line("i32.eqz")?;
+                f.line(&format!("br_if $end{}", label))?;
+            }
+            InterpreterOp::LoopEnd(_) => {
+                let label = f
+                    .loops
+                    .pop()
+                    .ok_or_else(|| anyhow!("[error] Instruction ']' is missing match"))?;
+                f.line(&format!("  br $loop{}", label))?;
+                f.line("end")?;
+                f.line("end")?;
+            }
+            InterpreterOp::IfStart(_) => {
+                let label = f.next_label;
+                f.next_label += 1;
+                f.load(0)?;
+                f.line(&format!("if $if{}", label))?;
+                f.loops.push(label);
+            }
+            InterpreterOp::IfEnd(_) => {
+                f.loops
+                    .pop()
+                    .ok_or_else(|| anyhow!("[error] Instruction ']' is missing match"))?;
+                f.line("end")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::ChangeBy(amount))) => {
+                f.address(0)?;
+                f.load(0)?;
+                f.line(&format!("i32.const {}", amount))?;
+                f.line("i32.add")?;
+                f.line("i32.store8")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Shift(amount))) => {
+                f.shift(*amount)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Input(count))) => {
+                for _ in 0..*count {
+                    f.address(0)?;
+                    f.load(0)?;
+                    f.line("call $input")?;
+                    f.line("i32.store8")?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Output(count))) => {
+                for _ in 0..*count {
+                    f.load(0)?;
+                    f.line("call $put")?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::LoopStart | BasicOp::LoopEnd)) => {
+                return Err(anyhow!("[error] Cannot transpile unprocessed loop instruction"));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Panic(value)) => {
+                f.store_const(0, *value)?;
+                f.line(&format!("i32.const {}", value))?;
+                f.line("call $panic")?;
+                f.line("unreachable")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Zero) => {
+                f.store_const(0, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroAdvance(amount)) => {
+                f.fill_zero(0, *amount)?;
+                f.shift(*amount as i64)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroRetreat(amount)) => {
+                f.fill_zero(1 - *amount as i64, *amount)?;
+                f.shift(-(*amount as i64))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Set(value)) => {
+                f.store_const(0, *value)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Dupe(offset)) => {
+                f.address(0)?;
+                f.load(*offset)?;
+                f.line("i32.store8")?;
+                f.store_const(1, 0)?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitAnd) => {
+                // Warning: Unsound
+
+                // a b ? ? ? ? ? ?
+                //               ^
+
+                f.address(-7)?;
+                f.load(-7)?;
+                f.load(-6)?;
+                f.line("i32.and")?;
+                f.line("i32.store8")?;
+                f.store_const(-6, 0)?;
+                f.shift(2)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::WellBehavedDivMod(shift_amount)) => {
+                // $a = n, $b = d, $c = d or 1 when d is zero so that dividing never traps
+                f.load(-2)?;
+                f.line("local.set $a")?;
+                f.load(-1)?;
+                f.line("local.tee $b")?;
+                f.line("i32.const 1")?;
+                f.line("local.get $b")?;
+                f.line("select")?;
+                f.line("local.set $c")?;
+
+                f.address(1)?;
+                f.line("local.get $a")?;
+                f.line("local.get $c")?;
+                f.line("i32.div_u")?;
+                f.line("i32.const 0")?;
+                f.line("local.get $b")?;
+                f.line("select")?;
+                f.line("i32.store8")?;
+
+                f.line("local.get $a")?;
+                f.line("local.get $c")?;
+                f.line("i32.rem_u")?;
+                f.line("i32.const 0")?;
+                f.line("local.get $b")?;
+                f.line("select")?;
+                f.line("local.set $c")?;
+
+                f.address(0)?;
+                f.line("local.get $c")?;
+                f.line("i32.store8")?;
+                f.address(-1)?;
+                f.line("local.get $b")?;
+                f.line("local.get $c")?;
+                f.line("i32.sub")?;
+                f.line("i32.store8")?;
+                f.store_const(-2, 0)?;
+                f.store_const(2, 0)?;
+                f.store_const(3, 0)?;
+                f.shift(*shift_amount)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitNeg) => {
+                f.address(0)?;
+                f.load(0)?;
+                f.line("i32.const -1")?;
+                f.line("i32.xor")?;
+                f.line("i32.store8")?;
+                f.store_const(1, 0)?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Equals) => {
+                f.compare("i32.eq", 0, 1)?;
+                f.store_const(1, 0)?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::NotEquals) => {
+                f.compare("i32.ne", 0, 1)?;
+                f.store_const(1, 0)?;
+                f.shift(1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftLeftLogical) => {
+                f.logical_shift("i32.shl")?;
+                f.store_const(-1, 0)?;
+                f.store_const(0, 0)?;
+                f.shift(-1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftRightLogical) => {
+                f.logical_shift("i32.shr_u")?;
+                f.fill_zero(-1, 5)?;
+                f.shift(-1)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::LessThan) => {
+                f.compare("i32.lt_u", -2, -1)?;
+                f.fill_zero(-1, 3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThan) => {
+                f.compare("i32.gt_u", -2, -1)?;
+                f.fill_zero(-1, 3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::LessThanEqual) => {
+                f.compare("i32.le_u", -2, -1)?;
+                f.fill_zero(-1, 3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThanEqual) => {
+                f.compare("i32.ge_u", -2, -1)?;
+                f.fill_zero(-1, 3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd(offset)) => {
+                f.add_cell_to(0, *offset)?;
+                f.store_const(0, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSet(offset)) => {
+                f.address(*offset)?;
+                f.load(0)?;
+                f.line("i32.store8")?;
+                f.store_const(0, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2(offset1, offset2)) => {
+                f.add_cell_to(0, *offset1)?;
+                f.add_cell_to(0, *offset2)?;
+                f.store_const(0, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::PrintStatic(content)) => {
+                for c in content {
+                    f.line(&format!("i32.const {}", c))?;
+                    f.line("call $put")?;
+                }
+                f.store_const(0, *content.last().unwrap())?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                f.load(-1)?;
+                f.line("local.set $b")?;
+                f.address(-3 - *offset as i64)?;
+                f.line("local.get $b")?;
+                f.line("i32.add")?;
+                f.load(-2)?;
+                f.line("i32.store8")?;
+                f.address(-2)?;
+                f.line("local.get $b")?;
+                f.line("i32.store8")?;
+                f.shift(-2)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU16(offset)) => {
+                // Warning: Unsound
+                f.address(-2)?;
+                f.line("i32.load16_u align=1")?;
+                f.line("local.set $b")?;
+                f.address(-(*offset as i64))?;
+                f.line("local.get $b")?;
+                f.line("i32.add")?;
+                f.load(-3)?;
+                f.line("i32.store8")?;
+                f.address(-3)?;
+                f.line("local.get $b")?;
+                f.line("i32.store16 align=1")?;
+                f.shift(-3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                f.address(-1)?;
+                f.address(-1 - *offset as i64)?;
+                f.load(-1)?;
+                f.line("i32.add")?;
+                f.line("i32.load8_u")?;
+                f.line("i32.store8")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                f.address(-4)?;
+                f.line("i32.load align=1")?;
+                f.line("local.set $b")?;
+                f.address(-(*offset as i64))?;
+                f.line("local.get $b")?;
+                f.line("i32.add")?;
+                f.load(-5)?;
+                f.line("i32.store8")?;
+                f.address(-5)?;
+                f.line("local.get $b")?;
+                f.line("i32.store align=1")?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                f.address(-4)?;
+                f.address(-(*offset as i64))?;
+                f.address(-4)?;
+                f.line("i32.load align=1")?;
+                f.line("i32.add")?;
+                f.line("i32.load8_u")?;
+                f.line("i32.store8")?;
+                f.shift(-3)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellsStaticReverse(offset, count)) => {
+                let count = *count as i64;
+                f.address(*offset + 1 - count)?;
+                f.address(1 - count)?;
+                f.line(&format!("i32.const {}", count))?;
+                f.line("memory.copy")?;
+                f.fill_zero(1 - count, count as u64)?;
+                f.shift(-count)?;
+            }
+            InterpreterOp::CompoundOp(op @ CompoundOp::AddU32 | op @ CompoundOp::MulU32) => {
+                // Warning: Unsound
+                f.address(-8)?;
+                f.address(-8)?;
+                f.line("i32.load align=1")?;
+                f.address(-4)?;
+                f.line("i32.load align=1")?;
+
+                match *op {
+                    CompoundOp::AddU32 => f.line("i32.add")?,
+                    CompoundOp::MulU32 => f.line("i32.mul")?,
+                    _ => unimplemented!(),
+                };
+
+                f.line("i32.store align=1")?;
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(
+                op @ (CompoundOp::SubU32
+                | CompoundOp::LessThanU32
+                | CompoundOp::EqualsU32
+                | CompoundOp::AndU32
+                | CompoundOp::OrU32
+                | CompoundOp::XorU32
+                | CompoundOp::ShiftLeftU32
+                | CompoundOp::ShiftRightU32
+                | CompoundOp::DivModU32),
+            ) => {
+                f.address(-8)?;
+                f.line("i32.load align=1")?;
+                f.line("local.set $a")?;
+                f.address(-4)?;
+                f.line("i32.load align=1")?;
+                f.line("local.set $b")?;
+
+                match op {
+                    CompoundOp::DivModU32 => {
+                        // $c = the divisor or 1 when it's zero so that dividing never traps
+                        f.line("local.get $b")?;
+                        f.line("i32.const 1")?;
+                        f.line("local.get $b")?;
+                        f.line("select")?;
+                        f.line("local.set $c")?;
+
+                        for (offset, instruction) in [(-8, "i32.div_u"), (-4, "i32.rem_u")] {
+                            f.address(offset)?;
+                            f.line("local.get $a")?;
+                            f.line("local.get $c")?;
+                            f.line(instruction)?;
+                            f.line("i32.const 0")?;
+                            f.line("local.get $b")?;
+                            f.line("select")?;
+                            f.line("i32.store align=1")?;
+                        }
+                    }
+                    _ => {
+                        f.address(-8)?;
+                        f.line("local.get $a")?;
+                        f.line("local.get $b")?;
+
+                        match op {
+                            CompoundOp::SubU32 => f.line("i32.sub")?,
+                            CompoundOp::LessThanU32 => f.line("i32.lt_u")?,
+                            CompoundOp::EqualsU32 => f.line("i32.eq")?,
+                            CompoundOp::AndU32 => f.line("i32.and")?,
+                            CompoundOp::OrU32 => f.line("i32.or")?,
+                            CompoundOp::XorU32 => f.line("i32.xor")?,
+                            _ => {
+                                // Shift amounts wrap around at the width, so larger ones are picked out first
+                                match op {
+                                    CompoundOp::ShiftLeftU32 => f.line("i32.shl")?,
+                                    _ => f.line("i32.shr_u")?,
+                                }
+
+                                f.line("i32.const 0")?;
+                                f.line("local.get $b")?;
+                                f.line("i32.const 32")?;
+                                f.line("i32.lt_u")?;
+                                f.line("select")?;
+                            }
+                        }
+
+                        f.line("i32.store align=1")?;
+                        f.address(-4)?;
+                        f.line("i32.const 0")?;
+                        f.line("i32.store align=1")?;
+                    }
+                }
+
+                f.shift(-5)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddMany(targets)) => {
+                for (offset, factor) in targets {
+                    f.address(*offset)?;
+                    f.load(*offset)?;
+                    f.load(0)?;
+                    f.line(&format!("i32.const {}", factor))?;
+                    f.line("i32.mul")?;
+                    f.line("i32.add")?;
+                    f.line("i32.store8")?;
+                }
+
+                f.store_const(0, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(stride)) => {
+                f.scan(*stride as i64)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(stride)) => {
+                f.scan(-(*stride as i64))?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetCells(offset, values)) => {
+                f.address(*offset)?;
+                f.line("i32.const 0")?;
+                f.line(&format!("i32.const {}", values.len()))?;
+                f.line(&format!("memory.init $data{}", f.data.len()))?;
+                f.data.push(values.clone());
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ChangeByAt(offset, amount)) => {
+                f.address(*offset)?;
+                f.load(*offset)?;
+                f.line(&format!("i32.const {}", amount))?;
+                f.line("i32.add")?;
+                f.line("i32.store8")?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetAt(offset, value)) => {
+                f.store_const(*offset, *value)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::InputAt(offset, count)) => {
+                for _ in 0..*count {
+                    f.address(*offset)?;
+                    f.load(*offset)?;
+                    f.line("call $input")?;
+                    f.line("i32.store8")?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::OutputAt(offset, count)) => {
+                for _ in 0..*count {
+                    f.load(*offset)?;
+                    f.line("call $put")?;
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAddAt(from, to)) => {
+                f.add_cell_to(*from, *to)?;
+                f.store_const(*from, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2At(from, to1, to2)) => {
+                f.add_cell_to(*from, *to1)?;
+                f.add_cell_to(*from, *to2)?;
+                f.store_const(*from, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSetAt(from, to)) => {
+                f.address(*to)?;
+                f.load(*from)?;
+                f.line("i32.store8")?;
+                f.store_const(*from, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddManyAt(from, targets)) => {
+                for (offset, factor) in targets {
+                    f.address(*offset)?;
+                    f.load(*offset)?;
+                    f.load(*from)?;
+                    f.line(&format!("i32.const {}", factor))?;
+                    f.line("i32.mul")?;
+                    f.line("i32.add")?;
+                    f.line("i32.store8")?;
+                }
+
+                f.store_const(*from, 0)?;
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Affine(updates)) => {
+                // Every value is left on the stack before any is stored, then they're stored last first
+                for update in updates {
+                    f.line(&format!("i32.const {}", update.constant))?;
+
+                    for (offset, coefficient) in &update.terms {
+                        f.load(*offset)?;
+                        f.line(&format!("i32.const {}", coefficient))?;
+                        f.line("i32.mul")?;
+                        f.line("i32.add")?;
+                    }
+                }
+
+                for update in updates.iter().rev() {
+                    f.line("local.set $a")?;
+                    f.address(update.offset)?;
+                    f.line("local.get $a")?;
+                    f.line("i32.store8")?;
+                }
+            }
+        }
+    }
+
+    f.raw("  )")?;
+
+    for (data_i, values) in std::mem::take(&mut f.data).into_iter().enumerate() {
+        let escaped: String = values.iter().map(|value| format!("\\{:02x}", value)).collect();
+        f.raw(&format!("  (data $data{} \"{}\")", data_i, escaped))?;
+    }
+
+    f.raw(")")?;
+    Ok(())
+}
+
+struct Wat {
+    f: BufWriter<File>,
+    next_label: usize,
+    loops: Vec<usize>,
+    /// Passive data segments copied onto the tape by `SetCells`
+    data: Vec<Vec<u8>>,
+}
+
+impl Wat {
+    fn raw(&mut self, line: &str) -> Result<()> {
+        self.f.write_all(line.as_bytes())?;
+        self.f.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Writes an instruction of the main function, indented by loop depth
+    fn line(&mut self, line: &str) -> Result<()> {
+        let indent = 4 + 2 * self.loops.len();
+        self.f
+            .write_all(format!("{:indent$}{}\n", "", line, indent = indent).as_bytes())?;
+        Ok(())
+    }
+
+    /// Pushes the address of the cell at `offset` from the current cell
+    fn address(&mut self, offset: i64) -> Result<()> {
+        self.line("local.get $i")?;
+
+        if offset != 0 {
+            self.line(&format!("i32.const {}", offset))?;
+            self.line("i32.add")?;
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, offset: i64) -> Result<()> {
+        self.address(offset)?;
+        self.line("i32.load8_u")
+    }
+
+    fn store_const(&mut self, offset: i64, value: u8) -> Result<()> {
+        self.address(offset)?;
+        self.line(&format!("i32.const {}", value))?;
+        self.line("i32.store8")
+    }
+
+    fn fill_zero(&mut self, offset: i64, count: u64) -> Result<()> {
+        self.address(offset)?;
+        self.line("i32.const 0")?;
+        self.line(&format!("i32.const {}", count))?;
+        self.line("memory.fill")
+    }
+
+    fn shift(&mut self, amount: i64) -> Result<()> {
+        self.line("local.get $i")?;
+        self.line(&format!("i32.const {}", amount))?;
+        self.line("i32.add")?;
+        self.line("local.set $i")
+    }
+
+    fn add_cell_to(&mut self, from: i64, to: i64) -> Result<()> {
+        self.address(to)?;
+        self.load(to)?;
+        self.load(from)?;
+        self.line("i32.add")?;
+        self.line("i32.store8")
+    }
+
+    /// Moves by `stride` until the current cell is zero
+    fn scan(&mut self, stride: i64) -> Result<()> {
+        let label = self.next_label;
+        self.next_label += 1;
+        self.line(&format!("block $scanned{}", label))?;
+        self.line(&format!("loop $scan{}", label))?;
+        self.load(0)?;
+        self.line("i32.eqz")?;
+        self.line(&format!("br_if $scanned{}", label))?;
+        self.shift(stride)?;
+        self.line(&format!("br $scan{}", label))?;
+        self.line("end")?;
+        self.line("end")
+    }
+
+    /// Stores whether `a <comparison> b` holds into the cell of `a`
+    fn compare(&mut self, comparison: &str, a_offset: i64, b_offset: i64) -> Result<()> {
+        self.address(a_offset)?;
+        self.load(a_offset)?;
+        self.load(b_offset)?;
+        self.line(comparison)?;
+        self.line("i32.store8")
+    }
+
+    fn logical_shift(&mut self, instruction: &str) -> Result<()> {
+        // Shift amounts are taken modulo 32, so anything of 8 or more is zeroed explicitly
+        self.load(-1)?;
+        self.line("local.set $b")?;
+        self.address(-2)?;
+        self.load(-2)?;
+        self.line("local.get $b")?;
+        self.line(instruction)?;
+        self.line("i32.const 0")?;
+        self.line("local.get $b")?;
+        self.line("i32.const 8")?;
+        self.line("i32.lt_u")?;
+        self.line("select")?;
+        self.line("i32.store8")
+    }
+}
@@ -0,0 +1,462 @@
+use std::{collections::HashMap, fmt};
+
+// Declared in encoding order, so every register must be listed even if unused
+#[allow(dead_code)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reg {
+    Rax,
+    Rcx,
+    Rdx,
+    Rbx,
+    Rsp,
+    Rbp,
+    Rsi,
+    Rdi,
+    R8,
+    R9,
+    R10,
+    R11,
+    R12,
+    R13,
+    R14,
+    R15,
+}
+
+impl Reg {
+    fn number(self) -> u8 {
+        self as u8
+    }
+
+    fn low(self) -> u8 {
+        self.number() & 0b111
+    }
+
+    fn name64(self) -> &'static str {
+        [
+            "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12", "r13", "r14",
+            "r15",
+        ][self as usize]
+    }
+
+    fn name32(self) -> &'static str {
+        [
+            "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi", "r8d", "r9d", "r10d", "r11d", "r12d", "r13d",
+            "r14d", "r15d",
+        ][self as usize]
+    }
+
+    fn name8(self) -> &'static str {
+        [
+            "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil", "r8b", "r9b", "r10b", "r11b", "r12b", "r13b", "r14b",
+            "r15b",
+        ][self as usize]
+    }
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name64())
+    }
+}
+
+/// Memory operand of the form `[base + index + disp]`
+#[derive(Copy, Clone, Debug)]
+pub struct Mem {
+    pub base: Reg,
+    pub index: Option<Reg>,
+    pub disp: i32,
+}
+
+impl Mem {
+    pub fn new(base: Reg, index: Option<Reg>, disp: i32) -> Self {
+        assert!(index != Some(Reg::Rsp), "rsp cannot be used as an index register");
+        Self { base, index, disp }
+    }
+}
+
+impl fmt::Display for Mem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}", self.base)?;
+
+        if let Some(index) = self.index {
+            write!(f, " + {}", index)?;
+        }
+
+        match self.disp {
+            0 => write!(f, "]"),
+            disp if disp > 0 => write!(f, " + {}]", disp),
+            disp => write!(f, " - {}]", -(disp as i64)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Label(pub usize);
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ".L{}", self.0)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Cond {
+    Below = 0x2,
+    AboveEqual = 0x3,
+    Equal = 0x4,
+    NotEqual = 0x5,
+    BelowEqual = 0x6,
+    Above = 0x7,
+    LessEqual = 0xE,
+}
+
+impl Cond {
+    fn suffix(self) -> &'static str {
+        match self {
+            Cond::Below => "b",
+            Cond::AboveEqual => "ae",
+            Cond::Equal => "e",
+            Cond::NotEqual => "ne",
+            Cond::BelowEqual => "be",
+            Cond::Above => "a",
+            Cond::LessEqual => "le",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum AluOp {
+    Add = 0,
+    Or = 1,
+    And = 4,
+    Sub = 5,
+    Xor = 6,
+    Cmp = 7,
+}
+
+impl AluOp {
+    fn mnemonic(self) -> &'static str {
+        match self {
+            AluOp::Add => "add",
+            AluOp::Or => "or",
+            AluOp::And => "and",
+            AluOp::Sub => "sub",
+            AluOp::Xor => "xor",
+            AluOp::Cmp => "cmp",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum ShiftOp {
+    Shl = 4,
+    Shr = 5,
+}
+
+impl ShiftOp {
+    fn mnemonic(self) -> &'static str {
+        match self {
+            ShiftOp::Shl => "shl",
+            ShiftOp::Shr => "shr",
+        }
+    }
+}
+
+/// Subset of x86-64 instructions used by the code generators
+///
+/// Register operands are 64-bit unless the variant says otherwise,
+/// byte variants operate on the low 8 bits of the given register.
+#[derive(Clone, Debug)]
+pub enum Inst {
+    Label(Label),
+    Data(Vec<u8>),
+    MovImm(Reg, i64),
+    Mov(Reg, Reg),
+    Store64(Mem, Reg),
+    Load32(Reg, Mem),
+    Store32(Mem, Reg),
+    LoadByte(Reg, Mem),
+    StoreByte(Mem, Reg),
+    StoreByteImm(Mem, u8),
+    AddByteImm(Mem, u8),
+    AddByte(Mem, Reg),
+    CmpByteImm(Mem, u8),
+    Lea(Reg, Mem),
+    LeaLabel(Reg, Label),
+    Alu(AluOp, Reg, Reg),
+    AluImm(AluOp, Reg, i32),
+    Shift(ShiftOp, Reg, u8),
+    ShiftCl(ShiftOp, Reg),
+    Imul(Reg, Reg),
+    Not(Reg),
+    Div32(Reg),
+    SetCond(Cond, Reg),
+    Test(Reg, Reg),
+    Jump(Label),
+    JumpIf(Cond, Label),
+    Call(Label),
+    Ret,
+    Push(Reg),
+    Pop(Reg),
+    Syscall,
+    RepStosb,
+    RepMovsb,
+    RepneScasb,
+    Std,
+    Cld,
+}
+
+/// Formats the instruction as GNU assembler source in Intel syntax
+impl fmt::Display for Inst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Inst::*;
+
+        match self {
+            Inst::Label(label) => write!(f, "{}:", label),
+            Data(bytes) => {
+                let lines: Vec<String> = bytes
+                    .chunks(16)
+                    .map(|chunk| {
+                        let values: Vec<String> = chunk.iter().map(|byte| byte.to_string()).collect();
+                        format!("    .byte {}", values.join(", "))
+                    })
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+            MovImm(reg, value) if i32::try_from(*value).is_ok() => write!(f, "    mov {}, {}", reg, value),
+            MovImm(reg, value) => write!(f, "    movabs {}, {}", reg, value),
+            Mov(dest, src) => write!(f, "    mov {}, {}", dest, src),
+            Store64(mem, reg) => write!(f, "    mov qword ptr {}, {}", mem, reg),
+            Load32(reg, mem) => write!(f, "    mov {}, dword ptr {}", reg.name32(), mem),
+            Store32(mem, reg) => write!(f, "    mov dword ptr {}, {}", mem, reg.name32()),
+            LoadByte(reg, mem) => write!(f, "    movzx {}, byte ptr {}", reg.name32(), mem),
+            StoreByte(mem, reg) => write!(f, "    mov byte ptr {}, {}", mem, reg.name8()),
+            StoreByteImm(mem, value) => write!(f, "    mov byte ptr {}, {}", mem, value),
+            AddByteImm(mem, value) => write!(f, "    add byte ptr {}, {}", mem, value),
+            AddByte(mem, reg) => write!(f, "    add byte ptr {}, {}", mem, reg.name8()),
+            CmpByteImm(mem, value) => write!(f, "    cmp byte ptr {}, {}", mem, value),
+            Lea(reg, mem) => write!(f, "    lea {}, {}", reg, mem),
+            LeaLabel(reg, label) => write!(f, "    lea {}, [rip + {}]", reg, label),
+            Alu(op, dest, src) => write!(f, "    {} {}, {}", op.mnemonic(), dest, src),
+            AluImm(op, reg, value) => write!(f, "    {} {}, {}", op.mnemonic(), reg, value),
+            Shift(op, reg, amount) => write!(f, "    {} {}, {}", op.mnemonic(), reg, amount),
+            ShiftCl(op, reg) => write!(f, "    {} {}, cl", op.mnemonic(), reg),
+            Imul(dest, src) => write!(f, "    imul {}, {}", dest, src),
+            Not(reg) => write!(f, "    not {}", reg),
+            Div32(reg) => write!(f, "    div {}", reg.name32()),
+            SetCond(cond, reg) => write!(f, "    set{} {}", cond.suffix(), reg.name8()),
+            Test(a, b) => write!(f, "    test {}, {}", a, b),
+            Jump(label) => write!(f, "    jmp {}", label),
+            JumpIf(cond, label) => write!(f, "    j{} {}", cond.suffix(), label),
+            Call(label) => write!(f, "    call {}", label),
+            Ret => write!(f, "    ret"),
+            Push(reg) => write!(f, "    push {}", reg),
+            Pop(reg) => write!(f, "    pop {}", reg),
+            Syscall => write!(f, "    syscall"),
+            RepStosb => write!(f, "    rep stosb"),
+            RepMovsb => write!(f, "    rep movsb"),
+            RepneScasb => write!(f, "    repne scasb"),
+            Std => write!(f, "    std"),
+            Cld => write!(f, "    cld"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Assembler {
+    insts: Vec<Inst>,
+    next_label: usize,
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_label(&mut self) -> Label {
+        let label = Label(self.next_label);
+        self.next_label += 1;
+        label
+    }
+
+    pub fn push(&mut self, inst: Inst) {
+        self.insts.push(inst);
+    }
+
+    pub fn insts(&self) -> &[Inst] {
+        &self.insts
+    }
+
+    /// Encodes the instructions into position independent machine code
+    pub fn encode(&self) -> Vec<u8> {
+        // Every instruction has a fixed size regardless of label positions,
+        // so one pass to place labels and one pass to emit is enough
+        let mut labels = HashMap::new();
+        let mut offset = 0;
+
+        for inst in &self.insts {
+            if let Inst::Label(label) = inst {
+                labels.insert(*label, offset);
+            }
+            offset += encode_inst(inst, offset, &HashMap::new()).len();
+        }
+
+        let mut code = Vec::with_capacity(offset);
+
+        for inst in &self.insts {
+            let bytes = encode_inst(inst, code.len(), &labels);
+            code.extend_from_slice(&bytes);
+        }
+
+        code
+    }
+}
+
+fn rex(w: bool, reg: u8, index: u8, base: u8) -> u8 {
+    0x40 | (w as u8) << 3 | (reg >> 3) << 2 | (index >> 3) << 1 | (base >> 3)
+}
+
+fn modrm_reg(reg: u8, rm: Reg) -> u8 {
+    0b11 << 6 | (reg & 0b111) << 3 | rm.low()
+}
+
+/// Encodes the ModRM, SIB and displacement bytes for a memory operand
+fn modrm_mem(reg: u8, mem: &Mem) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(6);
+    let (mode, disp) = match i8::try_from(mem.disp) {
+        Ok(disp) => (0b01, vec![disp as u8]),
+        Err(_) => (0b10, mem.disp.to_le_bytes().to_vec()),
+    };
+
+    match mem.index {
+        Some(index) => {
+            bytes.push(mode << 6 | (reg & 0b111) << 3 | 0b100);
+            bytes.push(index.low() << 3 | mem.base.low());
+        }
+        None if mem.base.low() == 0b100 => {
+            bytes.push(mode << 6 | (reg & 0b111) << 3 | 0b100);
+            bytes.push(0b100 << 3 | mem.base.low());
+        }
+        None => {
+            bytes.push(mode << 6 | (reg & 0b111) << 3 | mem.base.low());
+        }
+    }
+
+    bytes.extend(disp);
+    bytes
+}
+
+fn mem_inst(w: bool, prefix: &[u8], opcode: &[u8], reg: u8, mem: &Mem) -> Vec<u8> {
+    let mut bytes = prefix.to_vec();
+    let index = mem.index.map(|index| index.number()).unwrap_or(0);
+    bytes.push(rex(w, reg, index, mem.base.number()));
+    bytes.extend_from_slice(opcode);
+    bytes.extend(modrm_mem(reg, mem));
+    bytes
+}
+
+fn reg_inst(w: bool, opcode: &[u8], reg: u8, rm: Reg) -> Vec<u8> {
+    let mut bytes = vec![rex(w, reg, 0, rm.number())];
+    bytes.extend_from_slice(opcode);
+    bytes.push(modrm_reg(reg, rm));
+    bytes
+}
+
+fn relative(labels: &HashMap<Label, usize>, label: &Label, end: usize) -> [u8; 4] {
+    let target = labels.get(label).copied().unwrap_or(end) as i64;
+    ((target - end as i64) as i32).to_le_bytes()
+}
+
+fn encode_inst(inst: &Inst, offset: usize, labels: &HashMap<Label, usize>) -> Vec<u8> {
+    use Inst::*;
+
+    match inst {
+        Inst::Label(_) => vec![],
+        Data(bytes) => bytes.clone(),
+        MovImm(reg, value) => match i32::try_from(*value) {
+            Ok(value) => {
+                let mut bytes = reg_inst(true, &[0xC7], 0, *reg);
+                bytes.extend(value.to_le_bytes());
+                bytes
+            }
+            Err(_) => {
+                let mut bytes = vec![rex(true, 0, 0, reg.number()), 0xB8 + reg.low()];
+                bytes.extend(value.to_le_bytes());
+                bytes
+            }
+        },
+        Mov(dest, src) => reg_inst(true, &[0x89], src.number(), *dest),
+        Store64(mem, reg) => mem_inst(true, &[], &[0x89], reg.number(), mem),
+        Load32(reg, mem) => mem_inst(false, &[], &[0x8B], reg.number(), mem),
+        Store32(mem, reg) => mem_inst(false, &[], &[0x89], reg.number(), mem),
+        LoadByte(reg, mem) => mem_inst(false, &[], &[0x0F, 0xB6], reg.number(), mem),
+        StoreByte(mem, reg) => mem_inst(false, &[], &[0x88], reg.number(), mem),
+        StoreByteImm(mem, value) => {
+            let mut bytes = mem_inst(false, &[], &[0xC6], 0, mem);
+            bytes.push(*value);
+            bytes
+        }
+        AddByteImm(mem, value) => {
+            let mut bytes = mem_inst(false, &[], &[0x80], 0, mem);
+            bytes.push(*value);
+            bytes
+        }
+        AddByte(mem, reg) => mem_inst(false, &[], &[0x00], reg.number(), mem),
+        CmpByteImm(mem, value) => {
+            let mut bytes = mem_inst(false, &[], &[0x80], 7, mem);
+            bytes.push(*value);
+            bytes
+        }
+        Lea(reg, mem) => mem_inst(true, &[], &[0x8D], reg.number(), mem),
+        LeaLabel(reg, label) => {
+            let mut bytes = vec![rex(true, reg.number(), 0, 0), 0x8D, reg.low() << 3 | 0b101];
+            bytes.extend(relative(labels, label, offset + 7));
+            bytes
+        }
+        Alu(op, dest, src) => reg_inst(true, &[(*op as u8) << 3 | 0x01], src.number(), *dest),
+        AluImm(op, reg, value) => {
+            let mut bytes = reg_inst(true, &[0x81], *op as u8, *reg);
+            bytes.extend(value.to_le_bytes());
+            bytes
+        }
+        Shift(op, reg, amount) => {
+            let mut bytes = reg_inst(true, &[0xC1], *op as u8, *reg);
+            bytes.push(*amount);
+            bytes
+        }
+        ShiftCl(op, reg) => reg_inst(true, &[0xD3], *op as u8, *reg),
+        Imul(dest, src) => {
+            let mut bytes = vec![rex(true, dest.number(), 0, src.number()), 0x0F, 0xAF];
+            bytes.push(modrm_reg(dest.number(), *src));
+            bytes
+        }
+        Not(reg) => reg_inst(true, &[0xF7], 2, *reg),
+        Div32(reg) => reg_inst(false, &[0xF7], 6, *reg),
+        SetCond(cond, reg) => reg_inst(false, &[0x0F, 0x90 + *cond as u8], 0, *reg),
+        Test(a, b) => reg_inst(true, &[0x85], b.number(), *a),
+        Jump(label) => {
+            let mut bytes = vec![0xE9];
+            bytes.extend(relative(labels, label, offset + 5));
+            bytes
+        }
+        JumpIf(cond, label) => {
+            let mut bytes = vec![0x0F, 0x80 + *cond as u8];
+            bytes.extend(relative(labels, label, offset + 6));
+            bytes
+        }
+        Call(label) => {
+            let mut bytes = vec![0xE8];
+            bytes.extend(relative(labels, label, offset + 5));
+            bytes
+        }
+        Ret => vec![0xC3],
+        Push(reg) => vec![rex(false, 0, 0, reg.number()), 0x50 + reg.low()],
+        Pop(reg) => vec![rex(false, 0, 0, reg.number()), 0x58 + reg.low()],
+        Syscall => vec![0x0F, 0x05],
+        RepStosb => vec![0xF3, 0xAA],
+        RepMovsb => vec![0xF3, 0xA4],
+        RepneScasb => vec![0xF2, 0xAE],
+        Std => vec![0xFD],
+        Cld => vec![0xFC],
+    }
+}
@@ -0,0 +1,890 @@
+use crate::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{EofBehavior, CELL_COUNT},
+    interpreter_op::InterpreterOp,
+    x86_64::{AluOp, Assembler, Cond, Inst, Label, Mem, Reg, ShiftOp},
+};
+use anyhow::{anyhow, Result};
+use std::collections::BTreeSet;
+
+// Register assignment shared with whoever emits the surrounding entry/exit code
+pub const TAPE: Reg = Reg::R12;
+pub const TAPE_LEN: Reg = Reg::R13;
+pub const CELL: Reg = Reg::Rbx;
+pub const INSTR: Reg = Reg::R14;
+pub const VALUE: Reg = Reg::R15;
+
+const SYS_WRITE: i64 = 1;
+const SYS_EXIT: i64 = 60;
+
+const OUT_OF_BOUNDS_MESSAGE: &[u8] = b"[error] Out of bounds memory access\n";
+
+fn panic_report(value: u8) -> Vec<u8> {
+    format!("[PANIC] Program entered panic loop with error code {}\n", value).into_bytes()
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CodegenOptions {
+    pub bounds_checks: bool,
+    pub eof_behavior: EofBehavior,
+}
+
+/// Labels that the generated code jumps to when it stops running
+///
+/// On entry to `out_of_bounds` and `panic`, `INSTR` holds the index of the
+/// offending instruction, and for `panic`, `VALUE` holds the panic code.
+/// `CELL` always holds the current cell index.
+#[derive(Copy, Clone, Debug)]
+pub struct Exits {
+    pub done: Label,
+    pub out_of_bounds: Label,
+    pub panic: Label,
+}
+
+/// Lowers interpreter ops to x86-64 instructions that run against the tape at `TAPE`
+///
+/// I/O is performed with raw Linux `read`/`write` syscalls, so the generated code
+/// has no dependencies and can run both in-process and as a standalone executable.
+pub fn lower(asm: &mut Assembler, ops: &[InterpreterOp], options: CodegenOptions, exits: Exits) -> Result<()> {
+    let get = asm.new_label();
+    let put = asm.new_label();
+
+    let mut codegen = Codegen {
+        asm,
+        options,
+        exits,
+        get,
+        put,
+        loops: Vec::new(),
+        statics: Vec::new(),
+    };
+
+    for (instr_i, op) in ops.iter().enumerate() {
+        codegen.lower_op(instr_i, op)?;
+    }
+
+    codegen.asm.push(Inst::Jump(exits.done));
+    codegen.emit_runtime();
+    Ok(())
+}
+
+/// Lowers a whole standalone Linux program, which expects the tape address to already be in `TAPE`
+///
+/// A panic prints a report and exits with the panic code, an out of bounds access prints an error and exits
+/// with 1.
+pub fn lower_executable(asm: &mut Assembler, ops: &[InterpreterOp], options: CodegenOptions) -> Result<()> {
+    let exits = Exits {
+        done: asm.new_label(),
+        out_of_bounds: asm.new_label(),
+        panic: asm.new_label(),
+    };
+    let message = asm.new_label();
+
+    asm.push(Inst::MovImm(TAPE_LEN, CELL_COUNT as i64));
+    asm.push(Inst::MovImm(CELL, 0));
+    asm.push(Inst::MovImm(INSTR, 0));
+    asm.push(Inst::MovImm(VALUE, 0));
+
+    lower(asm, ops, options, exits)?;
+
+    asm.push(Inst::Label(exits.done));
+    asm.push(Inst::MovImm(Reg::Rdi, 0));
+    asm.push(Inst::MovImm(Reg::Rax, SYS_EXIT));
+    asm.push(Inst::Syscall);
+
+    asm.push(Inst::Label(exits.out_of_bounds));
+    asm.push(Inst::MovImm(Reg::Rax, SYS_WRITE));
+    asm.push(Inst::MovImm(Reg::Rdi, 2));
+    asm.push(Inst::LeaLabel(Reg::Rsi, message));
+    asm.push(Inst::MovImm(Reg::Rdx, OUT_OF_BOUNDS_MESSAGE.len() as i64));
+    asm.push(Inst::Syscall);
+    asm.push(Inst::MovImm(Reg::Rdi, 1));
+    asm.push(Inst::MovImm(Reg::Rax, SYS_EXIT));
+    asm.push(Inst::Syscall);
+
+    // Panic codes are known at compile time, so each one gets its own report instead of formatting `VALUE`
+    let panic_codes: BTreeSet<u8> = ops
+        .iter()
+        .filter_map(|op| match op {
+            InterpreterOp::CompoundOp(CompoundOp::Panic(value)) => Some(*value),
+            _ => None,
+        })
+        .collect();
+    let panic_messages: Vec<(u8, Label, Label)> = panic_codes
+        .into_iter()
+        .map(|value| (value, asm.new_label(), asm.new_label()))
+        .collect();
+
+    asm.push(Inst::Label(exits.panic));
+    for (value, report, _) in &panic_messages {
+        asm.push(Inst::AluImm(AluOp::Cmp, VALUE, *value as i32));
+        asm.push(Inst::JumpIf(Cond::Equal, *report));
+    }
+    asm.push(Inst::Mov(Reg::Rdi, VALUE));
+    asm.push(Inst::MovImm(Reg::Rax, SYS_EXIT));
+    asm.push(Inst::Syscall);
+
+    for (value, report, panic_message) in &panic_messages {
+        asm.push(Inst::Label(*report));
+        asm.push(Inst::MovImm(Reg::Rax, SYS_WRITE));
+        asm.push(Inst::MovImm(Reg::Rdi, 2));
+        asm.push(Inst::LeaLabel(Reg::Rsi, *panic_message));
+        asm.push(Inst::MovImm(Reg::Rdx, panic_report(*value).len() as i64));
+        asm.push(Inst::Syscall);
+        asm.push(Inst::MovImm(Reg::Rdi, *value as i64));
+        asm.push(Inst::MovImm(Reg::Rax, SYS_EXIT));
+        asm.push(Inst::Syscall);
+    }
+
+    asm.push(Inst::Label(message));
+    asm.push(Inst::Data(OUT_OF_BOUNDS_MESSAGE.to_vec()));
+
+    for (value, _, panic_message) in panic_messages {
+        asm.push(Inst::Label(panic_message));
+        asm.push(Inst::Data(panic_report(value)));
+    }
+
+    Ok(())
+}
+
+struct Codegen<'a> {
+    asm: &'a mut Assembler,
+    options: CodegenOptions,
+    exits: Exits,
+    get: Label,
+    put: Label,
+    loops: Vec<(Label, Label)>,
+    statics: Vec<(Label, Vec<u8>)>,
+}
+
+fn cell(disp: i32) -> Mem {
+    Mem::new(TAPE, Some(CELL), disp)
+}
+
+fn dynamic_cell(index: Reg) -> Mem {
+    Mem::new(TAPE, Some(index), 0)
+}
+
+fn disp(offset: i64) -> Result<i32> {
+    i32::try_from(offset).map_err(|_| anyhow!("[error] Offset {} is too large for x86-64 code generation", offset))
+}
+
+impl<'a> Codegen<'a> {
+    fn emit(&mut self, inst: Inst) {
+        self.asm.push(inst);
+    }
+
+    /// Jumps to the out of bounds exit unless the cell at `disp` is on the tape
+    fn check(&mut self, disp: i32) {
+        if !self.options.bounds_checks {
+            return;
+        }
+
+        if disp == 0 {
+            self.emit(Inst::Alu(AluOp::Cmp, CELL, TAPE_LEN));
+        } else {
+            self.emit(Inst::Lea(Reg::R11, Mem::new(CELL, None, disp)));
+            self.emit(Inst::Alu(AluOp::Cmp, Reg::R11, TAPE_LEN));
+        }
+
+        self.emit(Inst::JumpIf(Cond::AboveEqual, self.exits.out_of_bounds));
+    }
+
+    /// Bounds checks the contiguous cells from `first` to `last` inclusive
+    fn check_range(&mut self, first: i32, last: i32) {
+        self.check(first);
+
+        if last != first {
+            self.check(last);
+        }
+    }
+
+    fn check_dynamic(&mut self, index: Reg) {
+        if self.options.bounds_checks {
+            self.emit(Inst::Alu(AluOp::Cmp, index, TAPE_LEN));
+            self.emit(Inst::JumpIf(Cond::AboveEqual, self.exits.out_of_bounds));
+        }
+    }
+
+    fn shift(&mut self, amount: i64) {
+        match i32::try_from(amount) {
+            Ok(0) => (),
+            Ok(amount) => self.emit(Inst::AluImm(AluOp::Add, CELL, amount)),
+            Err(_) => {
+                self.emit(Inst::MovImm(Reg::Rax, amount));
+                self.emit(Inst::Alu(AluOp::Add, CELL, Reg::Rax));
+            }
+        }
+    }
+
+    fn zero_range(&mut self, first: i32, count: u64) -> Result<()> {
+        if count <= 8 {
+            for i in 0..count as i32 {
+                self.emit(Inst::StoreByteImm(cell(first + i), 0));
+            }
+        } else {
+            self.emit(Inst::Lea(Reg::Rdi, cell(first)));
+            self.emit(Inst::MovImm(Reg::Rcx, i64::try_from(count)?));
+            self.emit(Inst::Alu(AluOp::Xor, Reg::Rax, Reg::Rax));
+            self.emit(Inst::RepStosb);
+        }
+
+        Ok(())
+    }
+
+    fn load_pair(&mut self, first: i32, second: i32) {
+        self.emit(Inst::LoadByte(Reg::Rax, cell(first)));
+        self.emit(Inst::LoadByte(Reg::Rcx, cell(second)));
+    }
+
+    fn comparison(&mut self, cond: Cond) {
+        // a b 0 0
+        //     ^
+        self.check_range(-2, 1);
+        self.load_pair(-2, -1);
+        self.emit(Inst::Alu(AluOp::Cmp, Reg::Rax, Reg::Rcx));
+        self.emit(Inst::SetCond(cond, Reg::Rax));
+        self.emit(Inst::StoreByte(cell(-2), Reg::Rax));
+        self.emit(Inst::StoreByteImm(cell(-1), 0));
+        self.emit(Inst::StoreByteImm(cell(0), 0));
+        self.emit(Inst::StoreByteImm(cell(1), 0));
+    }
+
+    fn logical_shift(&mut self, op: ShiftOp) {
+        let in_range = self.asm.new_label();
+
+        self.load_pair(-2, -1);
+        self.emit(Inst::AluImm(AluOp::Cmp, Reg::Rcx, 8));
+        self.emit(Inst::JumpIf(Cond::Below, in_range));
+        self.emit(Inst::Alu(AluOp::Xor, Reg::Rax, Reg::Rax));
+        self.emit(Inst::Label(in_range));
+        self.emit(Inst::ShiftCl(op, Reg::Rax));
+        self.emit(Inst::StoreByte(cell(-2), Reg::Rax));
+    }
+
+    /// Searches the `rcx` cells from `rdi` in the direction flag's direction for a zero, leaving `CELL` one past it
+    ///
+    /// Running off the tape without finding one counts as an out of bounds access.
+    fn scan_bytes(&mut self) {
+        self.emit(Inst::Alu(AluOp::Xor, Reg::Rax, Reg::Rax));
+        self.emit(Inst::RepneScasb);
+        self.emit(Inst::JumpIf(Cond::NotEqual, self.exits.out_of_bounds));
+        self.emit(Inst::Mov(CELL, Reg::Rdi));
+        self.emit(Inst::Alu(AluOp::Sub, CELL, TAPE));
+    }
+
+    /// Moves by `stride` until the current cell is zero
+    fn scan(&mut self, stride: i64) {
+        let scan = self.asm.new_label();
+        let scanned = self.asm.new_label();
+
+        self.emit(Inst::Label(scan));
+        self.check(0);
+        self.emit(Inst::CmpByteImm(cell(0), 0));
+        self.emit(Inst::JumpIf(Cond::Equal, scanned));
+        self.shift(stride);
+        self.emit(Inst::Jump(scan));
+        self.emit(Inst::Label(scanned));
+    }
+
+    fn lower_op(&mut self, instr_i: usize, op: &InterpreterOp) -> Result<()> {
+        if self.options.bounds_checks {
+            self.emit(Inst::MovImm(INSTR, instr_i as i64));
+        }
+
+        match op {
+            InterpreterOp::LoopStart(_) => {
+                let start = self.asm.new_label();
+                let end = self.asm.new_label();
+                self.loops.push((start, end));
+
+                self.check(0);
+                self.emit(Inst::CmpByteImm(cell(0), 0));
+                self.emit(Inst::JumpIf(Cond::Equal, end));
+                self.emit(Inst::Label(start));
+            }
+            InterpreterOp::LoopEnd(_) => {
+                let (start, end) = self
+                    .loops
+                    .pop()
+                    .ok_or_else(|| anyhow!("[error] Instruction ']' is missing match"))?;
+
+                self.check(0);
+                self.emit(Inst::CmpByteImm(cell(0), 0));
+                self.emit(Inst::JumpIf(Cond::NotEqual, start));
+                self.emit(Inst::Label(end));
+            }
+            InterpreterOp::IfStart(_) => {
+                // Nothing jumps back to the start of an if
+                let end = self.asm.new_label();
+                self.loops.push((end, end));
+
+                self.check(0);
+                self.emit(Inst::CmpByteImm(cell(0), 0));
+                self.emit(Inst::JumpIf(Cond::Equal, end));
+            }
+            InterpreterOp::IfEnd(_) => {
+                let (_, end) = self
+                    .loops
+                    .pop()
+                    .ok_or_else(|| anyhow!("[error] Instruction ']' is missing match"))?;
+
+                self.emit(Inst::Label(end));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::ChangeBy(amount))) => {
+                self.check(0);
+                self.emit(Inst::AddByteImm(cell(0), *amount));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Shift(amount))) => {
+                self.shift(*amount);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Input(count))) => {
+                self.check(0);
+                for _ in 0..*count {
+                    self.emit(Inst::Lea(Reg::Rsi, cell(0)));
+                    self.emit(Inst::Call(self.get));
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::Output(count))) => {
+                self.check(0);
+                for _ in 0..*count {
+                    self.emit(Inst::Lea(Reg::Rsi, cell(0)));
+                    self.emit(Inst::MovImm(Reg::Rdx, 1));
+                    self.emit(Inst::Call(self.put));
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BasicOp(BasicOp::LoopStart | BasicOp::LoopEnd)) => {
+                return Err(anyhow!("[error] Cannot compile unprocessed loop instruction"));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Panic(value)) => {
+                self.emit(Inst::MovImm(INSTR, instr_i as i64));
+                self.emit(Inst::MovImm(VALUE, *value as i64));
+                self.emit(Inst::Jump(self.exits.panic));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Zero) => {
+                self.check(0);
+                self.emit(Inst::StoreByteImm(cell(0), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroAdvance(amount)) => {
+                self.check_range(0, disp(*amount as i64 - 1)?);
+                self.zero_range(0, *amount)?;
+                self.shift(*amount as i64);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ZeroRetreat(amount)) => {
+                let first = disp(1 - *amount as i64)?;
+                self.check_range(first, 0);
+                self.zero_range(first, *amount)?;
+                self.shift(-(*amount as i64));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Set(value)) => {
+                self.check(0);
+                self.emit(Inst::StoreByteImm(cell(0), *value));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Dupe(offset)) => {
+                let offset = disp(*offset)?;
+                self.check(offset);
+                self.check_range(0, 1);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(offset)));
+                self.emit(Inst::StoreByte(cell(0), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(1), 0));
+                self.shift(1);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitAnd) => {
+                // Warning: Unsound
+
+                // a b ? ? ? ? ? ?
+                //               ^
+
+                self.check_range(-7, -6);
+                self.load_pair(-7, -6);
+                self.emit(Inst::Alu(AluOp::And, Reg::Rax, Reg::Rcx));
+                self.emit(Inst::StoreByte(cell(-7), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(-6), 0));
+                self.shift(2);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::WellBehavedDivMod(shift_amount)) => {
+                let divide_by_zero = self.asm.new_label();
+                let divided = self.asm.new_label();
+
+                self.check_range(-2, 3);
+                self.load_pair(-2, -1);
+                self.emit(Inst::Alu(AluOp::Xor, Reg::Rdx, Reg::Rdx));
+                self.emit(Inst::Test(Reg::Rcx, Reg::Rcx));
+                self.emit(Inst::JumpIf(Cond::Equal, divide_by_zero));
+                self.emit(Inst::Div32(Reg::Rcx));
+                self.emit(Inst::Jump(divided));
+                self.emit(Inst::Label(divide_by_zero));
+                self.emit(Inst::Alu(AluOp::Xor, Reg::Rax, Reg::Rax));
+                self.emit(Inst::Label(divided));
+
+                // Quotient is in rax, remainder is in rdx
+                self.emit(Inst::Alu(AluOp::Sub, Reg::Rcx, Reg::Rdx));
+                self.emit(Inst::StoreByteImm(cell(-2), 0));
+                self.emit(Inst::StoreByte(cell(-1), Reg::Rcx));
+                self.emit(Inst::StoreByte(cell(0), Reg::Rdx));
+                self.emit(Inst::StoreByte(cell(1), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(2), 0));
+                self.emit(Inst::StoreByteImm(cell(3), 0));
+                self.shift(*shift_amount);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::BitNeg) => {
+                self.check_range(0, 1);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(0)));
+                self.emit(Inst::Not(Reg::Rax));
+                self.emit(Inst::StoreByte(cell(0), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(1), 0));
+                self.shift(1);
+            }
+            InterpreterOp::CompoundOp(op @ (CompoundOp::Equals | CompoundOp::NotEquals)) => {
+                let cond = match op {
+                    CompoundOp::Equals => Cond::Equal,
+                    _ => Cond::NotEqual,
+                };
+
+                self.check_range(0, 1);
+                self.load_pair(0, 1);
+                self.emit(Inst::Alu(AluOp::Cmp, Reg::Rax, Reg::Rcx));
+                self.emit(Inst::SetCond(cond, Reg::Rax));
+                self.emit(Inst::StoreByte(cell(0), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(1), 0));
+                self.shift(1);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftLeftLogical) => {
+                self.check_range(-2, 0);
+                self.logical_shift(ShiftOp::Shl);
+                self.emit(Inst::StoreByteImm(cell(-1), 0));
+                self.emit(Inst::StoreByteImm(cell(0), 0));
+                self.shift(-1);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ShiftRightLogical) => {
+                self.check_range(-2, 3);
+                self.logical_shift(ShiftOp::Shr);
+                self.zero_range(-1, 5)?;
+                self.shift(-1);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::LessThan) => self.comparison(Cond::Below),
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThan) => self.comparison(Cond::Above),
+            InterpreterOp::CompoundOp(CompoundOp::LessThanEqual) => self.comparison(Cond::BelowEqual),
+            InterpreterOp::CompoundOp(CompoundOp::GreaterThanEqual) => self.comparison(Cond::AboveEqual),
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd(offset)) => {
+                let offset = disp(*offset)?;
+                self.check(0);
+                self.check(offset);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(0)));
+                self.emit(Inst::AddByte(cell(offset), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(0), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSet(offset)) => {
+                let offset = disp(*offset)?;
+                self.check(0);
+                self.check(offset);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(0)));
+                self.emit(Inst::StoreByte(cell(offset), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(0), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2(offset1, offset2)) => {
+                let offset1 = disp(*offset1)?;
+                let offset2 = disp(*offset2)?;
+                self.check(0);
+                self.check(offset1);
+                self.check(offset2);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(0)));
+                self.emit(Inst::AddByte(cell(offset1), Reg::Rax));
+                self.emit(Inst::AddByte(cell(offset2), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(0), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::PrintStatic(content)) => {
+                let label = self.asm.new_label();
+                self.statics.push((label, content.clone()));
+
+                self.emit(Inst::LeaLabel(Reg::Rsi, label));
+                self.emit(Inst::MovImm(Reg::Rdx, content.len() as i64));
+                self.emit(Inst::Call(self.put));
+                self.check(0);
+                self.emit(Inst::StoreByteImm(cell(0), *content.last().unwrap()));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                self.check_range(-2, -1);
+                self.load_pair(-2, -1);
+                self.emit(Inst::Lea(Reg::Rdx, cell_offset(-3 - *offset as i64)?));
+                self.emit(Inst::Alu(AluOp::Add, Reg::Rdx, Reg::Rcx));
+                self.check_dynamic(Reg::Rdx);
+                self.emit(Inst::StoreByte(dynamic_cell(Reg::Rdx), Reg::Rax));
+                self.emit(Inst::StoreByte(cell(-2), Reg::Rcx));
+                self.shift(-2);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU16(offset)) => {
+                // Warning: Unsound
+                self.check_range(-3, -1);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(-3)));
+                self.emit(Inst::LoadByte(Reg::R8, cell(-2)));
+                self.emit(Inst::LoadByte(Reg::R9, cell(-1)));
+                self.emit(Inst::Mov(Reg::Rcx, Reg::R9));
+                self.emit(Inst::Shift(ShiftOp::Shl, Reg::Rcx, 8));
+                self.emit(Inst::Alu(AluOp::Or, Reg::Rcx, Reg::R8));
+                self.emit(Inst::Lea(Reg::Rdx, cell_offset(-(*offset as i64))?));
+                self.emit(Inst::Alu(AluOp::Add, Reg::Rdx, Reg::Rcx));
+                self.check_dynamic(Reg::Rdx);
+                self.emit(Inst::StoreByte(dynamic_cell(Reg::Rdx), Reg::Rax));
+                self.emit(Inst::StoreByte(cell(-3), Reg::R8));
+                self.emit(Inst::StoreByte(cell(-2), Reg::R9));
+                self.shift(-3);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU8(offset)) => {
+                // Warning: Unsound
+                self.check(-1);
+                self.emit(Inst::LoadByte(Reg::Rcx, cell(-1)));
+                self.emit(Inst::Lea(Reg::Rdx, cell_offset(-1 - *offset as i64)?));
+                self.emit(Inst::Alu(AluOp::Add, Reg::Rdx, Reg::Rcx));
+                self.check_dynamic(Reg::Rdx);
+                self.emit(Inst::LoadByte(Reg::Rax, dynamic_cell(Reg::Rdx)));
+                self.emit(Inst::StoreByte(cell(-1), Reg::Rax));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                self.check_range(-5, -1);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(-5)));
+                self.emit(Inst::Load32(Reg::Rcx, cell(-4)));
+                self.emit(Inst::Lea(Reg::Rdx, cell_offset(-(*offset as i64))?));
+                self.emit(Inst::Alu(AluOp::Add, Reg::Rdx, Reg::Rcx));
+                self.check_dynamic(Reg::Rdx);
+                self.emit(Inst::StoreByte(dynamic_cell(Reg::Rdx), Reg::Rax));
+                self.emit(Inst::Store32(cell(-5), Reg::Rcx));
+                self.shift(-5);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::CopyCellDynamicU32(offset)) => {
+                // Warning: Unsound
+                self.check_range(-4, -1);
+                self.emit(Inst::Load32(Reg::Rcx, cell(-4)));
+                self.emit(Inst::Lea(Reg::Rdx, cell_offset(-(*offset as i64))?));
+                self.emit(Inst::Alu(AluOp::Add, Reg::Rdx, Reg::Rcx));
+                self.check_dynamic(Reg::Rdx);
+                self.emit(Inst::LoadByte(Reg::Rax, dynamic_cell(Reg::Rdx)));
+                self.emit(Inst::StoreByte(cell(-4), Reg::Rax));
+                self.shift(-3);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveCellsStaticReverse(offset, count)) => {
+                let count_i64 = i64::try_from(*count)?;
+                let src = disp(1 - count_i64)?;
+                let dest = disp(*offset + 1 - count_i64)?;
+                let last = disp(count_i64 - 1)?;
+
+                self.check_range(src, 0);
+                self.check_range(dest, dest + last);
+
+                // Same semantics as memmove, copy backwards when moving to higher addresses
+                if *offset > 0 {
+                    self.emit(Inst::Lea(Reg::Rsi, cell(src + last)));
+                    self.emit(Inst::Lea(Reg::Rdi, cell(dest + last)));
+                    self.emit(Inst::MovImm(Reg::Rcx, count_i64));
+                    self.emit(Inst::Std);
+                    self.emit(Inst::RepMovsb);
+                    self.emit(Inst::Cld);
+                } else {
+                    self.emit(Inst::Lea(Reg::Rsi, cell(src)));
+                    self.emit(Inst::Lea(Reg::Rdi, cell(dest)));
+                    self.emit(Inst::MovImm(Reg::Rcx, count_i64));
+                    self.emit(Inst::RepMovsb);
+                }
+
+                self.zero_range(src, *count)?;
+                self.shift(-count_i64);
+            }
+            InterpreterOp::CompoundOp(op @ (CompoundOp::AddU32 | CompoundOp::MulU32)) => {
+                // Warning: Unsound
+                self.check_range(-8, -1);
+                self.emit(Inst::Load32(Reg::Rax, cell(-8)));
+                self.emit(Inst::Load32(Reg::Rcx, cell(-4)));
+
+                match op {
+                    CompoundOp::AddU32 => self.emit(Inst::Alu(AluOp::Add, Reg::Rax, Reg::Rcx)),
+                    _ => self.emit(Inst::Imul(Reg::Rax, Reg::Rcx)),
+                }
+
+                self.emit(Inst::Store32(cell(-8), Reg::Rax));
+                self.shift(-5);
+            }
+            InterpreterOp::CompoundOp(
+                op @ (CompoundOp::SubU32
+                | CompoundOp::LessThanU32
+                | CompoundOp::EqualsU32
+                | CompoundOp::AndU32
+                | CompoundOp::OrU32
+                | CompoundOp::XorU32
+                | CompoundOp::ShiftLeftU32
+                | CompoundOp::ShiftRightU32
+                | CompoundOp::DivModU32),
+            ) => {
+                self.check_range(-8, -1);
+                self.emit(Inst::Load32(Reg::Rax, cell(-8)));
+                self.emit(Inst::Load32(Reg::Rcx, cell(-4)));
+                // Left as the second number, which only dividing puts anything other than zero in
+                self.emit(Inst::Alu(AluOp::Xor, Reg::Rdx, Reg::Rdx));
+
+                match op {
+                    CompoundOp::SubU32 => self.emit(Inst::Alu(AluOp::Sub, Reg::Rax, Reg::Rcx)),
+                    CompoundOp::AndU32 => self.emit(Inst::Alu(AluOp::And, Reg::Rax, Reg::Rcx)),
+                    CompoundOp::OrU32 => self.emit(Inst::Alu(AluOp::Or, Reg::Rax, Reg::Rcx)),
+                    CompoundOp::XorU32 => self.emit(Inst::Alu(AluOp::Xor, Reg::Rax, Reg::Rcx)),
+                    CompoundOp::LessThanU32 | CompoundOp::EqualsU32 => {
+                        let cond = match op {
+                            CompoundOp::LessThanU32 => Cond::Below,
+                            _ => Cond::Equal,
+                        };
+
+                        self.emit(Inst::Alu(AluOp::Cmp, Reg::Rax, Reg::Rcx));
+                        self.emit(Inst::Mov(Reg::Rax, Reg::Rdx));
+                        self.emit(Inst::SetCond(cond, Reg::Rax));
+                    }
+                    CompoundOp::ShiftLeftU32 | CompoundOp::ShiftRightU32 => {
+                        let in_range = self.asm.new_label();
+                        let shift_op = match op {
+                            CompoundOp::ShiftLeftU32 => ShiftOp::Shl,
+                            _ => ShiftOp::Shr,
+                        };
+
+                        self.emit(Inst::AluImm(AluOp::Cmp, Reg::Rcx, 32));
+                        self.emit(Inst::JumpIf(Cond::Below, in_range));
+                        self.emit(Inst::Alu(AluOp::Xor, Reg::Rax, Reg::Rax));
+                        self.emit(Inst::Label(in_range));
+                        self.emit(Inst::ShiftCl(shift_op, Reg::Rax));
+                    }
+                    _ => {
+                        let divide_by_zero = self.asm.new_label();
+                        let divided = self.asm.new_label();
+
+                        self.emit(Inst::Test(Reg::Rcx, Reg::Rcx));
+                        self.emit(Inst::JumpIf(Cond::Equal, divide_by_zero));
+                        self.emit(Inst::Div32(Reg::Rcx));
+                        self.emit(Inst::Jump(divided));
+                        self.emit(Inst::Label(divide_by_zero));
+                        self.emit(Inst::Alu(AluOp::Xor, Reg::Rax, Reg::Rax));
+                        self.emit(Inst::Label(divided));
+                    }
+                }
+
+                self.emit(Inst::Store32(cell(-8), Reg::Rax));
+                self.emit(Inst::Store32(cell(-4), Reg::Rdx));
+                self.shift(-5);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddMany(targets)) => {
+                self.check(0);
+
+                for (offset, _) in targets {
+                    self.check(disp(*offset)?);
+                }
+
+                self.emit(Inst::LoadByte(Reg::Rax, cell(0)));
+
+                for (offset, factor) in targets {
+                    if *factor == 1 {
+                        self.emit(Inst::AddByte(cell(disp(*offset)?), Reg::Rax));
+                    } else {
+                        self.emit(Inst::MovImm(Reg::Rcx, *factor as i64));
+                        self.emit(Inst::Imul(Reg::Rcx, Reg::Rax));
+                        self.emit(Inst::AddByte(cell(disp(*offset)?), Reg::Rcx));
+                    }
+                }
+
+                self.emit(Inst::StoreByteImm(cell(0), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(1)) => {
+                self.check(0);
+                self.emit(Inst::Lea(Reg::Rdi, cell(0)));
+                self.emit(Inst::Mov(Reg::Rcx, TAPE_LEN));
+                self.emit(Inst::Alu(AluOp::Sub, Reg::Rcx, CELL));
+                self.scan_bytes();
+                self.shift(-1);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(1)) => {
+                self.check(0);
+                self.emit(Inst::Lea(Reg::Rdi, cell(0)));
+                self.emit(Inst::Lea(Reg::Rcx, Mem::new(CELL, None, 1)));
+                self.emit(Inst::Std);
+                self.scan_bytes();
+                self.emit(Inst::Cld);
+                self.shift(1);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanRight(stride)) => {
+                self.scan(i64::try_from(*stride)?);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ScanLeft(stride)) => {
+                self.scan(-i64::try_from(*stride)?);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetCells(offset, values)) => {
+                let first = disp(*offset)?;
+                let label = self.asm.new_label();
+                self.statics.push((label, values.clone()));
+
+                self.check_range(first, disp(*offset + values.len() as i64 - 1)?);
+                self.emit(Inst::LeaLabel(Reg::Rsi, label));
+                self.emit(Inst::Lea(Reg::Rdi, cell(first)));
+                self.emit(Inst::MovImm(Reg::Rcx, values.len() as i64));
+                self.emit(Inst::RepMovsb);
+            }
+            InterpreterOp::CompoundOp(CompoundOp::ChangeByAt(offset, amount)) => {
+                let offset = disp(*offset)?;
+                self.check(offset);
+                self.emit(Inst::AddByteImm(cell(offset), *amount));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::SetAt(offset, value)) => {
+                let offset = disp(*offset)?;
+                self.check(offset);
+                self.emit(Inst::StoreByteImm(cell(offset), *value));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::InputAt(offset, count)) => {
+                let offset = disp(*offset)?;
+                self.check(offset);
+                for _ in 0..*count {
+                    self.emit(Inst::Lea(Reg::Rsi, cell(offset)));
+                    self.emit(Inst::Call(self.get));
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::OutputAt(offset, count)) => {
+                let offset = disp(*offset)?;
+                self.check(offset);
+                for _ in 0..*count {
+                    self.emit(Inst::Lea(Reg::Rsi, cell(offset)));
+                    self.emit(Inst::MovImm(Reg::Rdx, 1));
+                    self.emit(Inst::Call(self.put));
+                }
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAddAt(from, to)) => {
+                let from = disp(*from)?;
+                let to = disp(*to)?;
+                self.check(from);
+                self.check(to);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(from)));
+                self.emit(Inst::AddByte(cell(to), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(from), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveAdd2At(from, to1, to2)) => {
+                let from = disp(*from)?;
+                let to1 = disp(*to1)?;
+                let to2 = disp(*to2)?;
+                self.check(from);
+                self.check(to1);
+                self.check(to2);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(from)));
+                self.emit(Inst::AddByte(cell(to1), Reg::Rax));
+                self.emit(Inst::AddByte(cell(to2), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(from), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MoveSetAt(from, to)) => {
+                let from = disp(*from)?;
+                let to = disp(*to)?;
+                self.check(from);
+                self.check(to);
+                self.emit(Inst::LoadByte(Reg::Rax, cell(from)));
+                self.emit(Inst::StoreByte(cell(to), Reg::Rax));
+                self.emit(Inst::StoreByteImm(cell(from), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::MulAddManyAt(from, targets)) => {
+                let from = disp(*from)?;
+                self.check(from);
+
+                for (offset, _) in targets {
+                    self.check(disp(*offset)?);
+                }
+
+                self.emit(Inst::LoadByte(Reg::Rax, cell(from)));
+
+                for (offset, factor) in targets {
+                    if *factor == 1 {
+                        self.emit(Inst::AddByte(cell(disp(*offset)?), Reg::Rax));
+                    } else {
+                        self.emit(Inst::MovImm(Reg::Rcx, *factor as i64));
+                        self.emit(Inst::Imul(Reg::Rcx, Reg::Rax));
+                        self.emit(Inst::AddByte(cell(disp(*offset)?), Reg::Rcx));
+                    }
+                }
+
+                self.emit(Inst::StoreByteImm(cell(from), 0));
+            }
+            InterpreterOp::CompoundOp(CompoundOp::Affine(updates)) => {
+                // Checked up front so nothing is left on the stack when jumping to an exit
+                for update in updates {
+                    self.check(disp(update.offset)?);
+
+                    for (offset, _) in &update.terms {
+                        self.check(disp(*offset)?);
+                    }
+                }
+
+                // Every value is pushed before any is stored, then they're popped and stored last first
+                for update in updates {
+                    self.emit(Inst::MovImm(Reg::Rax, update.constant as i64));
+
+                    for (offset, coefficient) in &update.terms {
+                        self.emit(Inst::LoadByte(Reg::Rcx, cell(disp(*offset)?)));
+
+                        if *coefficient != 1 {
+                            self.emit(Inst::MovImm(Reg::Rdx, *coefficient as i64));
+                            self.emit(Inst::Imul(Reg::Rcx, Reg::Rdx));
+                        }
+
+                        self.emit(Inst::Alu(AluOp::Add, Reg::Rax, Reg::Rcx));
+                    }
+
+                    self.emit(Inst::Push(Reg::Rax));
+                }
+
+                for update in updates.iter().rev() {
+                    self.emit(Inst::Pop(Reg::Rax));
+                    self.emit(Inst::StoreByte(cell(disp(update.offset)?), Reg::Rax));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Emits the I/O subroutines and static data referenced by the lowered ops
+    fn emit_runtime(&mut self) {
+        // get: reads one byte into [rsi], applying the EOF behavior when nothing could be read
+        let got = self.asm.new_label();
+        self.emit(Inst::Label(self.get));
+        self.emit(Inst::MovImm(Reg::Rax, 0));
+        self.emit(Inst::MovImm(Reg::Rdi, 0));
+        self.emit(Inst::MovImm(Reg::Rdx, 1));
+        self.emit(Inst::Syscall);
+        self.emit(Inst::AluImm(AluOp::Cmp, Reg::Rax, 1));
+        self.emit(Inst::JumpIf(Cond::Equal, got));
+
+        match self.options.eof_behavior {
+            EofBehavior::Zero => self.emit(Inst::StoreByteImm(Mem::new(Reg::Rsi, None, 0), 0)),
+            EofBehavior::Unchanged => (),
+            EofBehavior::Max => self.emit(Inst::StoreByteImm(Mem::new(Reg::Rsi, None, 0), u8::MAX)),
+        }
+
+        self.emit(Inst::Label(got));
+        self.emit(Inst::Ret);
+
+        // put: writes rdx bytes starting at rsi to stdout
+        let written = self.asm.new_label();
+        self.emit(Inst::Label(self.put));
+        self.emit(Inst::Test(Reg::Rdx, Reg::Rdx));
+        self.emit(Inst::JumpIf(Cond::Equal, written));
+        self.emit(Inst::MovImm(Reg::Rax, 1));
+        self.emit(Inst::MovImm(Reg::Rdi, 1));
+        self.emit(Inst::Syscall);
+        self.emit(Inst::AluImm(AluOp::Cmp, Reg::Rax, 0));
+        self.emit(Inst::JumpIf(Cond::LessEqual, written));
+        self.emit(Inst::Alu(AluOp::Add, Reg::Rsi, Reg::Rax));
+        self.emit(Inst::Alu(AluOp::Sub, Reg::Rdx, Reg::Rax));
+        self.emit(Inst::Jump(self.put));
+        self.emit(Inst::Label(written));
+        self.emit(Inst::Ret);
+
+        for (label, content) in std::mem::take(&mut self.statics) {
+            self.emit(Inst::Label(label));
+            self.emit(Inst::Data(content));
+        }
+    }
+}
+
+fn cell_offset(offset: i64) -> Result<Mem> {
+    Ok(Mem::new(CELL, None, disp(offset)?))
+}
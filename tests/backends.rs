@@ -0,0 +1,190 @@
+//! Runs the same programs through every backend whose toolchain is installed, checking that they all print the
+//! same output and exit with the same code as the interpreter
+
+use blazing_fast_interpreter::backend::Backends;
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+/// Programs with the input they read
+const PROGRAMS: &[(&str, &str, &[u8])] = &[
+    (
+        "hello world",
+        "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.",
+        b"",
+    ),
+    ("echo", ",[.,]", b"echo this back\n"),
+    ("reverse", ">,[>,]<[.<]", b"reversed"),
+    (
+        "nested loops",
+        "++++++++[>++++[>++>+++<<-]<-]>>.>.[-]<<<+++[>>>+++[<.>-]<<<-]",
+        b"",
+    ),
+    ("scans", ">+>+>+>+>>+>>>+<<<[<]>.>>>>[>>>]<.[<<]<.", b""),
+    ("multiplication", ",>,<[>[>+>+<<-]>>[<<+>>-]<<<-]>>.", b"\x07\x06"),
+    ("panic", "++++++++++[>+++++++<-]>.[-]+++++[]", b""),
+];
+
+/// How a program finished: what it printed and its exit code
+type Run = (Vec<u8>, i32);
+
+fn finished(output: Output) -> Run {
+    (output.stdout, output.status.code().expect("killed by a signal"))
+}
+
+fn run(command: &mut Command, input: &[u8]) -> Run {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    finished(child.wait_with_output().unwrap())
+}
+
+fn interpreter() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_blazing-fast-interpreter"))
+}
+
+fn has_tool(tool: &str) -> bool {
+    Command::new(tool)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Writes the program with a backend that generates code, returning the file written
+fn emit(backend: &str, source: &Path, dir: &Path, extension: &str) -> String {
+    let output = dir.join(format!("{}.{}", backend, extension));
+    let status = interpreter()
+        .args([
+            "--backend",
+            backend,
+            "--output",
+            output.to_str().unwrap(),
+            source.to_str().unwrap(),
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success(), "{} failed to write {}", backend, output.display());
+    output.to_str().unwrap().to_string()
+}
+
+/// Builds an executable with a toolchain command, given the file to write
+fn build(command: &mut Command, executable: &str) -> Command {
+    assert!(command.status().unwrap().success(), "{:?} failed", command);
+    Command::new(executable)
+}
+
+/// LLVM 14 and earlier still need opaque pointers asked for
+fn lli() -> Command {
+    let version = Command::new("lli").arg("--version").output().unwrap().stdout;
+    let major = String::from_utf8_lossy(&version)
+        .split("version ")
+        .nth(1)
+        .and_then(|version| version.split('.').next()?.parse::<u32>().ok())
+        .unwrap_or(u32::MAX);
+
+    let mut lli = Command::new("lli");
+    if major < 15 {
+        lli.arg("-opaque-pointers");
+    }
+    lli
+}
+
+/// Command running the program with `backend`, or `None` if its toolchain isn't installed
+fn command(backend: &str, source: &Path, dir: &Path) -> Option<Command> {
+    let native = cfg!(all(target_arch = "x86_64", target_os = "linux"));
+    let executable = dir.join(backend).to_str().unwrap().to_string();
+
+    Some(match backend {
+        "interpreter" => {
+            let mut command = interpreter();
+            command.arg(source);
+            command
+        }
+        "jit" if native => {
+            let mut command = interpreter();
+            command.args(["--backend", "jit"]).arg(source);
+            command
+        }
+        "c-run" if has_tool("cc") => {
+            let mut command = interpreter();
+            command.args(["--backend", "c-run"]).arg(source);
+            command
+        }
+        "c" if has_tool("cc") => {
+            let c = emit(backend, source, dir, "c");
+            build(Command::new("cc").args(["-O1", &c, "-o", &executable]), &executable)
+        }
+        "c-compile" if has_tool("cc") => Command::new(emit(backend, source, dir, "out")),
+        "rust" if has_tool("rustc") => {
+            let rust = emit(backend, source, dir, "rs");
+            build(
+                Command::new("rustc").args(["-O", &rust, "-o", &executable]),
+                &executable,
+            )
+        }
+        "llvm" if has_tool("lli") => {
+            let mut command = lli();
+            command.arg(emit(backend, source, dir, "ll"));
+            command
+        }
+        "asm" if native && has_tool("as") && has_tool("ld") => {
+            let asm = emit(backend, source, dir, "s");
+            let object = format!("{}.o", executable);
+            build(Command::new("as").args([&asm, "-o", &object]), &object);
+            build(Command::new("ld").args([&object, "-o", &executable]), &executable)
+        }
+        "elf" if native => Command::new(emit(backend, source, dir, "out")),
+        "bf" => {
+            let mut command = interpreter();
+            command.arg(emit(backend, source, dir, "b"));
+            command
+        }
+        // Needs a host providing the panic import, and no WebAssembly runtime is assumed to be installed
+        "wat" => return None,
+        // Their toolchain or platform is missing
+        "jit" | "c-run" | "c" | "c-compile" | "rust" | "llvm" | "asm" | "elf" => return None,
+        name => panic!("no way to run the {} backend, add one here", name),
+    })
+}
+
+#[test]
+fn backends_agree_with_the_interpreter() {
+    let backends = Backends::builtin();
+
+    for (name, program, input) in PROGRAMS {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("program.b");
+        std::fs::write(&source, program).unwrap();
+
+        let expected = run(command("interpreter", &source, dir.path()).as_mut().unwrap(), input);
+
+        for backend in backends.iter() {
+            if let Some(mut command) = command(backend.name(), &source, dir.path()) {
+                assert_eq!(
+                    run(&mut command, input),
+                    expected,
+                    "{} on the {} backend",
+                    name,
+                    backend.name()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn the_interpreter_reports_panics_with_their_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("program.b");
+    std::fs::write(&source, "[-]+++++[]").unwrap();
+
+    assert_eq!(run(interpreter().arg(&source), b""), (Vec::new(), 5));
+}
@@ -0,0 +1,159 @@
+//! Checks nesting the flat ops into blocks and flattening them back, and what's worked out about each loop
+
+use blazing_fast_interpreter::{
+    basic_op::BasicOp,
+    block::{Block, Loop, Node},
+    compound_op::CompoundOp,
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+};
+
+use BasicOp::*;
+use CompoundOp::*;
+
+fn ops(compound_ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in compound_ops {
+        acc.feed(op).unwrap();
+    }
+
+    acc.view().unwrap().clone()
+}
+
+/// The loop at `index` in the block
+fn loop_at(block: &Block, index: usize) -> &Loop {
+    match &block.nodes[index] {
+        Node::Loop(inner) | Node::If(inner) => inner,
+        Node::Op(op) => panic!("{:?} isn't a loop", op),
+    }
+}
+
+#[test]
+fn nested_loops_flatten_back_to_the_same_ops() {
+    let program = ops(vec![
+        BasicOp(Input(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopStart),
+        BasicOp(Output(1)),
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(LoopStart),
+        BasicOp(LoopEnd),
+        Zero,
+    ]);
+    let block = Block::from_ops(&program).unwrap();
+
+    assert_eq!(block.nodes.len(), 4);
+    assert_eq!(block.to_ops(), program);
+}
+
+#[test]
+fn ifs_flatten_back_to_the_same_ops() {
+    let program = vec![
+        InterpreterOp::IfStart(4),
+        InterpreterOp::LoopStart(2),
+        InterpreterOp::CompoundOp(Zero),
+        InterpreterOp::LoopEnd(2),
+        InterpreterOp::IfEnd(4),
+        InterpreterOp::CompoundOp(BasicOp(Shift(1))),
+    ];
+    let block = Block::from_ops(&program).unwrap();
+
+    assert!(matches!(block.nodes[0], Node::If(_)));
+    assert_eq!(block.to_ops(), program);
+}
+
+#[test]
+fn unmatched_loops_are_rejected() {
+    let programs = [
+        vec![InterpreterOp::LoopStart(3), InterpreterOp::CompoundOp(Zero)],
+        vec![InterpreterOp::CompoundOp(Zero), InterpreterOp::LoopEnd(1)],
+        vec![InterpreterOp::IfStart(1), InterpreterOp::LoopEnd(1)],
+        vec![
+            InterpreterOp::LoopStart(2),
+            InterpreterOp::CompoundOp(Zero),
+            InterpreterOp::LoopEnd(1),
+        ],
+    ];
+
+    for program in programs {
+        assert!(Block::from_ops(&program).is_err(), "{:?}", program);
+    }
+}
+
+#[test]
+fn loops_know_how_their_body_moves_the_pointer() {
+    let program = ops(vec![
+        // Balanced, so it runs on the same cell every time
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(2)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-2)),
+        BasicOp(LoopEnd),
+        // Moves one cell each time around
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        // Holds a loop that moves the pointer an unknown distance
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(2)),
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        // Scans an unknown distance
+        BasicOp(LoopStart),
+        ScanRight(2),
+        BasicOp(LoopEnd),
+    ]);
+    let block = Block::from_ops(&program).unwrap();
+
+    let balanced = loop_at(&block, 0);
+    assert_eq!((balanced.shift, balanced.balanced), (Some(0), true));
+
+    let stride = loop_at(&block, 1);
+    assert_eq!((stride.shift, stride.balanced), (Some(1), false));
+
+    let outer = loop_at(&block, 2);
+    assert_eq!((outer.shift, outer.balanced), (None, false));
+    assert_eq!(loop_at(&outer.body, 1).shift, Some(2));
+
+    let dynamic = loop_at(&block, 3);
+    assert_eq!((dynamic.shift, dynamic.balanced), (None, false));
+
+    // Only the balanced loop leaves the pointer somewhere known
+    assert_eq!(Block::from_ops(&program[..6]).unwrap().shift(), Some(0));
+    assert_eq!(block.shift(), None);
+}
+
+#[test]
+fn loops_know_whether_their_body_does_io() {
+    let program = ops(vec![
+        BasicOp(LoopStart),
+        BasicOp(LoopStart),
+        BasicOp(Output(1)),
+        Zero,
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(LoopStart),
+        MoveAdd(1),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(LoopStart),
+        OutputAt(1, 1),
+        BasicOp(LoopEnd),
+    ]);
+    let block = Block::from_ops(&program).unwrap();
+
+    assert!(loop_at(&block, 0).io);
+    assert!(!loop_at(&block, 1).io);
+    assert!(loop_at(&block, 2).io);
+    assert!(block.does_io());
+    assert!(!Block::from_ops(&program[6..10]).unwrap().does_io());
+}
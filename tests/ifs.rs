@@ -0,0 +1,129 @@
+//! Checks which loops are turned into ifs, which only test their cell on the way in
+
+use blazing_fast_interpreter::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    ifs::ifs,
+    interpreter::{Interpreter, RuntimeOptions},
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+};
+
+use BasicOp::*;
+use CompoundOp::*;
+
+fn ops(compound_ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in compound_ops {
+        acc.feed(op).unwrap();
+    }
+
+    acc.view().unwrap().clone()
+}
+
+/// The ops with the loop around `body`
+fn looped(body: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut program = vec![BasicOp(LoopStart)];
+    program.extend(body);
+    program.push(BasicOp(LoopEnd));
+    ops(program)
+}
+
+/// The ops with an if around `body`, as the flat ops mark them
+fn iffed(body: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut program = looped(body);
+    let end = program.len() - 1;
+    program[0] = InterpreterOp::IfStart(end);
+    program[end] = InterpreterOp::IfEnd(end);
+    program
+}
+
+#[test]
+fn loops_that_zero_their_cell_become_ifs() {
+    let bodies = [
+        // `[>+<[-]]`
+        vec![BasicOp(Shift(1)), BasicOp(ChangeBy(1)), BasicOp(Shift(-1)), Zero],
+        // Zeroing first, then changing other cells
+        vec![Zero, BasicOp(Shift(1)), BasicOp(Output(1)), BasicOp(Shift(-1))],
+        vec![MoveAdd2(1, 2)],
+        vec![BasicOp(Output(1)), SetAt(0, 0)],
+        vec![BasicOp(Shift(-2)), ZeroAdvance(3), BasicOp(Shift(-1))],
+    ];
+
+    for body in bodies {
+        assert_eq!(ifs(&looped(body.clone())).unwrap(), iffed(body.clone()), "{:?}", body);
+    }
+}
+
+#[test]
+fn loops_ending_with_a_loop_on_their_cell_become_ifs() {
+    let inner = looped(vec![BasicOp(Output(1)), BasicOp(ChangeBy(u8::MAX))]);
+    let distance = inner.len() + 1;
+    let mut program = vec![InterpreterOp::LoopStart(distance)];
+    program.extend(inner.iter().cloned());
+    program.push(InterpreterOp::LoopEnd(distance));
+
+    let mut expected = vec![InterpreterOp::IfStart(distance)];
+    expected.extend(inner);
+    expected.push(InterpreterOp::IfEnd(distance));
+
+    assert_eq!(ifs(&program).unwrap(), expected);
+}
+
+#[test]
+fn loops_that_may_run_again_stay_loops() {
+    let bodies = [
+        // Changes the cell after zeroing it
+        vec![Zero, BasicOp(ChangeBy(1))],
+        // Zeroes a different cell
+        vec![BasicOp(Shift(1)), Zero, BasicOp(Shift(-1))],
+        // Not balanced, so it tests a different cell each time
+        vec![Zero, BasicOp(Shift(1))],
+        // Reads into the cell after zeroing it
+        vec![Zero, BasicOp(Input(1))],
+        // Moves the pointer by a distance read from the tape
+        vec![Zero, ScanRight(1), ScanLeft(1)],
+    ];
+
+    for body in bodies {
+        let program = looped(body.clone());
+        assert_eq!(ifs(&program).unwrap(), program, "{:?}", body);
+    }
+}
+
+#[test]
+fn ifs_run_like_the_loops_they_replace() {
+    let program = ops(vec![
+        BasicOp(LoopStart),
+        BasicOp(Output(1)),
+        MoveAdd(2),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(3)),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(1)),
+        BasicOp(Output(1)),
+    ]);
+    let converted = ifs(&program).unwrap();
+
+    assert!(converted.iter().any(|op| matches!(op, InterpreterOp::IfStart(_))));
+
+    for value in [0, 1, 200] {
+        let run = |ops: &[InterpreterOp]| {
+            let mut cells = vec![0; 16];
+            cells[4] = value;
+            let mut output = Vec::new();
+            let end =
+                unsafe { Interpreter::new(ops, RuntimeOptions::default()).run::<true>(&mut cells, 4, &mut output) };
+            (cells, end.unwrap(), output)
+        };
+
+        assert_eq!(run(&converted), run(&program));
+    }
+}
@@ -0,0 +1,108 @@
+//! Checks how compiled code stops running, which the JIT reports back instead of writing anywhere
+
+#![cfg(all(target_arch = "x86_64", target_os = "linux"))]
+
+use blazing_fast_interpreter::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::RuntimeOptions,
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+    jit::Jit,
+};
+
+fn ops(compound_ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in compound_ops {
+        acc.feed(op).unwrap();
+    }
+
+    acc.view().unwrap().clone()
+}
+
+fn run(compound_ops: Vec<CompoundOp>, options: RuntimeOptions) -> anyhow::Result<i32> {
+    Jit::new(&ops(compound_ops), options)?.run()
+}
+
+fn checked() -> RuntimeOptions {
+    RuntimeOptions {
+        bounds_checks: true,
+        ..RuntimeOptions::default()
+    }
+}
+
+#[test]
+fn finishing_exits_with_zero() {
+    let program = vec![
+        CompoundOp::Set(3),
+        CompoundOp::BasicOp(BasicOp::LoopStart),
+        CompoundOp::BasicOp(BasicOp::ChangeBy(u8::MAX)),
+        CompoundOp::BasicOp(BasicOp::Shift(1)),
+        CompoundOp::BasicOp(BasicOp::ChangeBy(2)),
+        CompoundOp::BasicOp(BasicOp::Shift(-1)),
+        CompoundOp::BasicOp(BasicOp::LoopEnd),
+    ];
+
+    assert_eq!(run(program, checked()).unwrap(), 0);
+}
+
+#[test]
+fn panics_exit_with_the_panic_code() {
+    let program = vec![CompoundOp::BasicOp(BasicOp::Shift(4)), CompoundOp::Panic(7)];
+
+    assert_eq!(run(program, RuntimeOptions::default()).unwrap(), 7);
+}
+
+#[test]
+fn leaving_the_tape_is_an_error_with_bounds_checks() {
+    let left = vec![CompoundOp::BasicOp(BasicOp::Shift(-1)), CompoundOp::Zero];
+    let right = vec![
+        CompoundOp::BasicOp(BasicOp::Shift(4)),
+        CompoundOp::BasicOp(BasicOp::ChangeBy(1)),
+    ];
+    let options = RuntimeOptions {
+        tape_size: 4,
+        ..checked()
+    };
+
+    for program in [left, right] {
+        let error = run(program, options).unwrap_err().to_string();
+        assert!(error.contains("Out of bounds"), "{}", error);
+    }
+}
+
+#[test]
+fn scans_that_find_no_zero_cell_leave_the_tape() {
+    let program = vec![
+        CompoundOp::Set(1),
+        CompoundOp::BasicOp(BasicOp::Shift(1)),
+        CompoundOp::Set(1),
+        CompoundOp::ScanLeft(1),
+    ];
+    let options = RuntimeOptions {
+        tape_size: 8,
+        ..checked()
+    };
+
+    assert!(run(program, options).is_err());
+}
+
+#[test]
+fn the_tape_origin_is_where_the_program_starts() {
+    let program = vec![CompoundOp::BasicOp(BasicOp::Shift(-2)), CompoundOp::Set(1)];
+    let options = RuntimeOptions {
+        tape_size: 4,
+        tape_origin: 2,
+        ..checked()
+    };
+
+    assert_eq!(run(program.clone(), options).unwrap(), 0);
+    assert!(run(
+        program,
+        RuntimeOptions {
+            tape_origin: 1,
+            ..options
+        }
+    )
+    .is_err());
+}
@@ -0,0 +1,110 @@
+//! Checks what the known zero pass removes, and that it leaves accesses to cells off the tape alone
+
+use blazing_fast_interpreter::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{Interpreter, RuntimeOptions},
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+    known_zero::known_zero,
+};
+
+use BasicOp::*;
+use CompoundOp::*;
+
+fn ops(compound_ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in compound_ops {
+        acc.feed(op).unwrap();
+    }
+
+    acc.view().unwrap().clone()
+}
+
+fn simplified(compound_ops: Vec<CompoundOp>, options: RuntimeOptions) -> Vec<InterpreterOp> {
+    known_zero(&ops(compound_ops), options).unwrap()
+}
+
+fn small_tape() -> RuntimeOptions {
+    RuntimeOptions {
+        bounds_checks: true,
+        tape_size: 4,
+        tape_origin: 1,
+        ..RuntimeOptions::default()
+    }
+}
+
+#[test]
+fn work_on_zero_cells_is_removed() {
+    let program = vec![
+        BasicOp(LoopStart),
+        BasicOp(Output(1)),
+        BasicOp(LoopEnd),
+        Zero,
+        BasicOp(ChangeBy(5)),
+        BasicOp(Shift(2)),
+        MoveAdd(-1),
+        ChangeByAt(1, 3),
+    ];
+
+    assert_eq!(
+        simplified(program, RuntimeOptions::default()),
+        ops(vec![Set(5), BasicOp(Shift(2)), SetAt(1, 3)])
+    );
+}
+
+#[test]
+fn only_the_current_cell_is_known_after_a_loop() {
+    let program = vec![
+        BasicOp(Input(1)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(1)),
+        Zero,
+    ];
+
+    let kept = simplified(program, RuntimeOptions::default());
+
+    assert_eq!(kept[kept.len() - 3..], ops(vec![Set(2), BasicOp(Shift(1)), Zero])[..]);
+}
+
+#[test]
+fn cells_off_the_tape_are_not_known_to_be_zero() {
+    let left = vec![
+        BasicOp(Shift(-2)),
+        BasicOp(LoopStart),
+        BasicOp(Output(1)),
+        BasicOp(LoopEnd),
+    ];
+    let right = vec![BasicOp(Shift(3)), BasicOp(ChangeBy(1))];
+    let at = vec![ChangeByAt(-2, 1)];
+
+    for program in [left, right, at] {
+        let kept = simplified(program.clone(), small_tape());
+        assert_eq!(kept, ops(program));
+
+        let mut cells = vec![0; 4];
+        let run = unsafe { Interpreter::new(&kept, small_tape()).run::<true>(&mut cells, 1, &mut Vec::new()) };
+        assert!(run.is_err());
+    }
+}
+
+#[test]
+fn cells_at_the_ends_of_the_tape_are_known_to_be_zero() {
+    let program = vec![
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(1)),
+    ];
+
+    assert_eq!(
+        simplified(program, small_tape()),
+        ops(vec![BasicOp(Shift(-1)), Set(1), BasicOp(Shift(3)), Set(1)])
+    );
+}
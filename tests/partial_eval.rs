@@ -0,0 +1,117 @@
+//! Checks where running a program ahead of time stops and how the program resumes from there
+
+use blazing_fast_interpreter::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::RuntimeOptions,
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+    partial_eval::partial_eval,
+};
+
+use BasicOp::*;
+use CompoundOp::*;
+
+fn ops(compound_ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in compound_ops {
+        acc.feed(op).unwrap();
+    }
+
+    acc.view().unwrap().clone()
+}
+
+fn evaluated(compound_ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    partial_eval(&ops(compound_ops), RuntimeOptions::default()).unwrap()
+}
+
+#[test]
+fn programs_without_input_become_their_output() {
+    let program = vec![
+        Set(3),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(b'a')),
+        BasicOp(Output(1)),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+    ];
+
+    assert_eq!(
+        evaluated(program),
+        ops(vec![PrintStatic(vec![b'a', b'a' * 2, b'a'.wrapping_mul(3)])])
+    );
+}
+
+#[test]
+fn evaluation_stops_at_the_first_input() {
+    let program = vec![
+        BasicOp(ChangeBy(3)),
+        BasicOp(Output(1)),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Input(1)),
+        BasicOp(Output(1)),
+    ];
+
+    assert_eq!(
+        evaluated(program),
+        ops(vec![
+            PrintStatic(vec![3]),
+            SetCells(0, vec![3, 2]),
+            BasicOp(Shift(1)),
+            BasicOp(Input(1)),
+            BasicOp(Output(1)),
+        ])
+    );
+}
+
+#[test]
+fn input_inside_a_loop_resumes_with_the_rest_of_its_body() {
+    let body = [
+        BasicOp(Shift(1)),
+        BasicOp(Input(1)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+    ];
+    let whole_loop: Vec<CompoundOp> = std::iter::once(BasicOp(LoopStart))
+        .chain(body.iter().cloned())
+        .chain(std::iter::once(BasicOp(LoopEnd)))
+        .collect();
+    let program: Vec<CompoundOp> = std::iter::once(Set(2)).chain(whole_loop.iter().cloned()).collect();
+
+    let mut expected = vec![SetCells(0, vec![2]), BasicOp(Shift(1))];
+    expected.extend(body[1..].iter().cloned());
+    expected.extend(whole_loop);
+
+    assert_eq!(evaluated(program), ops(expected));
+}
+
+#[test]
+fn endless_programs_stop_after_the_step_bound() {
+    let whole_loop = vec![
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(-1)),
+        BasicOp(LoopEnd),
+    ];
+    let program: Vec<CompoundOp> = std::iter::once(Set(1)).chain(whole_loop.iter().cloned()).collect();
+    let resumed = evaluated(program);
+
+    // Wherever in the body the bound is reached, the loop follows whole, so it can carry on forever
+    assert!(
+        matches!(&resumed[0], InterpreterOp::CompoundOp(SetCells(0, cells)) if cells[0] == 1),
+        "{:?}",
+        resumed
+    );
+    assert_eq!(resumed[resumed.len() - 5..], ops(whole_loop)[..]);
+}
+
+#[test]
+fn programs_reading_input_first_are_left_alone() {
+    let program = vec![BasicOp(Input(1)), BasicOp(ChangeBy(1)), BasicOp(Output(1))];
+
+    assert_eq!(evaluated(program.clone()), ops(program));
+}
@@ -0,0 +1,70 @@
+//! Loads pattern files, checking that only patterns equivalent to their code are accepted
+
+use blazing_fast_interpreter::{
+    compound_op::CompoundOp,
+    interpreter_op::InterpreterOp,
+    parser::Parser,
+    passes::Passes,
+    patterns::{self, Pattern},
+};
+
+fn load(source: &str) -> anyhow::Result<Vec<Pattern>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("patterns.txt");
+    std::fs::write(&path, source)?;
+    patterns::load(path.to_str().unwrap(), &Passes::default())
+}
+
+fn load_err(source: &str) -> String {
+    load(source).unwrap_err().to_string()
+}
+
+#[test]
+fn equivalent_patterns_are_applied() {
+    let patterns = load("# Doubles [0] into [1]\n\ndouble: [->++<] => Affine([1] = [1] + 2*[0], [0] = 0)\n").unwrap();
+
+    assert_eq!(patterns.len(), 1);
+    assert_eq!(patterns[0].name, "double");
+    assert!(matches!(patterns[0].replacement[..], [CompoundOp::Affine(_)]));
+
+    let affine = InterpreterOp::CompoundOp(patterns[0].replacement[0].clone());
+    let mut parser = Parser::new(None, None, Passes::default(), patterns).unwrap();
+
+    for byte in ",>+<[->++<]>.".bytes() {
+        parser.feed(byte).unwrap();
+    }
+
+    parser.flush().unwrap();
+    assert!(parser.view().unwrap().contains(&affine));
+}
+
+#[test]
+fn patterns_that_change_other_cells_are_rejected() {
+    let error = load_err(concat!(
+        "double: [->++<] => Affine([1] = [1] + 2*[0], [0] = 0)\n",
+        "triple: [->+++<] => Affine([1] = [1] + 2*[0], [0] = 0)\n",
+    ));
+
+    assert!(error.contains(":2:"), "{}", error);
+    assert!(error.contains("Pattern triple leaves [1]"), "{}", error);
+}
+
+#[test]
+fn patterns_that_move_the_pointer_elsewhere_are_rejected() {
+    let error = load_err("clear: [-]> => Zero");
+
+    assert!(error.contains("leaves the pointer at 0 instead of 1"), "{}", error);
+}
+
+#[test]
+fn malformed_patterns_are_rejected() {
+    for (source, message) in [
+        ("no ops here", "Expected `name: code => ops`"),
+        ("read: , => Zero", "can't read or write"),
+        ("unbalanced: [- => Zero", "'[' is missing match"),
+        ("unknown: [-] => Clear", "Unknown op Clear"),
+    ] {
+        let error = load_err(source);
+        assert!(error.contains(message), "{}", error);
+    }
+}
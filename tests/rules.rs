@@ -0,0 +1,820 @@
+//! Checks every rule against the interpreter by expanding the ops it matches back into basic ops and running
+//! them and their rewrite on random tapes, finding what each rule needs of the tape for the two to agree
+
+use blazing_fast_interpreter::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    expand::{self, instantiate},
+    interpreter::{Interpreter, RuntimeOptions},
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+    rules::{self, Rule},
+};
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+const TAPE_SIZE: usize = 1 << 14;
+const START: usize = TAPE_SIZE / 2;
+/// Cells either side of the pointer that are given random values
+const SPREAD: i64 = 96;
+/// Tapes each rule and each candidate set of preconditions is checked on
+const TRIALS: usize = 256;
+/// Runs of the matched code taking longer than this are treated as never finishing
+const MAX_STEPS: usize = 1 << 24;
+
+thread_local! {
+    /// Whether the rewritten ops are running, whose panics from leaving the tape aren't reported
+    static RUNNING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Values of the variables in the rules' patterns, satisfying their guards and leaving extra shifts and zeroing
+/// behind where the rule allows for it
+const BINDINGS: &[(&str, &[(&str, i64)])] = &[
+    ("panic_loop", &[("value", 3)]),
+    ("scan_right", &[("stride", 2)]),
+    ("scan_left", &[("stride", -3)]),
+    ("set", &[("value", 7)]),
+    ("move_add", &[("toward_amount", 3), ("back_amount", -3)]),
+    ("move_add_reversed", &[("toward_amount", -2), ("back_amount", 2)]),
+    (
+        "move_add_2",
+        &[("toward_amount1", 1), ("toward_amount2", 2), ("back_amount", -3)],
+    ),
+    ("div_mod", &[("neg_5_plus_extra", -6)]),
+    ("print_static", &[("initial_letter", 65), ("letter_count", 2)]),
+    (
+        "print_static_continuation",
+        &[("string", 65), ("letter_change_amount", 1), ("letter_count", 3)],
+    ),
+    ("move_cell_dynamic_u8", &[("neg_offset", -3), ("neg_2_plus_extra", -3)]),
+    (
+        "copy_cell_dynamic_u8",
+        &[
+            ("neg_2_plus_neg_offset", -7),
+            ("move_offset", 5),
+            ("pos_2_plus_pos_offset", 7),
+            ("shift_pos_2_plus_pos_offset", 7),
+            ("move_neg_2_plus_neg_offset", -7),
+        ],
+    ),
+    (
+        "move_cell_dynamic_u16",
+        &[
+            ("six_plus_extra", 7),
+            ("neg_offset_and_2", -3),
+            ("neg_three_plus_extra", -4),
+        ],
+    ),
+    (
+        "move_cell_dynamic_u32",
+        &[
+            ("amount_plus_11", 12),
+            ("negative_offset_plus_sizeof_index_which_is_4", -5),
+            ("extra_plus_negative_seven", -8),
+        ],
+    ),
+    (
+        "copy_cell_dynamic_u32",
+        &[
+            ("seven_plus_extra", 8),
+            ("neg_offset", -2),
+            ("pos_offset", 2),
+            ("neg_offset_and_1", -3),
+            ("pos_offset_and_1", 3),
+            ("neg_offset_and_1_second", -3),
+            ("negative_3_plus_extra", -4),
+        ],
+    ),
+    (
+        "move_cells_static_reverse",
+        &[("offset", -4), ("offset_again", -4), ("negative_1_plus_extra", -2)],
+    ),
+    (
+        "move_cells_static_reverse_extend",
+        &[
+            ("offset", -4),
+            ("count", 2),
+            ("offset_again", -4),
+            ("negative_1_plus_extra", -1),
+        ],
+    ),
+    (
+        "move_set",
+        &[
+            ("toward_amount_plus_extra", 5),
+            ("back_amount", -3),
+            ("move_add_toward_amount", 3),
+        ],
+    ),
+    (
+        "dupe",
+        &[
+            ("advance_amount", 2),
+            ("toward_shift", 1),
+            ("offset", -2),
+            ("offset_plus_1", -1),
+            ("back_shift", -1),
+            ("return_shift", 1),
+        ],
+    ),
+    ("less_than", &[("zero_advance_amount", 2)]),
+    ("greater_than", &[("zero_advance_amount", 2)]),
+    (
+        "add_u32",
+        &[("three_plus_extra_advance", 4), ("neg_twelve_plus_extra_shift", -13)],
+    ),
+    (
+        "mul_u32",
+        &[("three_plus_extra_advance", 3), ("neg_44_plus_extra_shift", -44)],
+    ),
+    (
+        "sub_u32",
+        &[("six_plus_extra_shift", 7), ("neg_five_plus_extra_shift", -6)],
+    ),
+    (
+        "less_than_u32",
+        &[("six_plus_extra_shift", 7), ("neg_five_plus_extra_shift", -6)],
+    ),
+    (
+        "equals_u32",
+        &[("neg_four_plus_extra_shift", -3), ("neg_five_plus_extra_shift", -6)],
+    ),
+    (
+        "and_u32",
+        &[("ten_plus_extra_shift", 11), ("neg_fifteen_plus_extra_shift", -16)],
+    ),
+    (
+        "or_u32",
+        &[("ten_plus_extra_shift", 11), ("neg_fifteen_plus_extra_shift", -16)],
+    ),
+    (
+        "xor_u32",
+        &[("ten_plus_extra_shift", 11), ("neg_fifteen_plus_extra_shift", -16)],
+    ),
+    (
+        "shift_left_u32",
+        &[("neg_three_plus_extra_shift", -2), ("neg_one_plus_extra_shift", -2)],
+    ),
+    (
+        "shift_right_u32",
+        &[("neg_three_plus_extra_shift", -2), ("neg_one_plus_extra_shift", -2)],
+    ),
+    (
+        "div_mod_u32",
+        &[
+            ("twenty_three_plus_extra_shift", 24),
+            ("neg_eight_plus_extra_shift", -9),
+        ],
+    ),
+];
+
+/// What the rules that don't hold on every tape need of the cells around the pointer, with the bindings above,
+/// as found by `find_preconditions`
+const EXPECTED: &[Expected] = &[
+    Expected {
+        name: "less_than_equal",
+        zero: &[],
+        non_zero: &[],
+        dead: &[-1..=-1],
+    },
+    Expected {
+        name: "bit_and",
+        zero: &[],
+        non_zero: &[],
+        dead: &[-5..=2],
+    },
+    Expected {
+        name: "bit_neg",
+        zero: &[1..=1],
+        non_zero: &[],
+        dead: &[],
+    },
+    Expected {
+        name: "div_mod",
+        zero: &[],
+        non_zero: &[-1..=-1],
+        dead: &[],
+    },
+    Expected {
+        name: "move_cell_dynamic_u8",
+        zero: &[-1..=-1],
+        non_zero: &[],
+        dead: &[0..=2],
+    },
+    Expected {
+        name: "copy_cell_dynamic_u8",
+        zero: &[],
+        non_zero: &[],
+        dead: &[-1..=96],
+    },
+    Expected {
+        name: "move_cell_dynamic_u16",
+        zero: &[-1..=-1],
+        non_zero: &[],
+        dead: &[1..=7],
+    },
+    Expected {
+        name: "move_cell_dynamic_u32",
+        zero: &[-3..=-3],
+        non_zero: &[],
+        dead: &[1..=12],
+    },
+    Expected {
+        name: "copy_cell_dynamic_u32",
+        zero: &[],
+        non_zero: &[],
+        dead: &[-3..=-2, 1..=96],
+    },
+    Expected {
+        name: "add_u32",
+        zero: &[],
+        non_zero: &[],
+        dead: &[-3..=39],
+    },
+    Expected {
+        name: "mul_u32",
+        zero: &[],
+        non_zero: &[],
+        dead: &[-4..=87],
+    },
+    Expected {
+        name: "sub_u32",
+        zero: &[1..=7],
+        non_zero: &[],
+        dead: &[],
+    },
+    Expected {
+        name: "less_than_u32",
+        zero: &[1..=5, 7..=7],
+        non_zero: &[],
+        dead: &[6..=6],
+    },
+    Expected {
+        name: "equals_u32",
+        zero: &[1..=1],
+        non_zero: &[],
+        dead: &[],
+    },
+    Expected {
+        name: "and_u32",
+        zero: &[1..=11],
+        non_zero: &[],
+        dead: &[],
+    },
+    Expected {
+        name: "or_u32",
+        zero: &[1..=11],
+        non_zero: &[],
+        dead: &[],
+    },
+    Expected {
+        name: "xor_u32",
+        zero: &[1..=11],
+        non_zero: &[],
+        dead: &[],
+    },
+    Expected {
+        name: "shift_left_u32",
+        zero: &[1..=1, 3..=3, 5..=5, 7..=7],
+        non_zero: &[],
+        dead: &[2..=2, 4..=4, 6..=6],
+    },
+    Expected {
+        name: "shift_right_u32",
+        zero: &[1..=1, 3..=3, 5..=5, 7..=7],
+        non_zero: &[],
+        dead: &[2..=2, 4..=4, 6..=6],
+    },
+    Expected {
+        name: "div_mod_u32",
+        zero: &[1..=8, 24..=34],
+        non_zero: &[],
+        dead: &[9..=22],
+    },
+];
+
+struct Expected {
+    name: &'static str,
+    zero: &'static [RangeInclusive<i64>],
+    non_zero: &'static [RangeInclusive<i64>],
+    /// Cells the rewrite leaves holding something else than the matched code does, which the program mustn't
+    /// read afterwards
+    dead: &'static [RangeInclusive<i64>],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Value {
+    Any,
+    Zero,
+    NonZero,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Condition {
+    value: Value,
+    dead: bool,
+}
+
+type Conditions = BTreeMap<i64, Condition>;
+
+/// Checks each rule holds given its preconditions, and that it doesn't once any one of them is loosened
+#[test]
+fn rules_match_the_interpreter() {
+    let failures: Vec<String> = each_rule(|rule| {
+        let (literal, rewritten) = versions(rule)?;
+        let conditions = expected(rule.name);
+
+        check(&literal, &rewritten, &conditions, TRIALS)
+            .map_err(|error| format!("differs even with its preconditions, {}", error))?;
+
+        for (&offset, condition) in &conditions {
+            for loosened in looser(*condition) {
+                let mut conditions = conditions.clone();
+                conditions.insert(offset, loosened);
+
+                if check(&literal, &rewritten, &conditions, TRIALS).is_ok() {
+                    return Err(format!("still holds with cell {} only {:?}", offset, loosened));
+                }
+            }
+        }
+
+        Ok(())
+    })
+    .into_iter()
+    .filter_map(|(rule, result)| result.err().map(|error| format!("{} {}", rule.name, error)))
+    .collect();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+/// Searches for the preconditions of every rule, printing them in the form of [`EXPECTED`] if they've changed
+///
+/// This takes a while, run it with `cargo test --test rules -- --ignored` after adding or changing a
+/// rule.
+#[test]
+#[ignore]
+fn find_preconditions() {
+    let mut changed = false;
+
+    for (rule, result) in each_rule(preconditions) {
+        match result {
+            Ok(conditions) if conditions == expected(rule.name) => (),
+            Ok(conditions) => {
+                changed = true;
+
+                if !conditions.is_empty() {
+                    println!("{}", describe(rule.name, &conditions));
+                }
+            }
+            Err(error) => {
+                changed = true;
+                println!("{} {}", rule.name, error);
+            }
+        }
+    }
+
+    assert!(!changed, "the preconditions above differ from EXPECTED");
+}
+
+/// Runs the function on every rule in parallel
+fn each_rule<T: Send>(f: impl Fn(&'static Rule) -> T + Sync) -> Vec<(&'static Rule, T)> {
+    let mut names = BTreeSet::new();
+    let rules: Vec<&Rule> = rules::all()
+        .into_iter()
+        .map(|(_, rule)| rule)
+        .filter(|rule| names.insert(rule.name))
+        .collect();
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = rules
+            .into_iter()
+            .map(|rule| {
+                let f = &f;
+                scope.spawn(move || (rule, f(rule)))
+            })
+            .collect();
+
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+fn expected(name: &str) -> Conditions {
+    let mut conditions = Conditions::new();
+
+    let Some(expected) = EXPECTED.iter().find(|expected| expected.name == name) else {
+        return conditions;
+    };
+
+    for (ranges, value) in [(expected.zero, Value::Zero), (expected.non_zero, Value::NonZero)] {
+        for offset in ranges.iter().cloned().flatten() {
+            conditions.insert(offset, Condition { value, dead: false });
+        }
+    }
+
+    for offset in expected.dead.iter().cloned().flatten() {
+        conditions
+            .entry(offset)
+            .or_insert(Condition {
+                value: Value::Any,
+                dead: false,
+            })
+            .dead = true;
+    }
+
+    conditions
+}
+
+/// The conditions as an entry of [`EXPECTED`]
+fn describe(name: &str, conditions: &Conditions) -> String {
+    let ranges = |keep: &dyn Fn(&Condition) -> bool| {
+        let mut ranges: Vec<(i64, i64)> = Vec::new();
+
+        for (&offset, _) in conditions.iter().filter(|(_, condition)| keep(condition)) {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == offset => *end = offset,
+                _ => ranges.push((offset, offset)),
+            }
+        }
+
+        let ranges: Vec<String> = ranges
+            .into_iter()
+            .map(|(start, end)| format!("{}..={}", start, end))
+            .collect();
+        format!("&[{}]", ranges.join(", "))
+    };
+
+    format!(
+        "    Expected {{\n        name: {:?},\n        zero: {},\n        non_zero: {},\n        dead: {},\n    }},",
+        name,
+        ranges(&|condition| condition.value == Value::Zero),
+        ranges(&|condition| condition.value == Value::NonZero),
+        ranges(&|condition| condition.dead)
+    )
+}
+
+/// The ways a condition on a cell can be loosened, loosest first
+fn looser(condition: Condition) -> Vec<Condition> {
+    let loosest = [
+        Condition {
+            value: Value::Any,
+            dead: false,
+        },
+        Condition {
+            value: Value::Any,
+            dead: true,
+        },
+        Condition {
+            value: condition.value,
+            dead: false,
+        },
+    ];
+
+    loosest
+        .into_iter()
+        .filter(|loosened| *loosened != condition && (condition.dead || !loosened.dead))
+        .filter(|loosened| condition.value != Value::Any || loosened.value == Value::Any)
+        .collect()
+}
+
+/// The matched code expanded into basic ops and its rewrite
+fn versions(rule: &Rule) -> Result<(Literal, Vec<InterpreterOp>), String> {
+    let bindings = BINDINGS
+        .iter()
+        .find(|(name, _)| *name == rule.name)
+        .map(|(_, bindings)| bindings.to_vec())
+        .unwrap_or_default();
+
+    let mut matched = instantiate(rule.context, &bindings);
+    matched.extend(instantiate(rule.patterns, &bindings));
+
+    let rewritten = rule
+        .rewrite(&matched)
+        .ok_or_else(|| "doesn't match its own patterns".to_string())?;
+
+    Ok((
+        Literal::new(matched.iter().flat_map(expand).collect()),
+        interpreter_ops(rewritten),
+    ))
+}
+
+/// Fewest conditions on the cells the rule needs, found by starting from every cell it touches being zero and
+/// dead, or one of them non-zero instead, and loosening the condition on each cell in turn as far as it still
+/// holds
+fn preconditions(rule: &Rule) -> Result<Conditions, String> {
+    let (literal, rewritten) = versions(rule)?;
+    let mut conditions = Conditions::new();
+
+    if check(&literal, &rewritten, &conditions, TRIALS).is_ok() {
+        return Ok(conditions);
+    }
+
+    let touched = touched(&literal, &rewritten);
+    let strictest = |value| Condition { value, dead: true };
+    conditions = touched.iter().map(|&offset| (offset, strictest(Value::Zero))).collect();
+
+    if let Err(error) = check(&literal, &rewritten, &conditions, TRIALS) {
+        // Some inputs have to be non-zero, such as divisors
+        conditions = touched
+            .iter()
+            .map(|&offset| {
+                let mut conditions = conditions.clone();
+                conditions.insert(offset, strictest(Value::NonZero));
+                conditions
+            })
+            .find(|conditions| check(&literal, &rewritten, conditions, TRIALS).is_ok())
+            .ok_or_else(|| format!("differs even with every cell it touches zero, {}", error))?;
+    }
+
+    for offset in touched {
+        for loosened in looser(conditions[&offset]) {
+            let mut loosened_conditions = conditions.clone();
+            loosened_conditions.insert(offset, loosened);
+
+            if check(&literal, &rewritten, &loosened_conditions, TRIALS).is_ok() {
+                conditions = loosened_conditions;
+                break;
+            }
+        }
+    }
+
+    conditions.retain(|_, condition| condition.value != Value::Any || condition.dead);
+
+    check(&literal, &rewritten, &conditions, TRIALS)
+        .map_err(|error| format!("doesn't hold on every tape with the preconditions found, {}", error))?;
+
+    Ok(conditions)
+}
+
+/// Cells near the pointer the matched code visits or the rewrite changes
+fn touched(literal: &Literal, rewritten: &[InterpreterOp]) -> BTreeSet<i64> {
+    let mut touched = BTreeSet::new();
+    literal.run(&mut vec![0; TAPE_SIZE], Some(&mut touched));
+
+    let mut rng = Rng::new();
+
+    for _ in 0..TRIALS {
+        let initial = random_tape(&mut rng, &Conditions::new());
+        literal.run(&mut initial.clone(), Some(&mut touched));
+
+        let mut actual = initial.clone();
+
+        if run(rewritten, &mut actual).is_some() {
+            touched.extend(
+                (0..TAPE_SIZE)
+                    .filter(|&i| actual[i] != initial[i])
+                    .map(|i| i as i64 - START as i64),
+            );
+        }
+    }
+
+    touched.retain(|offset| offset.abs() <= SPREAD);
+    touched
+}
+
+/// Runs both versions on random tapes meeting the conditions, describing the first tape they disagree on
+fn check(literal: &Literal, rewritten: &[InterpreterOp], conditions: &Conditions, trials: usize) -> Result<(), String> {
+    let mut rng = Rng::new();
+    let mut agreed = 0;
+
+    for _ in 0..trials {
+        let initial = random_tape(&mut rng, conditions);
+        let mut expected = initial.clone();
+        let mut actual = initial.clone();
+
+        let Some(expected_end) = literal.run(&mut expected, None) else {
+            // A panic stands in for a loop that never ends, other rewrites may assume the code finishes
+            if panics(rewritten) {
+                agreed += 1;
+            }
+
+            continue;
+        };
+
+        let actual_end = run(rewritten, &mut actual);
+
+        for (&offset, condition) in conditions {
+            if condition.dead {
+                actual[index(offset)] = expected[index(offset)];
+            }
+        }
+
+        if actual_end.as_ref() != Some(&expected_end) || actual != expected {
+            return Err(format!(
+                "starting from {}: ended at {:?} instead of {:?}, leaving {} instead of {}",
+                cells(&initial),
+                actual_end,
+                expected_end,
+                cells(&actual),
+                cells(&expected)
+            ));
+        }
+
+        agreed += 1;
+    }
+
+    if agreed < trials / 8 {
+        return Err(format!("only finished on {} of {} tapes", agreed, trials));
+    }
+
+    Ok(())
+}
+
+fn random_tape(rng: &mut Rng, conditions: &Conditions) -> Vec<u8> {
+    let mut tape = vec![0; TAPE_SIZE];
+
+    for offset in -SPREAD..=SPREAD {
+        tape[index(offset)] = match conditions.get(&offset).map_or(Value::Any, |condition| condition.value) {
+            Value::Any => rng.cell(),
+            Value::Zero => 0,
+            Value::NonZero => rng.non_zero_cell(),
+        };
+    }
+
+    tape
+}
+
+fn index(offset: i64) -> usize {
+    (START as i64 + offset) as usize
+}
+
+/// Non-zero cells relative to the start
+fn cells(tape: &[u8]) -> String {
+    let cells: Vec<String> = tape
+        .iter()
+        .enumerate()
+        .filter(|(_, &cell)| cell != 0)
+        .map(|(i, cell)| format!("{}: {}", i as i64 - START as i64, cell))
+        .collect();
+
+    format!("[{}]", cells.join(", "))
+}
+
+fn panics(ops: &[InterpreterOp]) -> bool {
+    ops.iter()
+        .any(|op| matches!(op, InterpreterOp::CompoundOp(CompoundOp::Panic(_))))
+}
+
+/// Runs the rewritten ops from the start, returning the cell they end on relative to the start and the
+/// output, or `None` if they panic or leave the tape
+fn run(ops: &[InterpreterOp], cells: &mut [u8]) -> Option<(i64, Vec<u8>)> {
+    static QUIET_PANICS: Once = Once::new();
+
+    QUIET_PANICS.call_once(|| {
+        let report = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !RUNNING.get() {
+                report(info);
+            }
+        }));
+    });
+
+    if panics(ops) {
+        return None;
+    }
+
+    RUNNING.set(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut output = Vec::new();
+        let end = unsafe { Interpreter::new(ops, RuntimeOptions::default()).run::<true>(cells, START, &mut output) };
+        Some((end.ok()? as i64 - START as i64, output))
+    }));
+    RUNNING.set(false);
+
+    result.ok().flatten()
+}
+
+fn interpreter_ops(ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in ops {
+        acc.feed(op).unwrap();
+    }
+
+    acc.view().unwrap().clone()
+}
+
+/// Matched code as basic ops, run by itself so it's independent of the interpreter
+struct Literal {
+    ops: Vec<BasicOp>,
+    /// Index of the matching bracket of every loop start and end
+    jumps: Vec<usize>,
+}
+
+impl Literal {
+    fn new(basic_ops: Vec<BasicOp>) -> Self {
+        // Runs left behind by expanding neighbouring ops are merged so long algorithms take fewer steps
+        let mut ops: Vec<BasicOp> = Vec::new();
+
+        for op in basic_ops {
+            match (ops.last_mut(), op) {
+                (Some(BasicOp::Shift(amount)), BasicOp::Shift(more)) => *amount += more,
+                (Some(BasicOp::ChangeBy(amount)), BasicOp::ChangeBy(more)) => *amount = amount.wrapping_add(more),
+                _ => ops.push(op),
+            }
+        }
+
+        let mut jumps = vec![0; ops.len()];
+        let mut starts = Vec::new();
+
+        for (i, op) in ops.iter().enumerate() {
+            match op {
+                BasicOp::LoopStart => starts.push(i),
+                BasicOp::LoopEnd => {
+                    let start = starts.pop().expect("unbalanced loops");
+                    jumps[start] = i;
+                    jumps[i] = start;
+                }
+                _ => (),
+            }
+        }
+
+        assert!(starts.is_empty(), "unbalanced loops");
+        Self { ops, jumps }
+    }
+
+    /// Returns the cell the ops end on relative to the start and the output, or `None` if they run out of
+    /// steps or off the tape, recording the cells the pointer visits
+    fn run(&self, cells: &mut [u8], mut touched: Option<&mut BTreeSet<i64>>) -> Option<(i64, Vec<u8>)> {
+        let mut output = Vec::new();
+        let mut cell_i = START;
+        let mut instr_i = 0;
+
+        for _ in 0..MAX_STEPS {
+            if instr_i == self.ops.len() {
+                return Some((cell_i as i64 - START as i64, output));
+            }
+
+            if let Some(touched) = &mut touched {
+                touched.insert(cell_i as i64 - START as i64);
+            }
+
+            match self.ops[instr_i] {
+                BasicOp::ChangeBy(amount) => cells[cell_i] = cells[cell_i].wrapping_add(amount),
+                BasicOp::Shift(amount) => {
+                    cell_i = usize::try_from(cell_i as i64 + amount)
+                        .ok()
+                        .filter(|&cell_i| cell_i < cells.len())?
+                }
+                BasicOp::LoopStart if cells[cell_i] == 0 => instr_i = self.jumps[instr_i],
+                // An empty loop on a non-zero cell never ends
+                BasicOp::LoopEnd if cells[cell_i] != 0 && self.jumps[instr_i] + 1 == instr_i => return None,
+                BasicOp::LoopEnd if cells[cell_i] != 0 => instr_i = self.jumps[instr_i],
+                BasicOp::LoopStart | BasicOp::LoopEnd => (),
+                BasicOp::Input(_) => panic!("no rule reads input"),
+                BasicOp::Output(count) => output.extend(std::iter::repeat_n(cells[cell_i], count as usize)),
+            }
+
+            instr_i += 1;
+        }
+
+        None
+    }
+}
+
+/// Basic ops an op stands for, expanding compound ops through the rules that recognise them
+fn expand(op: &CompoundOp) -> Vec<BasicOp> {
+    match op {
+        CompoundOp::BasicOp(op) => vec![*op],
+        op => expand::expand(op)
+            .unwrap_or_else(|| panic!("no rule recognises {:?}", op))
+            .iter()
+            .flat_map(expand)
+            .collect(),
+    }
+}
+
+/// xorshift64*, so the tapes are the same on every run
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        Self(0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Mostly zero and small values, so loops counting cells down finish and indices stay on the tape
+    fn cell(&mut self) -> u8 {
+        match self.next() % 4 {
+            0 | 1 => 0,
+            2 => 1 + (self.next() % 3) as u8,
+            _ => self.next() as u8,
+        }
+    }
+
+    fn non_zero_cell(&mut self) -> u8 {
+        match self.next() % 2 {
+            0 => 1 + (self.next() % 3) as u8,
+            _ => 1 + (self.next() % 255) as u8,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Checks the interpreter's scans, which search whole blocks of cells at a time, against stepping one stride at a
+//! time
+
+use blazing_fast_interpreter::{
+    compound_op::CompoundOp,
+    interpreter::{Interpreter, RuntimeOptions},
+    interpreter_op::InterpreterOp,
+};
+
+const TAPE_SIZE: usize = 1000;
+
+/// Cell the scan stops on, or `None` if it runs off the tape
+fn stepped(cells: &[u8], from: usize, stride: i64) -> Option<usize> {
+    let mut cell_i = from as i64;
+
+    while cells.get(usize::try_from(cell_i).ok()?)? != &0 {
+        cell_i += stride;
+    }
+
+    Some(cell_i as usize)
+}
+
+fn scanned(cells: &[u8], from: usize, stride: i64) -> Option<usize> {
+    let op = if stride > 0 {
+        CompoundOp::ScanRight(stride as u64)
+    } else {
+        CompoundOp::ScanLeft(stride.unsigned_abs())
+    };
+    let ops = [InterpreterOp::CompoundOp(op)];
+
+    unsafe {
+        Interpreter::new(&ops, RuntimeOptions::default())
+            .run::<true>(&mut cells.to_vec(), from, &mut Vec::new())
+            .ok()
+    }
+}
+
+#[test]
+fn scans_stop_on_the_first_zero_cell_they_visit() {
+    // xorshift, so the tapes are the same on every run
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for trial in 0..200 {
+        // From zeros everywhere to a single zero in the whole tape
+        let sparsity = 1 + trial % 10 * 40;
+        let cells: Vec<u8> = (0..TAPE_SIZE)
+            .map(|_| if next() % sparsity == 0 { 0 } else { next() as u8 | 1 })
+            .collect();
+        let from = next() as usize % TAPE_SIZE;
+
+        for stride in [1, 2, 3, 4, 5, 7, 8, 16, 63, 64, 65] {
+            for stride in [stride, -stride] {
+                assert_eq!(
+                    scanned(&cells, from, stride),
+                    stepped(&cells, from, stride),
+                    "stride {} from {} on trial {}",
+                    stride,
+                    from,
+                    trial
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Checks that shifts are sunk to the end of each basic block without changing what the program does
+
+use blazing_fast_interpreter::{
+    basic_op::BasicOp,
+    compound_op::CompoundOp,
+    interpreter::{Interpreter, RuntimeOptions},
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+    sink_shifts::sink_shifts,
+};
+
+use BasicOp::*;
+use CompoundOp::*;
+
+fn ops(compound_ops: Vec<CompoundOp>) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+
+    for op in compound_ops {
+        acc.feed(op).unwrap();
+    }
+
+    acc.view().unwrap().clone()
+}
+
+/// Runs the ops from the middle of a tape counting up from the left, returning the tape, where the ops stopped
+/// and their output
+fn run(ops: &[InterpreterOp]) -> (Vec<u8>, usize, Vec<u8>) {
+    let mut cells: Vec<u8> = (0..64).map(|i| i as u8).collect();
+    let mut output = Vec::new();
+    let end = unsafe { Interpreter::new(ops, RuntimeOptions::default()).run::<true>(&mut cells, 32, &mut output) };
+    (cells, end.unwrap(), output)
+}
+
+#[test]
+fn straight_line_code_shifts_once() {
+    let program = ops(vec![
+        BasicOp(Shift(1)),
+        BasicOp(ChangeBy(1)),
+        BasicOp(Shift(2)),
+        Set(9),
+        BasicOp(Shift(-1)),
+        BasicOp(Output(1)),
+        MoveAdd(-2),
+    ]);
+
+    assert_eq!(
+        sink_shifts(&program).unwrap(),
+        ops(vec![
+            ChangeByAt(1, 1),
+            SetAt(3, 9),
+            OutputAt(2, 1),
+            MoveAddAt(2, 0),
+            BasicOp(Shift(2)),
+        ])
+    );
+}
+
+#[test]
+fn shifts_stay_inside_their_loop() {
+    let program = ops(vec![
+        BasicOp(Shift(2)),
+        BasicOp(LoopStart),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(3)),
+        BasicOp(Shift(1)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(-1)),
+    ]);
+
+    assert_eq!(
+        sink_shifts(&program).unwrap(),
+        ops(vec![
+            BasicOp(Shift(2)),
+            BasicOp(LoopStart),
+            BasicOp(ChangeBy(u8::MAX)),
+            ChangeByAt(-1, 3),
+            BasicOp(LoopEnd),
+            BasicOp(Shift(-1)),
+        ])
+    );
+}
+
+#[test]
+fn ops_without_an_offset_form_get_their_shifts_first() {
+    let program = ops(vec![
+        BasicOp(Shift(3)),
+        BasicOp(ChangeBy(2)),
+        BasicOp(Shift(-1)),
+        ScanLeft(1),
+        BasicOp(Shift(1)),
+        Zero,
+    ]);
+
+    assert_eq!(
+        sink_shifts(&program).unwrap(),
+        ops(vec![
+            ChangeByAt(3, 2),
+            BasicOp(Shift(2)),
+            ScanLeft(1),
+            SetAt(1, 0),
+            BasicOp(Shift(1)),
+        ])
+    );
+}
+
+#[test]
+fn sinking_leaves_the_same_tape() {
+    let program = ops(vec![
+        BasicOp(Shift(-3)),
+        MulAddMany(vec![(1, 3), (4, 255)]),
+        BasicOp(Shift(2)),
+        BasicOp(LoopStart),
+        BasicOp(Shift(1)),
+        BasicOp(Output(1)),
+        MoveAdd2(-1, 2),
+        BasicOp(Shift(-1)),
+        BasicOp(ChangeBy(u8::MAX)),
+        BasicOp(LoopEnd),
+        BasicOp(Shift(5)),
+        MoveSet(-4),
+        SetCells(-2, vec![7, 0, 1]),
+        BasicOp(Shift(-1)),
+        BasicOp(Output(2)),
+    ]);
+
+    assert_eq!(run(&sink_shifts(&program).unwrap()), run(&program));
+}
@@ -0,0 +1,63 @@
+//! Checks that optimizing the whole program at once recognises everything the window does, and more
+
+use blazing_fast_interpreter::{
+    compound_op::CompoundOp,
+    interpreter_op::{InterpreterOp, InterpreterOpAcc},
+    parser::Parser,
+    passes::Passes,
+    transpile_bf::transpile_bf,
+};
+
+fn parse(source: &str, whole_program: bool) -> Vec<InterpreterOp> {
+    let mut parser = Parser::new(None, None, Passes::default(), Vec::new()).unwrap();
+
+    if whole_program {
+        parser.whole_program();
+    }
+
+    for byte in source.bytes() {
+        parser.feed(byte).unwrap();
+    }
+
+    parser.flush().unwrap();
+    parser.view().unwrap().clone()
+}
+
+fn interpreter_ops(op: CompoundOp) -> Vec<InterpreterOp> {
+    let mut acc = InterpreterOpAcc::new();
+    acc.feed(op).unwrap();
+    acc.view().unwrap().clone()
+}
+
+fn to_bf(ops: &[InterpreterOp]) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("program.b");
+    transpile_bf(ops.iter(), path.to_str().unwrap()).unwrap();
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn rules_match_the_same_ops() {
+    use CompoundOp::*;
+
+    for op in [Dupe(3), WellBehavedDivMod(5), MoveCellDynamicU16(3), MulU32, DivModU32] {
+        let ops = interpreter_ops(op.clone());
+        assert_eq!(parse(&to_bf(&ops), true), ops, "{:?}", op);
+    }
+}
+
+#[test]
+fn linear_loops_longer_than_the_window_collapse() {
+    let source = format!("[-{}{}]", ">+".repeat(1500), "<".repeat(1500));
+    let targets = (1..=1500).map(|offset| (offset, 1)).collect();
+
+    assert_eq!(parse(&source, true), interpreter_ops(CompoundOp::MulAddMany(targets)));
+    assert_ne!(parse(&source, false), parse(&source, true));
+}
+
+#[test]
+fn programs_longer_than_the_window_optimize_the_same() {
+    let source = ">+[->+++<]>[-<+>>++<]<[<]>.".repeat(1000);
+
+    assert_eq!(parse(&source, true), parse(&source, false));
+}